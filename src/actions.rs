@@ -1,10 +1,14 @@
 use std::io::{Error, ErrorKind};
 use std::sync::{Arc, Mutex};
-use std::net::TcpStream;
+use std::net::{TcpStream, Shutdown};
 use std::collections::HashSet;
-use tcp::receive;
-use message_parser::parse;
-use state::{State, Status, HistoryItem};
+use tcp::{receive, send};
+use message_parser::{parse, serialize};
+use state::{timestamp_now, State, Status, HistoryItem, Expansion, Edit, Navigation, ChartSource};
+use session;
+use layout::Layout;
+use config::next_theme;
+use invariants;
 
 pub enum Message {
     Empty,
@@ -12,6 +16,16 @@ pub enum Message {
     Client(Arc<Mutex<TcpStream>>),
     HistoryItem(HistoryItem),
     SelectAction((String, HashSet<usize>)),
+    ClientId(String),
+    JsonPath((String, String)),
+    InspectorPath((String, String, String)),
+    Expansion((String, String, Expansion)),
+    InspectorPage((String, String, String, usize)),
+    EditState((String, usize, Edit)),
+    Filter((String, String)),
+    Navigate((String, Navigation)),
+    Layout(Layout),
+    Rename((String, String)),
 }
 
 impl Message {
@@ -42,6 +56,76 @@ impl Message {
             _ => Err("Expected Message::SelectAction"),
         }.unwrap()
     }
+
+    pub fn expect_client_id(self) -> String {
+        match self {
+            Message::ClientId(content) => Ok(content),
+            _ => Err("Expected Message::ClientId"),
+        }.unwrap()
+    }
+
+    pub fn expect_json_path(self) -> (String, String) {
+        match self {
+            Message::JsonPath(content) => Ok(content),
+            _ => Err("Expected Message::JsonPath"),
+        }.unwrap()
+    }
+
+    pub fn expect_inspector_path(self) -> (String, String, String) {
+        match self {
+            Message::InspectorPath(content) => Ok(content),
+            _ => Err("Expected Message::InspectorPath"),
+        }.unwrap()
+    }
+
+    pub fn expect_inspector_page(self) -> (String, String, String, usize) {
+        match self {
+            Message::InspectorPage(content) => Ok(content),
+            _ => Err("Expected Message::InspectorPage"),
+        }.unwrap()
+    }
+
+    pub fn expect_edit_state(self) -> (String, usize, Edit) {
+        match self {
+            Message::EditState(content) => Ok(content),
+            _ => Err("Expected Message::EditState"),
+        }.unwrap()
+    }
+
+    pub fn expect_filter(self) -> (String, String) {
+        match self {
+            Message::Filter(content) => Ok(content),
+            _ => Err("Expected Message::Filter"),
+        }.unwrap()
+    }
+
+    pub fn expect_navigate(self) -> (String, Navigation) {
+        match self {
+            Message::Navigate(content) => Ok(content),
+            _ => Err("Expected Message::Navigate"),
+        }.unwrap()
+    }
+
+    pub fn expect_layout(self) -> Layout {
+        match self {
+            Message::Layout(content) => Ok(content),
+            _ => Err("Expected Message::Layout"),
+        }.unwrap()
+    }
+
+    pub fn expect_rename(self) -> (String, String) {
+        match self {
+            Message::Rename(content) => Ok(content),
+            _ => Err("Expected Message::Rename"),
+        }.unwrap()
+    }
+
+    pub fn expect_expansion(self) -> (String, String, Expansion) {
+        match self {
+            Message::Expansion(content) => Ok(content),
+            _ => Err("Expected Message::Expansion"),
+        }.unwrap()
+    }
 }
 
 pub struct Action {
@@ -52,23 +136,31 @@ pub struct Action {
 }
 
 pub fn run(action: Action, mut state: State) -> Result<State, Error> {
-    println!("domain: {}", action.domain);
-    println!("invocation: {}", action.invocation);
-    println!("sender: {}", action.sender);
-
     match (action.domain.as_str(), action.invocation.as_str()) {
         ("client", "add") => {
             let socket = action.message.expect_client();
-            receive(socket, state.dispatcher.clone(), action.sender.clone())?;
+            let writer = socket.lock().unwrap().try_clone();
+            let (dispatcher, sender) = (state.dispatcher.clone(), action.sender.clone());
+            let connected = writer.and_then(|writer| receive(socket, dispatcher, sender).map(|_| writer));
 
-            state.add_client(action.sender.clone());
+            match connected {
+                Ok(writer) => state.add_client(action.sender.clone(), Arc::new(Mutex::new(writer))),
+                Err(err) => state.report_error(format!("{}: failed to accept the connection: {}", action.sender, err)),
+            };
             Ok(state)
         },
         ("client", "receive") => {
             let content = action.message.expect_raw();
-            let action = parse(content, action.sender)?;
-            state.dispatcher.send(action).expect("Failed to handle client action");
+            state.record_message(timestamp_now());
 
+            match parse(content, action.sender.clone()) {
+                Ok(action) => state.dispatcher.send(action).expect("Failed to handle client action"),
+                Err(err) => state.report_error(format!("{}: {}", action.sender, err)),
+            };
+            Ok(state)
+        },
+        ("client", "disconnect") => {
+            state.disconnect_client(action.sender.clone());
             Ok(state)
         },
         ("client", "remove") => {
@@ -78,13 +170,203 @@ pub fn run(action: Action, mut state: State) -> Result<State, Error> {
         ("item", "add") => {
             let history_item = action.message.expect_history_item();
 
-            state.add_history_item(action.sender.clone(), history_item);
+            match state.status {
+                Status::Paused => (),
+                _ => state.add_history_item(action.sender.clone(), history_item),
+            };
             Ok(state)
         },
         ("application", "quit") => {
             state.status = Status::ShuttingDown;
             Ok(state)
         },
+        ("application", "request quit") => {
+            if state.unsaved && state.has_history() {
+                state.confirming_quit = true;
+            } else {
+                state.status = Status::ShuttingDown;
+            }
+            Ok(state)
+        },
+        ("application", "cancel quit") => {
+            state.confirming_quit = false;
+            Ok(state)
+        },
+        ("application", "toggle pause") => {
+            state.status = match state.status {
+                Status::Paused => Status::Running,
+                Status::Running => Status::Paused,
+                ref status => status.clone(),
+            };
+            Ok(state)
+        },
+        ("session", "save") => {
+            match session::save(&state, &state.config.session_path) {
+                Ok(_) => state.unsaved = false,
+                Err(err) => state.report_error(format!("failed to save the session: {}", err)),
+            };
+            Ok(state)
+        },
+        // Quits only once the history is safe, otherwise the quit dialog stays open with the error.
+        ("session", "save and quit") => {
+            match session::save(&state, &state.config.session_path) {
+                Ok(_) => {
+                    state.unsaved = false;
+                    state.status = Status::ShuttingDown;
+                },
+                Err(err) => state.report_error(format!("failed to save the session: {}", err)),
+            };
+            Ok(state)
+        },
+        ("tab", "select") => {
+            let client_id = action.message.expect_client_id();
+
+            state.active_client = Some(client_id);
+            state.merged_view = false;
+            Ok(state)
+        },
+        ("tab", "close") => {
+            let client_id = action.message.expect_client_id();
+
+            if let Some(connection) = state.clients.get(&client_id).and_then(|client| client.connection.clone()) {
+                connection.lock().unwrap().shutdown(Shutdown::Both).ok();
+            }
+            state.remove_client(client_id);
+            Ok(state)
+        },
+        ("tab", "clear") => {
+            let client_id = action.message.expect_client_id();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.clear();
+            }
+            Ok(state)
+        },
+        ("tab", "toggle pin") => {
+            let client_id = action.message.expect_client_id();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.pinned = !client.pinned;
+            }
+            Ok(state)
+        },
+        ("tab", "start rename") => {
+            let client_id = action.message.expect_client_id();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.rename_draft = Some(String::from(client.display_name()));
+            }
+            Ok(state)
+        },
+        ("tab", "finish rename") => {
+            let client_id = action.message.expect_client_id();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                if let Some(name) = client.rename_draft.take() {
+                    client.rename(name);
+                }
+            }
+            Ok(state)
+        },
+        ("tab", "rename") => {
+            let (client_id, name) = action.message.expect_rename();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.rename_draft = Some(name);
+            }
+            Ok(state)
+        },
+        ("merged", "show") => {
+            state.merged_view = true;
+            Ok(state)
+        },
+        ("merged", "toggle view") => {
+            state.merged_view = !state.merged_view;
+            Ok(state)
+        },
+        ("merged", "toggle client") => {
+            let client_id = action.message.expect_client_id();
+
+            if !state.merged_excluded.remove(&client_id) {
+                state.merged_excluded.insert(client_id);
+            }
+            Ok(state)
+        },
+        ("merged", "select") => {
+            let (client_id, selections) = action.message.expect_select_action();
+
+            state.merged_selection = selections.iter().next().map(|index| (client_id.clone(), *index));
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.update_selections(selections);
+            }
+            Ok(state)
+        },
+        ("stats", "toggle view") => {
+            state.stats_view = !state.stats_view;
+            Ok(state)
+        },
+        ("search", "focus") => {
+            state.search_focused = true;
+            Ok(state)
+        },
+        ("search", "blur") => {
+            state.search_focused = false;
+            Ok(state)
+        },
+        ("search", "type") => {
+            let (client_id, text) = action.message.expect_filter();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.filter.push_str(&text);
+            }
+            Ok(state)
+        },
+        ("search", "backspace") => {
+            let client_id = action.message.expect_client_id();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.filter.pop();
+            }
+            Ok(state)
+        },
+        ("action", "navigate") => {
+            let (client_id, navigation) = action.message.expect_navigate();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.navigate(navigation, state.config.group_domains);
+            }
+            Ok(state)
+        },
+        ("theme", "cycle") => {
+            state.config.theme = String::from(next_theme(&state.config.theme));
+            Ok(state)
+        },
+        ("layout", "set") => {
+            state.layout = action.message.expect_layout();
+            Ok(state)
+        },
+        ("action", "toggle run") => {
+            let (client_id, selections) = action.message.expect_select_action();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                for start in selections {
+                    client.toggle_run(start);
+                }
+            }
+            Ok(state)
+        },
+        ("action", "toggle redundant") => {
+            let client_id = action.message.expect_client_id();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.redundant_only = !client.redundant_only;
+            }
+            Ok(state)
+        },
+        ("action", "toggle grouping") => {
+            state.config.group_domains = !state.config.group_domains;
+            Ok(state)
+        },
         ("action", "select") => {
             let (client_id, selections) = action.message.expect_select_action();
 
@@ -94,13 +376,116 @@ pub fn run(action: Action, mut state: State) -> Result<State, Error> {
                 ;
             Ok(state)
         },
-        ("ping", "pong") => {
-            println!("pong");
+        ("chart", "select") => {
+            let (client_id, path) = action.message.expect_json_path();
+
+            state.clients
+                .entry(client_id)
+                .and_modify(|client| client.select_chart(ChartSource::Path(path)))
+                ;
+            Ok(state)
+        },
+        ("chart", "select size") => {
+            let client_id = action.message.expect_client_id();
 
+            state.clients
+                .entry(client_id)
+                .and_modify(|client| client.select_chart(ChartSource::Size))
+                ;
+            Ok(state)
+        },
+        ("chart", "select nodes") => {
+            let client_id = action.message.expect_client_id();
+
+            state.clients
+                .entry(client_id)
+                .and_modify(|client| client.select_chart(ChartSource::Nodes))
+                ;
+            Ok(state)
+        },
+        ("chart", "toggle axis") => {
+            let client_id = action.message.expect_client_id();
+
+            state.clients
+                .entry(client_id)
+                .and_modify(|client| client.toggle_chart_axis())
+                ;
+            Ok(state)
+        },
+        ("chart", "close") => {
+            let client_id = action.message.expect_client_id();
+
+            state.clients
+                .entry(client_id)
+                .and_modify(|client| client.close_chart())
+                ;
+            Ok(state)
+        },
+        ("inspector", "toggle") => {
+            let (client_id, scope, path) = action.message.expect_inspector_path();
+
+            state.clients
+                .entry(client_id)
+                .and_modify(|client| client.toggle_path(scope, path))
+                ;
+            Ok(state)
+        },
+        ("inspector", "page") => {
+            let (client_id, scope, path, start) = action.message.expect_inspector_page();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.set_page(scope, path, start);
+            }
+            Ok(state)
+        },
+        ("inspector", "expand") => {
+            let (client_id, scope, expansion) = action.message.expect_expansion();
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                client.expand(scope, expansion);
+            }
+            Ok(state)
+        },
+        ("state", "edit") => {
+            let (client_id, index, edit) = action.message.expect_edit_state();
+
+            let mut error = None;
+
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                match client.edited_history_item(index, &edit) {
+                    Ok(mut item) => {
+                        item.violations = invariants::check_all(&state.invariants, &item.state.id, &item.state.content);
+                        item.schema_errors = state.schemas.check(&item);
+                        client.watch_growth(&mut item, &state.config);
+                        if let Some(ref connection) = client.connection {
+                            let command = serialize(String::from("state"), String::from("set"), item.state.content.clone())?;
+                            if let Err(err) = send(connection, command) {
+                                error = Some(format!("{}: failed to send the edited state: {}", client_id, err));
+                            }
+                        }
+
+                        client.push(item);
+                        state.unsaved = true;
+                        let mut selections = HashSet::new();
+                        selections.insert(client.history_item_amount() - 1);
+                        client.update_selections(selections);
+                    },
+                    Err(err) => error = Some(format!("{}: {}", client_id, err)),
+                }
+            }
+
+            if let Some(error) = error {
+                state.report_error(error);
+            }
+            Ok(state)
+        },
+        ("ping", "pong") => {
             Ok(state)
         }
-        _ => {
-            Err(Error::new(ErrorKind::InvalidData, "domain and invocation not matched"))
+        (domain, invocation) => {
+            let error = Error::new(ErrorKind::InvalidData, format!("{}:{} is not a known action", domain, invocation));
+            state.report_error(format!("{}: {}", action.sender, error));
+            Ok(state)
         },
     }
 }
@@ -0,0 +1,25 @@
+//! Records without a window, the same as `vision_quest --headless`.
+
+extern crate vision_quest;
+
+use std::env;
+use std::process;
+use vision_quest::{cli, collector, config, headless};
+
+fn main() {
+    let args = env::args().skip(1).filter(|arg| arg != "--headless");
+    let options = cli::parse(Some(String::from("--headless")).into_iter().chain(args)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, cli::USAGE);
+        process::exit(2);
+    });
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    let config = config::load().expect("failed to load config");
+    let idle_timeout = options.idle_timeout.unwrap_or(config.idle_timeout);
+    let updates = collector::start_headless(config).expect("failed to make tcp connection");
+
+    process::exit(headless::record(updates, options, idle_timeout));
+}
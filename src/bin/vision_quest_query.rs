@@ -0,0 +1,29 @@
+//! Answers questions about a saved session without opening the inspector.
+
+extern crate vision_quest;
+
+use std::env;
+use std::io;
+use std::process;
+use vision_quest::query;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", query::USAGE);
+        return;
+    }
+
+    let stdout = io::stdout();
+    match query::run(args.into_iter(), &mut stdout.lock()) {
+        Ok(()) => (),
+        Err(ref err) if err.kind() == io::ErrorKind::InvalidInput => {
+            eprintln!("{}\n\n{}", err, query::USAGE);
+            process::exit(2);
+        },
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
+    }
+}
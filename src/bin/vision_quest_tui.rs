@@ -0,0 +1,37 @@
+//! Shows the inspector in the terminal, the same as `vision_quest --tui`. Only built for unix
+//! terminals.
+
+extern crate vision_quest;
+
+#[cfg(unix)]
+use std::env;
+use std::process;
+#[cfg(unix)]
+use vision_quest::{cli, collector, config, tui};
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("the terminal frontend needs a unix terminal");
+    process::exit(2);
+}
+
+#[cfg(unix)]
+fn main() {
+    let args = env::args().skip(1).filter(|arg| arg != "--tui");
+    let options = cli::parse(Some(String::from("--tui")).into_iter().chain(args)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, cli::USAGE);
+        process::exit(2);
+    });
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    if let Some((expected, actual)) = options.compare {
+        process::exit(tui::compare(&expected, &actual));
+    }
+
+    let config = config::load().expect("failed to load config");
+    let states = collector::start(config, false).expect("failed to make tcp connection");
+
+    tui::run(states);
+}
@@ -0,0 +1,88 @@
+use std::io::{Error, ErrorKind};
+
+pub const USAGE: &'static str = "usage: vision_quest [--tui [--compare EXPECTED ACTUAL] | --headless [--output PATH] [--idle-timeout SECONDS] [--golden PATH [--report PATH]]]
+
+  --tui                   show the inspector in the terminal instead of a window
+  --compare EXPECTED ACTUAL
+                          align the actions of two saved sessions in the terminal and show where
+                          they diverge and where their states differ, instead of recording
+  --headless              record without a window, writing every received item as json lines
+  --output PATH           where headless mode writes the items, stdout when PATH is - or left out
+  --idle-timeout SECONDS  stop headless mode after this long without a message, 0 to never stop
+  --golden PATH           compare the headless recording with the session at PATH once it stops,
+                          failing when the actions diverge or the states differ
+  --report PATH           where the comparison is written as json, stdout when PATH is - or left out
+  --help                  show this message";
+
+/// How the inspector was asked to run from the command line.
+#[derive(Default)]
+pub struct Options {
+    pub tui: bool,
+    pub headless: bool,
+    /// Where headless mode writes to, stdout when unset.
+    pub output: Option<String>,
+    /// Replaces the configured idle timeout.
+    pub idle_timeout: Option<u64>,
+    /// The session a headless recording has to match.
+    pub golden: Option<String>,
+    /// Where the comparison with the golden session is written to, stdout when unset.
+    pub report: Option<String>,
+    /// The expected and actual sessions to show the comparison of in the terminal.
+    pub compare: Option<(String, String)>,
+    pub help: bool,
+}
+
+/// Reads the options from `args`, which exclude the program name.
+pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, Error> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tui" => options.tui = true,
+            "--headless" => options.headless = true,
+            "--output" => {
+                let path = value(&mut args, &arg)?;
+                options.output = if path == "-" { None } else { Some(path) };
+            },
+            "--idle-timeout" => {
+                let seconds = value(&mut args, &arg)?;
+                let seconds = seconds.parse::<u64>()
+                    .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("{} is not a number of seconds", seconds)))?;
+                options.idle_timeout = Some(seconds);
+            },
+            "--golden" => options.golden = Some(value(&mut args, &arg)?),
+            "--report" => {
+                let path = value(&mut args, &arg)?;
+                options.report = if path == "-" { None } else { Some(path) };
+            },
+            "--compare" => {
+                let expected = value(&mut args, &arg)?;
+                options.compare = Some((expected, value(&mut args, &arg)?));
+            },
+            "--help" | "-h" => options.help = true,
+            _ => return Err(Error::new(ErrorKind::InvalidInput, format!("unknown argument {}", arg))),
+        };
+    }
+
+    if options.tui && cfg!(not(unix)) {
+        return Err(Error::new(ErrorKind::InvalidInput, "--tui needs a unix terminal"));
+    }
+    if options.tui && options.headless {
+        return Err(Error::new(ErrorKind::InvalidInput, "--tui and --headless exclude each other"));
+    }
+    if options.compare.is_some() && !options.tui {
+        return Err(Error::new(ErrorKind::InvalidInput, "--compare only works with --tui"));
+    }
+    if options.golden.is_some() && !options.headless {
+        return Err(Error::new(ErrorKind::InvalidInput, "--golden only works with --headless"));
+    }
+    if options.golden.is_some() && options.output.is_none() && options.report.is_none() {
+        return Err(Error::new(ErrorKind::InvalidInput, "--golden needs --output or --report, so the recording and the report do not both go to stdout"));
+    }
+
+    Ok(options)
+}
+
+fn value<I: Iterator<Item = String>>(args: &mut I, name: &str) -> Result<String, Error> {
+    args.next().ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("{} needs a value", name)))
+}
@@ -0,0 +1,127 @@
+//! The reporting side: what an application links in to send its actions and states to the
+//! inspector.
+//!
+//! The default `gui` feature only builds the inspector window, so applications should leave it
+//! out to keep conrod and its windowing dependencies out of their build:
+//!
+//! ```toml
+//! [dependencies]
+//! vision_quest = { version = "0.1", default-features = false }
+//! ```
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use vision_quest::client::{Report, Sender};
+//!
+//! let sender = Sender::new("127.0.0.1:7033");
+//! let report = Report::new("counter", "increment")
+//!     .amplitude(&1)
+//!     .state("Counter", &42)
+//!     .build()
+//!     .unwrap();
+//! sender.send(&report).ok();
+//! sender.flush(Duration::from_secs(1)).ok();
+//! ```
+
+mod sender;
+mod recorder;
+
+pub use self::sender::Sender;
+pub use self::recorder::Recorder;
+
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+use serde::Serialize;
+use serde_json;
+use state::{timestamp_now, HistoryAction, HistoryState, HistoryItem};
+
+/// Builds the history item reporting one action and the state it led to.
+pub struct Report {
+    domain: String,
+    invocation: String,
+    amplitude: String,
+    state_type: String,
+    state: String,
+    timestamp: Option<u64>,
+    id: Option<String>,
+    caused_by: Option<String>,
+    duration: Option<f64>,
+    error: Option<serde_json::Error>,
+}
+
+impl Report {
+    pub fn new(domain: &str, invocation: &str) -> Report {
+        Report {
+            domain: String::from(domain),
+            invocation: String::from(invocation),
+            amplitude: String::from("null"),
+            state_type: String::new(),
+            state: String::from("null"),
+            timestamp: None,
+            id: None,
+            caused_by: None,
+            duration: None,
+            error: None,
+        }
+    }
+
+    /// The data the action carries.
+    pub fn amplitude<T: Serialize + ?Sized>(mut self, amplitude: &T) -> Self {
+        match serde_json::to_string(amplitude) {
+            Ok(json) => self.amplitude = json,
+            Err(err) => self.error = self.error.or(Some(err)),
+        };
+        self
+    }
+
+    /// The state after the action, and the name of its type.
+    pub fn state<T: Serialize + ?Sized>(mut self, state_type: &str, state: &T) -> Self {
+        self.state_type = String::from(state_type);
+        match serde_json::to_string(state) {
+            Ok(json) => self.state = json,
+            Err(err) => self.error = self.error.or(Some(err)),
+        };
+        self
+    }
+
+    /// When the action happened in milliseconds since the unix epoch, now when left out.
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// An id later reports can name as their cause.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(String::from(id));
+        self
+    }
+
+    /// The id of the action that set this one off, which another process may have reported.
+    pub fn caused_by(mut self, id: &str) -> Self {
+        self.caused_by = Some(String::from(id));
+        self
+    }
+
+    /// How long handling the action took, shown next to it and summed up per action.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 / 1_000_000.0);
+        self
+    }
+
+    /// The history item to send, or the first error serializing the amplitude or state.
+    pub fn build(self) -> Result<HistoryItem, Error> {
+        if let Some(err) = self.error {
+            return Err(Error::new(ErrorKind::InvalidData, err));
+        }
+
+        let now = timestamp_now();
+        let action = HistoryAction::new(self.domain, self.invocation, self.amplitude);
+        let state = HistoryState::new(self.state_type, self.state);
+        let mut item = HistoryItem::new(action, state, Some(self.timestamp.unwrap_or(now)), now);
+        item.id = self.id;
+        item.caused_by = self.caused_by;
+        item.duration = self.duration;
+
+        Ok(item)
+    }
+}
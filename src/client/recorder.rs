@@ -0,0 +1,80 @@
+use std::any::type_name;
+use std::time::Instant;
+use serde::Serialize;
+use serde_json::{self, Value};
+use client::{Report, Sender};
+
+/// Wraps a `reducer(state, action)` function so every dispatch is reported with the state it
+/// returned.
+///
+/// The whole action is the amplitude. The invocation is the string an action serializes to, such
+/// as a unit variant, or the name passed to `dispatch_as`; anything else is reported as
+/// "dispatch", since a struct with one field looks the same as an enum variant once serialized.
+pub struct Recorder<F> {
+    reducer: F,
+    sender: Sender,
+    domain: String,
+    state_type: Option<String>,
+}
+
+impl<F> Recorder<F> {
+    pub fn new(sender: Sender, domain: &str, reducer: F) -> Recorder<F> {
+        Recorder {
+            reducer: reducer,
+            sender: sender,
+            domain: String::from(domain),
+            state_type: None,
+        }
+    }
+
+    /// The state type shown in the inspector, the rust type name when left out.
+    pub fn state_type(mut self, state_type: &str) -> Self {
+        self.state_type = Some(String::from(state_type));
+        self
+    }
+
+    /// Runs the reducer and reports the action, the new state and how long the reducer took.
+    /// Reporting failures are counted by the sender rather than returned, so they never change
+    /// what the reducer does.
+    pub fn dispatch<S, A>(&mut self, state: S, action: A) -> S
+        where F: FnMut(S, A) -> S, S: Serialize, A: Serialize
+    {
+        let amplitude = serde_json::to_value(&action).unwrap_or(Value::Null);
+        let invocation = match amplitude {
+            Value::String(ref name) => name.clone(),
+            _ => String::from("dispatch"),
+        };
+        self.run(&invocation, amplitude, state, action)
+    }
+
+    /// Like `dispatch`, reporting the action under `invocation`, such as the name of its variant.
+    pub fn dispatch_as<S, A>(&mut self, invocation: &str, state: S, action: A) -> S
+        where F: FnMut(S, A) -> S, S: Serialize, A: Serialize
+    {
+        let amplitude = serde_json::to_value(&action).unwrap_or(Value::Null);
+        self.run(invocation, amplitude, state, action)
+    }
+
+    fn run<S, A>(&mut self, invocation: &str, amplitude: Value, state: S, action: A) -> S
+        where F: FnMut(S, A) -> S, S: Serialize
+    {
+        let started = Instant::now();
+        let state = (self.reducer)(state, action);
+        let duration = started.elapsed();
+
+        let state_type = match self.state_type {
+            Some(ref state_type) => state_type.clone(),
+            None => String::from(type_name::<S>()),
+        };
+        let report = Report::new(&self.domain, invocation)
+            .amplitude(&amplitude)
+            .state(&state_type, &state)
+            .duration(duration)
+            .build();
+        if let Ok(item) = report {
+            self.sender.send(&item).ok();
+        }
+
+        state
+    }
+}
@@ -0,0 +1,180 @@
+use std::io::{Error, ErrorKind, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Weak, Mutex, Condvar};
+use std::thread;
+use std::time::{Duration, Instant};
+use message_parser::serialize_history_item;
+use state::HistoryItem;
+
+/// The amount of reports kept while the inspector is slow or unreachable.
+const DEFAULT_CAPACITY: usize = 1024;
+const MIN_RETRY_DELAY: Duration = Duration::from_millis(100);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Sends reports to the inspector from a background thread, so reporting never blocks the
+/// application.
+///
+/// Reports wait in a bounded queue. When the inspector is unreachable the thread keeps
+/// reconnecting, backing off up to `MAX_RETRY_DELAY`, and reports that do not fit the queue in the
+/// meantime are dropped and counted.
+///
+/// Once every clone of the sender is dropped the thread delivers what is left in the queue and
+/// stops, or stops right away when the inspector is unreachable, dropping those reports. Call
+/// `flush` before exiting to wait for them.
+#[derive(Clone)]
+pub struct Sender {
+    queue: SyncSender<String>,
+    shared: Arc<Shared>,
+}
+
+/// What the senders share with the thread delivering their reports.
+struct Shared {
+    dropped: AtomicUsize,
+    /// Reports queued but not yet written to the inspector.
+    pending: Mutex<usize>,
+    delivered: Condvar,
+}
+
+impl Sender {
+    pub fn new(address: &str) -> Sender {
+        Sender::with_capacity(address, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(address: &str, capacity: usize) -> Sender {
+        let (queue, lines) = sync_channel(capacity);
+        let shared = Arc::new(Shared {
+            dropped: AtomicUsize::new(0),
+            pending: Mutex::new(0),
+            delivered: Condvar::new(),
+        });
+        let address = String::from(address);
+        let weak_shared = Arc::downgrade(&shared);
+        thread::spawn(move || deliver(&address, lines, weak_shared));
+
+        Sender {
+            queue: queue,
+            shared: shared,
+        }
+    }
+
+    /// Queues `item` without waiting. Fails when the queue is full, in which case the item is
+    /// dropped.
+    pub fn send(&self, item: &HistoryItem) -> Result<(), Error> {
+        let line = serialize_history_item(item)?;
+
+        // Counted before queueing, so the thread never finishes a report that is not counted yet.
+        *self.shared.pending.lock().unwrap() += 1;
+        let result = match self.queue.try_send(line) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                Err(Error::new(ErrorKind::WouldBlock, "the report queue is full"))
+            },
+            Err(TrySendError::Disconnected(_)) => Err(Error::new(ErrorKind::BrokenPipe, "the report sender stopped")),
+        };
+        *self.shared.pending.lock().unwrap() -= 1;
+        result
+    }
+
+    /// Waits until every queued report is written to the inspector, failing when that takes
+    /// longer than `timeout`.
+    pub fn flush(&self, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+        let mut pending = self.shared.pending.lock().unwrap();
+
+        while *pending > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::new(ErrorKind::TimedOut, format!("{} reports are still queued", *pending)));
+            }
+            pending = self.shared.delivered.wait_timeout(pending, deadline - now).unwrap().0;
+        }
+
+        Ok(())
+    }
+
+    /// How many reports were dropped because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Writes the queued lines to the inspector. It only holds on to the shared state weakly, so it can
+/// tell when every sender is gone and stop retrying.
+fn deliver(address: &str, lines: Receiver<String>, shared: Weak<Shared>) {
+    let mut connection: Option<TcpStream> = None;
+    let mut retry_delay = MIN_RETRY_DELAY;
+
+    for line in lines {
+        loop {
+            if connection.is_none() {
+                match TcpStream::connect(address) {
+                    Ok(stream) => {
+                        connection = Some(stream);
+                        retry_delay = MIN_RETRY_DELAY;
+                    },
+                    Err(_) => {
+                        if shared.upgrade().is_none() {
+                            return;
+                        }
+                        thread::sleep(retry_delay);
+                        retry_delay = ::std::cmp::min(retry_delay * 2, MAX_RETRY_DELAY);
+                        continue;
+                    },
+                };
+            }
+
+            let written = connection.as_mut()
+                .map(|stream| stream.write_all(line.as_bytes()).and_then(|_| stream.write_all(b"\n")))
+                .unwrap_or_else(|| Err(Error::new(ErrorKind::NotConnected, "not connected")));
+            match written {
+                Ok(()) => break,
+                Err(_) => connection = None,
+            };
+        }
+
+        if let Some(shared) = shared.upgrade() {
+            *shared.pending.lock().unwrap() -= 1;
+            shared.delivered.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use state::{HistoryAction, HistoryState};
+
+    fn item(invocation: &str) -> HistoryItem {
+        let action = HistoryAction::new(String::from("counter"), String::from(invocation), String::from("null"));
+        HistoryItem::new(action, HistoryState::new(String::from("Counter"), String::from("1")), Some(0), 0)
+    }
+
+    #[test]
+    fn flush_waits_for_queued_reports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let sender = Sender::new(&listener.local_addr().unwrap().to_string());
+        sender.send(&item("increment")).unwrap();
+        sender.send(&item("decrement")).unwrap();
+
+        sender.flush(Duration::from_secs(5)).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let lines: Vec<String> = BufReader::new(stream).lines().take(2).map(|line| line.unwrap()).collect();
+        assert!(lines[0].contains("increment"));
+        assert!(lines[1].contains("decrement"));
+    }
+
+    #[test]
+    fn flush_times_out_while_the_inspector_is_unreachable() {
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let sender = Sender::new(&address);
+        sender.send(&item("increment")).unwrap();
+
+        let err = sender.flush(Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+}
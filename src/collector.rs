@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::io::Error;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use actions::{self, Action};
+use config::Config;
+use layout;
+use state::{State, Status, HistoryItem};
+use tcp;
+
+/// What the headless recorder is sent after every action instead of the whole state, so the
+/// history is not copied for every message.
+pub struct Update {
+    /// The items added since the last update, with the id of their client and their index in its
+    /// history.
+    pub items: Vec<(String, usize, HistoryItem)>,
+    /// When the last message arrived.
+    pub last_received: Option<u64>,
+    pub status: Status,
+    pub last_error: Option<String>,
+    pub config_errors: Vec<String>,
+    pub listen_addresses: Vec<String>,
+    pub dispatcher: Sender<Action>,
+}
+
+/// Listens on the configured addresses and starts the state thread, which applies every action
+/// to the application state. Every new state is sent on the returned receiver, starting with the
+/// initial one, until the state shuts down. The window layout is loaded at the start and saved
+/// on shutdown only when `windowed` is set, the other frontends have no use for it.
+pub fn start(config: Config, windowed: bool) -> Result<Receiver<State>, Error> {
+    spawn(config, windowed, |state| state.clone())
+}
+
+/// Like `start`, sending only what changed since the last update.
+pub fn start_headless(config: Config) -> Result<Receiver<Update>, Error> {
+    let mut written: HashMap<String, usize> = HashMap::new();
+    spawn(config, false, move |state| new_items(state, &mut written))
+}
+
+/// The update carrying the items added since the last call, remembering per client how many were
+/// sent.
+fn new_items(state: &State, written: &mut HashMap<String, usize>) -> Update {
+    let mut items = Vec::new();
+
+    for client_id in state.client_ids() {
+        let client = &state.clients[&client_id];
+        let amount = written.entry(client_id.clone()).or_insert(0);
+
+        for (index, item) in client.history.iter().enumerate().skip(*amount) {
+            items.push((client.id.clone(), index, item.clone()));
+        }
+        *amount = client.history.len();
+    }
+
+    Update {
+        items: items,
+        last_received: state.received.back().cloned(),
+        status: state.status.clone(),
+        last_error: state.last_error.clone(),
+        config_errors: state.config_errors.clone(),
+        listen_addresses: state.listen_addresses.clone(),
+        dispatcher: state.dispatcher.clone(),
+    }
+}
+
+fn spawn<T, F>(config: Config, windowed: bool, mut publish: F) -> Result<Receiver<T>, Error>
+    where T: Send + 'static, F: FnMut(&State) -> T + Send + 'static
+{
+    let (tx, rx, addresses) = tcp::listen(&config.listen_addresses)?;
+    let (state_tx, state_rx) = channel();
+
+    thread::spawn(move || {
+        let layout = if windowed {
+            layout::load(&config.layout_path).unwrap_or_else(|err| {
+                eprintln!("failed to load the layout from {}: {}", config.layout_path, err);
+                layout::Layout::default()
+            })
+        } else {
+            layout::Layout::default()
+        };
+        let mut application_state = State::new(tx, config, layout);
+        application_state.status = Status::Running;
+        application_state.listen_addresses = addresses;
+        state_tx.send(publish(&application_state)).expect("Failed to send application state to the ui");
+
+        loop {
+            let action = rx.recv().expect("error receiving msg");
+            application_state = actions::run(action, application_state).expect("Failed to run action");
+
+            if let (Status::ShuttingDown, true) = (&application_state.status, windowed) {
+                if let Err(err) = layout::save(&application_state.layout, &application_state.config.layout_path) {
+                    eprintln!("failed to save the layout to {}: {}", application_state.config.layout_path, err);
+                }
+            }
+            state_tx.send(publish(&application_state)).expect("Failed to send application state to the ui");
+
+            match application_state.status {
+                Status::ShuttingDown => break,
+                _ => (),
+            };
+        }
+    });
+
+    Ok(state_rx)
+}
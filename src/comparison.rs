@@ -0,0 +1,349 @@
+use std::mem;
+use serde_json::{self, Value};
+use json_diff::{diff, Change};
+use state::HistoryItem;
+
+/// How an action of the expected recording lines up with the actual one.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Step {
+    /// The action happened in both recordings, with the differences between the two states
+    /// after it.
+    Matched { expected: usize, actual: usize, action: String, changes: Vec<Change> },
+    /// Only the expected recording has the action.
+    Missing { expected: usize, action: String },
+    /// Only the actual recording has the action.
+    Extra { actual: usize, action: String },
+}
+
+impl Step {
+    /// Whether the step is an action both recordings agree on, state included.
+    pub fn is_equal(&self) -> bool {
+        match *self {
+            Step::Matched { ref changes, .. } => changes.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// Where the action streams first part ways, as the index of the next item on either side.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+/// The alignment of two recordings of the same client.
+#[derive(Serialize, Debug, Clone)]
+pub struct Comparison {
+    pub expected_client: Option<String>,
+    pub actual_client: Option<String>,
+    /// False when the recordings differ over too long a stretch to align, in which case every
+    /// action between their shared start and end counts as missing or extra.
+    pub aligned: bool,
+    pub divergence: Option<Divergence>,
+    pub missing: usize,
+    pub extra: usize,
+    /// The amount of matched actions after which the states differ.
+    pub differing_states: usize,
+    pub steps: Vec<Step>,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.missing == 0 && self.extra == 0 && self.differing_states == 0
+    }
+}
+
+/// The comparison of two sessions, client by client.
+#[derive(Serialize, Debug, Clone)]
+pub struct Report {
+    pub passed: bool,
+    pub clients: Vec<Comparison>,
+}
+
+/// Parses a recorded json string, keeping text that is not json as a string, so formatting and
+/// key order do not count as differences.
+fn json(content: &str) -> Value {
+    serde_json::from_str(content).unwrap_or_else(|_| Value::String(String::from(content)))
+}
+
+/// What identifies an action when aligning two recordings.
+#[derive(PartialEq)]
+struct Key<'a> {
+    domain: &'a str,
+    invocation: &'a str,
+    amplitude: Value,
+}
+
+fn key<'a>(item: &'a HistoryItem) -> Key<'a> {
+    Key {
+        domain: &item.action.domain,
+        invocation: &item.action.invocation,
+        amplitude: json(&item.action.amplitude),
+    }
+}
+
+/// The items of a session per client, in the order the clients first show up.
+pub fn group_by_client(items: Vec<(String, HistoryItem)>) -> Vec<(String, Vec<HistoryItem>)> {
+    let mut clients: Vec<(String, Vec<HistoryItem>)> = Vec::new();
+
+    for (client, item) in items {
+        match clients.iter().position(|&(ref id, _)| *id == client) {
+            Some(position) => clients[position].1.push(item),
+            None => clients.push((client, vec![item])),
+        };
+    }
+
+    clients
+}
+
+/// Compares two sessions. Client ids differ between runs, so clients are paired in the order they
+/// first show up; a client without a counterpart counts as entirely missing or extra.
+pub fn compare_sessions(expected: Vec<(String, HistoryItem)>, actual: Vec<(String, HistoryItem)>) -> Report {
+    let mut expected = group_by_client(expected).into_iter();
+    let mut actual = group_by_client(actual).into_iter();
+    let mut clients = Vec::new();
+
+    loop {
+        let comparison = match (expected.next(), actual.next()) {
+            (None, None) => break,
+            (Some((expected_client, expected_items)), Some((actual_client, actual_items))) => {
+                let mut comparison = compare(&expected_items, &actual_items);
+                comparison.expected_client = Some(expected_client);
+                comparison.actual_client = Some(actual_client);
+                comparison
+            },
+            (Some((expected_client, expected_items)), None) => {
+                let mut comparison = compare(&expected_items, &[]);
+                comparison.expected_client = Some(expected_client);
+                comparison
+            },
+            (None, Some((actual_client, actual_items))) => {
+                let mut comparison = compare(&[], &actual_items);
+                comparison.actual_client = Some(actual_client);
+                comparison
+            },
+        };
+        clients.push(comparison);
+    }
+
+    Report {
+        passed: clients.iter().all(|comparison| comparison.passed()),
+        clients: clients,
+    }
+}
+
+/// Aligns the actions of `actual` with those of `expected` by domain, invocation and amplitude,
+/// keeping as many of them matched as possible, and compares the states after matched actions.
+pub fn compare(expected: &[HistoryItem], actual: &[HistoryItem]) -> Comparison {
+    let expected_keys: Vec<Key> = expected.iter().map(key).collect();
+    let actual_keys: Vec<Key> = actual.iter().map(key).collect();
+    let (pairs, aligned) = align(&expected_keys, &actual_keys);
+
+    let mut steps = Vec::new();
+    let mut divergence = None;
+    let (mut next_expected, mut next_actual) = (0, 0);
+    let (mut missing, mut extra, mut differing_states) = (0, 0, 0);
+
+    for (expected_index, actual_index) in pairs.into_iter().chain(Some((expected.len(), actual.len()))) {
+        if (next_expected < expected_index || next_actual < actual_index) && divergence.is_none() {
+            divergence = Some(Divergence { expected: next_expected, actual: next_actual });
+        }
+        for (index, item) in expected.iter().enumerate().take(expected_index).skip(next_expected) {
+            steps.push(Step::Missing { expected: index, action: action_name(item) });
+            missing += 1;
+        }
+        for (index, item) in actual.iter().enumerate().take(actual_index).skip(next_actual) {
+            steps.push(Step::Extra { actual: index, action: action_name(item) });
+            extra += 1;
+        }
+        if expected_index == expected.len() {
+            break;
+        }
+
+        let changes = diff(&json(&expected[expected_index].state.content), &json(&actual[actual_index].state.content));
+        if !changes.is_empty() {
+            differing_states += 1;
+        }
+        steps.push(Step::Matched {
+            expected: expected_index,
+            actual: actual_index,
+            action: action_name(&expected[expected_index]),
+            changes: changes,
+        });
+        next_expected = expected_index + 1;
+        next_actual = actual_index + 1;
+    }
+
+    Comparison {
+        expected_client: None,
+        actual_client: None,
+        aligned: aligned,
+        divergence: divergence,
+        missing: missing,
+        extra: extra,
+        differing_states: differing_states,
+        steps: steps,
+    }
+}
+
+fn action_name(item: &HistoryItem) -> String {
+    format!("{}:{}", item.action.domain, item.action.invocation)
+}
+
+/// The most cells the alignment compares between the shared start and end of two recordings,
+/// which keeps a comparison of recordings that differ throughout to a few seconds.
+const MAX_ALIGNMENT_CELLS: usize = 100_000_000;
+
+/// The index pairs of a longest common subsequence of `a` and `b`, and whether the part between
+/// their shared start and end was small enough to align. The shared start and end are matched
+/// directly; the rest is aligned in linear space, and left unmatched when it is too large.
+fn align<T: PartialEq>(a: &[T], b: &[T]) -> (Vec<(usize, usize)>, bool) {
+    let prefix = a.iter().zip(b.iter()).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|&(x, y)| x == y).count();
+    let middle_a = &a[prefix..a.len() - suffix];
+    let middle_b = &b[prefix..b.len() - suffix];
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|index| (index, index)).collect();
+    let aligned = middle_a.len().saturating_mul(middle_b.len()) <= MAX_ALIGNMENT_CELLS;
+    if aligned {
+        align_middle(middle_a, middle_b, prefix, prefix, &mut pairs);
+    }
+    pairs.extend((0..suffix).map(|offset| (a.len() - suffix + offset, b.len() - suffix + offset)));
+
+    (pairs, aligned)
+}
+
+/// Hirschberg's algorithm: splits `a` in half, finds where a longest common subsequence crosses
+/// that line from the lengths of both halves, and aligns the two sides on their own.
+fn align_middle<T: PartialEq>(a: &[T], b: &[T], a_offset: usize, b_offset: usize, pairs: &mut Vec<(usize, usize)>) {
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+    if a.len() == 1 {
+        if let Some(index) = b.iter().position(|item| *item == a[0]) {
+            pairs.push((a_offset, b_offset + index));
+        }
+        return;
+    }
+
+    let middle = a.len() / 2;
+    let forward = lcs_lengths(&a[..middle], b, false);
+    let backward = lcs_lengths(&a[middle..], b, true);
+    let split = (0..=b.len())
+        .max_by_key(|&split| (forward[split] + backward[b.len() - split], usize::MAX - split))
+        .unwrap_or(0);
+
+    align_middle(&a[..middle], &b[..split], a_offset, b_offset, pairs);
+    align_middle(&a[middle..], &b[split..], a_offset + middle, b_offset + split, pairs);
+}
+
+/// The length of a longest common subsequence of `a` and every prefix of `b`, indexed by the
+/// length of the prefix. When `reverse` is set both are read back to front, so the lengths are
+/// those of `a` and every suffix of `b`, indexed by the length of the suffix.
+fn lcs_lengths<T: PartialEq>(a: &[T], b: &[T], reverse: bool) -> Vec<u32> {
+    let at = |items: &[T], index: usize| if reverse { items.len() - 1 - index } else { index };
+    let mut previous = vec![0u32; b.len() + 1];
+    let mut current = vec![0u32; b.len() + 1];
+
+    for i in 0..a.len() {
+        for j in 0..b.len() {
+            current[j + 1] = if a[at(a, i)] == b[at(b, j)] {
+                previous[j] + 1
+            } else {
+                u32::max(previous[j + 1], current[j])
+            };
+        }
+        mem::swap(&mut previous, &mut current);
+    }
+
+    previous
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state::{HistoryAction, HistoryState};
+
+    fn item(invocation: &str, state: &str) -> HistoryItem {
+        let action = HistoryAction::new(String::from("todo"), String::from(invocation), String::from("{}"));
+        HistoryItem::new(action, HistoryState::new(String::from("Todo"), String::from(state)), None, 0)
+    }
+
+    fn is_common_subsequence(a: &[char], b: &[char], pairs: &[(usize, usize)]) -> bool {
+        pairs.windows(2).all(|pair| pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1)
+            && pairs.iter().all(|&(i, j)| a[i] == b[j])
+    }
+
+    #[test]
+    fn aligns_a_longest_common_subsequence() {
+        let cases = [("ABCBDAB", "BDCABA", 4), ("XMJYAUZ", "MZJAWXU", 4), ("abc", "abc", 3), ("abc", "", 0), ("", "xyz", 0), ("aXbYc", "aZbc", 3)];
+
+        for &(a, b, length) in cases.iter() {
+            let a: Vec<char> = a.chars().collect();
+            let b: Vec<char> = b.chars().collect();
+            let (pairs, aligned) = align(&a, &b);
+
+            assert!(aligned);
+            assert_eq!(pairs.len(), length, "{:?} / {:?}", a, b);
+            assert!(is_common_subsequence(&a, &b, &pairs));
+        }
+    }
+
+    #[test]
+    fn matches_the_quadratic_lcs_length() {
+        let mut seed: u32 = 7;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % 4
+        };
+
+        for _ in 0..200 {
+            let a: Vec<u32> = (0..next() * 5).map(|_| next()).collect();
+            let b: Vec<u32> = (0..next() * 5).map(|_| next()).collect();
+            let full = lcs_lengths(&a, &b, false);
+            let (pairs, _) = align(&a, &b);
+
+            assert_eq!(pairs.len() as u32, full[b.len()]);
+            assert!(pairs.windows(2).all(|pair| pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1));
+            assert!(pairs.iter().all(|&(i, j)| a[i] == b[j]));
+        }
+    }
+
+    #[test]
+    fn leaves_too_large_differences_unaligned() {
+        let a: Vec<usize> = (0..20_001).collect();
+        let b: Vec<usize> = (0..20_001).map(|value| if value == 0 || value == 20_000 { value } else { value + 100_000 }).collect();
+        let (pairs, aligned) = align(&a, &b);
+
+        assert!(!aligned);
+        assert_eq!(pairs, vec![(0, 0), (20_000, 20_000)]);
+    }
+
+    #[test]
+    fn reports_where_recordings_diverge() {
+        let expected = vec![item("add", "1"), item("toggle", "2"), item("remove", "3")];
+        let actual = vec![item("add", "1"), item("rename", "9"), item("toggle", "2"), item("remove", "4")];
+        let comparison = compare(&expected, &actual);
+
+        assert!(comparison.aligned);
+        assert_eq!(comparison.divergence, Some(Divergence { expected: 1, actual: 1 }));
+        assert_eq!((comparison.missing, comparison.extra, comparison.differing_states), (0, 1, 1));
+        assert!(!comparison.passed());
+        assert!(compare(&expected, &expected).passed());
+    }
+
+    #[test]
+    fn pairs_clients_in_order_of_appearance() {
+        let expected = vec![(String::from("a"), item("add", "1")), (String::from("b"), item("add", "1"))];
+        let actual = vec![(String::from("x"), item("add", "1"))];
+        let report = compare_sessions(expected, actual);
+
+        assert!(!report.passed);
+        assert_eq!(report.clients.len(), 2);
+        assert!(report.clients[0].passed());
+        assert_eq!(report.clients[1].actual_client, None);
+        assert_eq!(report.clients[1].missing, 1);
+    }
+}
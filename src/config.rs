@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{Error, ErrorKind};
+use serde_json::{self, Value};
+use invariants::InvariantConfig;
+
+const DEFAULT_PATH: &'static str = "vision_quest.json";
+
+/// The default key for every remappable command, written as `Modifier+Key` using the names of
+/// glutin's `VirtualKeyCode`s, e.g. `Ctrl+S` or `Home`.
+const DEFAULT_KEY_BINDINGS: &'static [(&'static str, &'static str)] = &[
+    ("previous_action", "Up"),
+    ("next_action", "Down"),
+    ("first_action", "Home"),
+    ("last_action", "End"),
+    ("next_tab", "Tab"),
+    ("previous_tab", "Shift+Tab"),
+    ("tab_1", "Key1"),
+    ("tab_2", "Key2"),
+    ("tab_3", "Key3"),
+    ("tab_4", "Key4"),
+    ("tab_5", "Key5"),
+    ("tab_6", "Key6"),
+    ("tab_7", "Key7"),
+    ("tab_8", "Key8"),
+    ("tab_9", "Key9"),
+    ("focus_search", "Ctrl+F"),
+    ("toggle_pause", "Ctrl+P"),
+    ("save", "Ctrl+S"),
+    ("cycle_theme", "Ctrl+T"),
+    ("toggle_grouping", "Ctrl+G"),
+    ("close_tab", "Ctrl+W"),
+    ("merged_view", "Ctrl+M"),
+    ("stats_view", "Ctrl+D"),
+    ("redundant_only", "Ctrl+R"),
+    ("clear_client", "Ctrl+L"),
+    ("quit", "Escape"),
+];
+
+pub const THEMES: &'static [&'static str] = &["dark", "light", "high_contrast"];
+
+/// User settings, read from `vision_quest.json` in the working directory or from the file named
+/// by the `VISION_QUEST_CONFIG` environment variable.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub key_bindings: HashMap<String, String>,
+    /// The addresses clients connect to.
+    pub listen_addresses: Vec<String>,
+    pub session_path: String,
+    pub layout_path: String,
+    /// One of `THEMES`.
+    pub theme: String,
+    /// A TrueType font to use instead of the embedded one, relative to the working directory or
+    /// the executable.
+    pub font_path: Option<String>,
+    pub font_size: u32,
+    /// Colours for action domains as `#rrggbb`, replacing the generated ones.
+    pub domain_colors: HashMap<String, String>,
+    /// Whether runs of consecutive actions of one domain are folded in the action list.
+    pub group_domains: bool,
+    /// Seconds without a received message after which headless mode stops, 0 to keep running.
+    pub idle_timeout: u64,
+    /// Rules every received state has to keep.
+    pub invariants: Vec<InvariantConfig>,
+    /// JSON Schemas for states by `StateType`, written inline or as the path of a schema file.
+    pub state_schemas: HashMap<String, Value>,
+    /// JSON Schemas for amplitudes by `domain:invocation`, written inline or as a file path.
+    pub amplitude_schemas: HashMap<String, Value>,
+    /// Kilobytes a state may grow by without ever shrinking before it is flagged, 0 to never flag.
+    pub state_growth_kb: u64,
+    /// Kilobytes a single action may add to the state before it is flagged, 0 to never flag.
+    pub action_growth_kb: u64,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            key_bindings: HashMap::new(),
+            listen_addresses: vec![String::from("127.0.0.1:7033")],
+            session_path: String::from("vision_quest_session.jsonl"),
+            layout_path: String::from("vision_quest_layout.json"),
+            theme: String::from("dark"),
+            font_path: None,
+            font_size: 18,
+            domain_colors: HashMap::new(),
+            group_domains: false,
+            idle_timeout: 0,
+            invariants: Vec::new(),
+            state_schemas: HashMap::new(),
+            amplitude_schemas: HashMap::new(),
+            state_growth_kb: 1024,
+            action_growth_kb: 100,
+        }
+    }
+}
+
+impl Config {
+    /// The key bound to `command`, falling back to the default binding.
+    pub fn key_binding(&self, command: &str) -> Option<&str> {
+        self.key_bindings.get(command)
+            .map(|key| key.as_str())
+            .or_else(|| {
+                DEFAULT_KEY_BINDINGS.iter()
+                    .find(|&&(name, _)| name == command)
+                    .map(|&(_, key)| key)
+            })
+    }
+
+    pub fn commands() -> Vec<&'static str> {
+        DEFAULT_KEY_BINDINGS.iter()
+            .map(|&(name, _)| name)
+            .collect()
+    }
+}
+
+/// The theme after `name`, for cycling through them.
+pub fn next_theme(name: &str) -> &'static str {
+    let index = THEMES.iter().position(|theme| *theme == name).unwrap_or(0);
+    THEMES[(index + 1) % THEMES.len()]
+}
+
+pub fn path() -> String {
+    env::var("VISION_QUEST_CONFIG").unwrap_or_else(|_| String::from(DEFAULT_PATH))
+}
+
+/// Loads the config file, using the defaults when there is none.
+pub fn load() -> Result<Config, Error> {
+    match File::open(path()) {
+        Ok(file) => Ok(serde_json::from_reader(file)?),
+        Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use serde_json::Value;
+
+/// Why the size of a state stands out.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SizeAlert {
+    /// The state grew from `from` to `to` bytes over `actions` actions without shrinking once.
+    Growth { from: usize, to: usize, actions: usize },
+    /// A single action added `added` bytes to the state.
+    Jump { added: usize },
+}
+
+impl fmt::Display for SizeAlert {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SizeAlert::Growth { from, to, actions } => write!(formatter, "the state grew from {} to {} over {} actions without shrinking", format_bytes(from), format_bytes(to), actions),
+            SizeAlert::Jump { added } => write!(formatter, "the action added {} to the state", format_bytes(added)),
+        }
+    }
+}
+
+/// Bytes for display, in the largest unit that keeps the number above 1.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&'static str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// The amount of values in `value`, itself included.
+pub fn node_count(value: &Value) -> usize {
+    match *value {
+        Value::Array(ref items) => 1 + items.iter().map(node_count).sum::<usize>(),
+        Value::Object(ref fields) => 1 + fields.values().map(node_count).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+/// Follows the sizes of a client's states, one after the other, to spot leaks.
+#[derive(Debug, Clone, Default)]
+pub struct GrowthTracker {
+    previous: Option<usize>,
+    /// The size the current run of growth started at, and how many actions it lasted so far.
+    streak_start: usize,
+    streak_length: usize,
+    /// Whether the current run of growth already raised an alert.
+    alerted: bool,
+}
+
+impl GrowthTracker {
+    /// Takes in the size of the next state. Growth without shrinking by `growth_limit` bytes is
+    /// reported once per run of growth, and a single action adding more than `jump_limit` bytes
+    /// every time. A limit of 0 turns its alert off.
+    pub fn observe(&mut self, size: usize, growth_limit: usize, jump_limit: usize) -> Vec<SizeAlert> {
+        let mut alerts = Vec::new();
+
+        match self.previous {
+            Some(previous) if size >= previous => {
+                self.streak_length += 1;
+                if jump_limit > 0 && size - previous > jump_limit {
+                    alerts.push(SizeAlert::Jump { added: size - previous });
+                }
+            },
+            _ => {
+                self.streak_start = size;
+                self.streak_length = 0;
+                self.alerted = false;
+            },
+        };
+
+        if growth_limit > 0 && !self.alerted && size - self.streak_start >= growth_limit {
+            alerts.push(SizeAlert::Growth { from: self.streak_start, to: size, actions: self.streak_length });
+            self.alerted = true;
+        }
+        self.previous = Some(size);
+
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe_all(tracker: &mut GrowthTracker, sizes: &[usize]) -> Vec<Vec<SizeAlert>> {
+        sizes.iter().map(|size| tracker.observe(*size, 100, 50)).collect()
+    }
+
+    #[test]
+    fn reports_growth_once_per_run() {
+        let mut tracker = GrowthTracker::default();
+        let alerts = observe_all(&mut tracker, &[10, 40, 70, 110, 140]);
+
+        assert_eq!(alerts[3], vec![SizeAlert::Growth { from: 10, to: 110, actions: 3 }]);
+        assert!(alerts.iter().enumerate().all(|(index, alerts)| index == 3 || alerts.is_empty()));
+    }
+
+    #[test]
+    fn shrinking_starts_a_new_run() {
+        let mut tracker = GrowthTracker::default();
+        let alerts = observe_all(&mut tracker, &[10, 60, 100, 50, 90, 130, 150]);
+
+        assert!(alerts[..6].iter().all(|alerts| alerts.is_empty()));
+        assert_eq!(alerts[6], vec![SizeAlert::Growth { from: 50, to: 150, actions: 3 }]);
+    }
+
+    #[test]
+    fn reports_every_jump() {
+        let mut tracker = GrowthTracker::default();
+        let alerts = observe_all(&mut tracker, &[0, 60, 70, 71, 150]);
+
+        assert_eq!(alerts[1], vec![SizeAlert::Jump { added: 60 }]);
+        assert!(alerts[2].is_empty() && alerts[3].is_empty());
+        assert_eq!(alerts[4], vec![SizeAlert::Jump { added: 79 }, SizeAlert::Growth { from: 0, to: 150, actions: 4 }]);
+    }
+
+    #[test]
+    fn a_limit_of_zero_turns_its_alert_off() {
+        let mut tracker = GrowthTracker::default();
+        let alerts: Vec<Vec<SizeAlert>> = [0, 5000, 10000].iter().map(|size| tracker.observe(*size, 0, 0)).collect();
+        assert!(alerts.iter().all(|alerts| alerts.is_empty()));
+    }
+
+    #[test]
+    fn counts_values_and_formats_sizes() {
+        assert_eq!(node_count(&json!({"a": [1, 2, {"b": null}], "c": "d"})), 7);
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -0,0 +1,249 @@
+use std::fs::File;
+use std::io::{self, Write, BufWriter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+#[cfg(unix)]
+use libc;
+use serde_json;
+use actions::{Action, Message};
+use state::{Status, HistoryItem};
+use collector::Update;
+use session;
+use cli::Options;
+use comparison;
+
+/// How often the recorder checks for signals and the idle timeout.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+fn watch_signals() {
+    let handler = interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+/// Lets the next SIGINT or SIGTERM end the process right away, for when stopping gracefully hangs.
+#[cfg(unix)]
+fn unwatch_signals() {
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+        libc::signal(libc::SIGTERM, libc::SIG_DFL);
+    }
+}
+
+/// Elsewhere Ctrl+C ends the process right away, and only the idle timeout stops recording.
+#[cfg(not(unix))]
+fn watch_signals() {}
+
+#[cfg(not(unix))]
+fn unwatch_signals() {}
+
+/// What a headless run recorded.
+pub struct Recording {
+    /// The items in the order they were written, with the id of their client. Only kept when
+    /// they are compared afterwards.
+    pub items: Vec<(String, HistoryItem)>,
+    /// The amount of items that broke an invariant or did not match a schema.
+    pub failing: usize,
+    /// The amount of actions that left the state unchanged.
+    pub unchanged: usize,
+    /// The amount of actions that repeated the one before them.
+    pub repeated: usize,
+    /// The amount of states that grew past a size limit.
+    pub growing: usize,
+    /// The amount of invariants and schemas from the config that could not be used.
+    pub config_errors: usize,
+}
+
+/// Records without a window as asked for by `options`, then compares the recording with the
+/// golden session if one was given. Returns the exit code: 1 when an invariant or schema from the
+/// config could not be used, an item failed a check, the recording differs from the golden
+/// session or recording failed.
+pub fn record(rx: Receiver<Update>, options: Options, idle_timeout: u64) -> i32 {
+    let recording = match run(rx, options.output, idle_timeout, options.golden.is_some()) {
+        Ok(recording) => recording,
+        Err(err) => {
+            eprintln!("failed to record: {}", err);
+            return 1;
+        },
+    };
+
+    if recording.unchanged > 0 || recording.repeated > 0 {
+        eprintln!("{} actions left the state unchanged, {} repeated the action before them", recording.unchanged, recording.repeated);
+    }
+    if recording.growing > 0 {
+        eprintln!("{} states grew past a size limit", recording.growing);
+    }
+
+    let mut exit_code = 0;
+    if recording.config_errors > 0 {
+        eprintln!("{} invariants or schemas from the config were not checked", recording.config_errors);
+        exit_code = 1;
+    }
+    if recording.failing > 0 {
+        eprintln!("{} items failed a check", recording.failing);
+        exit_code = 1;
+    }
+
+    if let Some(golden) = options.golden {
+        match compare_with_golden(&golden, recording.items, options.report) {
+            Ok(true) => (),
+            Ok(false) => exit_code = 1,
+            Err(err) => {
+                eprintln!("failed to compare with {}: {}", golden, err);
+                exit_code = 1;
+            },
+        };
+    }
+
+    exit_code
+}
+
+/// Writes the comparison of `items` with the session at `golden` as json to `report`, or to
+/// stdout, and sums it up on stderr. Returns whether the recording matches.
+fn compare_with_golden(golden: &str, items: Vec<(String, HistoryItem)>, report: Option<String>) -> Result<bool, io::Error> {
+    let comparison = comparison::compare_sessions(session::load(golden)?, items);
+
+    let mut writer: Box<dyn Write> = match report {
+        Some(ref path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+    writeln!(writer, "{}", serde_json::to_string_pretty(&comparison)?)?;
+    writer.flush()?;
+
+    for client in comparison.clients.iter().filter(|client| !client.passed()) {
+        let name = client.actual_client.as_ref().or(client.expected_client.as_ref()).map_or("", |id| id.as_str());
+        eprintln!("{} differs from {}: {} missing, {} extra, {} differing states", name, golden, client.missing, client.extra, client.differing_states);
+        if let Some(ref divergence) = client.divergence {
+            eprintln!("{} diverges at item {} of {} and item {} of the recording", name, divergence.expected, golden, divergence.actual);
+        }
+        if !client.aligned {
+            eprintln!("{} is too different from {} to align past the divergence", name, golden);
+        }
+    }
+
+    Ok(comparison.passed)
+}
+
+/// Records without a window. Every history item the state receives is written to `output`, or to
+/// stdout, as a json line of the session format, and every broken invariant or schema mismatch is
+/// reported on stderr, as is every invariant or schema from the config that could not be used.
+/// Recording stops on SIGINT or SIGTERM, or once no message came in for `idle_timeout` seconds
+/// when that is not 0; a second signal ends the process right away. The items are only kept in
+/// the recording when `keep_items` is set.
+pub fn run(rx: Receiver<Update>, output: Option<String>, idle_timeout: u64, keep_items: bool) -> Result<Recording, io::Error> {
+    let mut writer: Box<dyn Write> = match output {
+        Some(ref path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+
+    watch_signals();
+
+    let mut update = rx.recv().expect("Headless recorder failed to receive application state");
+    eprintln!("listening on {}", update.listen_addresses.join(", "));
+    for error in &update.config_errors {
+        eprintln!("{}", error);
+    }
+
+    let idle_timeout = Duration::from_secs(idle_timeout);
+    let mut last_received = Instant::now();
+    let mut last_message = update.last_received;
+    let mut recording = Recording {
+        items: Vec::new(),
+        failing: 0,
+        unchanged: 0,
+        repeated: 0,
+        growing: 0,
+        config_errors: update.config_errors.len(),
+    };
+    let mut last_error = update.last_error.clone();
+    let mut stopping = false;
+
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(new_update) => {
+                if new_update.last_received != last_message {
+                    last_received = Instant::now();
+                    last_message = new_update.last_received;
+                }
+                update = new_update;
+            },
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        write_new_items(&mut writer, &mut update.items, &mut recording, keep_items)?;
+
+        if update.last_error != last_error {
+            if let Some(ref error) = update.last_error {
+                eprintln!("{}", error);
+            }
+            last_error = update.last_error.clone();
+        }
+
+        if let Status::ShuttingDown = update.status {
+            break;
+        }
+
+        let interrupted = INTERRUPTED.load(Ordering::SeqCst);
+        let idle = idle_timeout.as_secs() > 0 && last_received.elapsed() >= idle_timeout;
+        if (interrupted || idle) && !stopping {
+            stopping = true;
+            let action = Action {
+                domain: String::from("application"),
+                invocation: String::from("quit"),
+                message: Message::Empty,
+                sender: String::from("headless"),
+            };
+
+            update.dispatcher.send(action).expect("Failed to send quit to the application state");
+            unwatch_signals();
+        }
+    }
+
+    writer.flush()?;
+    Ok(recording)
+}
+
+/// Writes the items of an update and counts them in `recording`, keeping them there too when
+/// `keep_items` is set. The items are taken out of `items`, so they are only written once.
+fn write_new_items<W: Write>(writer: &mut W, items: &mut Vec<(String, usize, HistoryItem)>, recording: &mut Recording, keep_items: bool) -> Result<(), io::Error> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    for (client_id, index, item) in items.drain(..) {
+        session::write_item(writer, &client_id, &item)?;
+
+        for violation in &item.violations {
+            eprintln!("{} #{} {}:{} broke {}", client_id, index, item.action.domain, item.action.invocation, violation);
+        }
+        for error in &item.schema_errors {
+            eprintln!("{} #{} {}:{} does not match its schema at {}", client_id, index, item.action.domain, item.action.invocation, error);
+        }
+        for alert in &item.size_alerts {
+            eprintln!("{} #{} {}:{}: {}", client_id, index, item.action.domain, item.action.invocation, alert);
+        }
+        if !item.violations.is_empty() || !item.schema_errors.is_empty() {
+            recording.failing += 1;
+        }
+        recording.unchanged += item.unchanged as usize;
+        recording.repeated += item.repeated as usize;
+        recording.growing += !item.size_alerts.is_empty() as usize;
+        if keep_items {
+            recording.items.push((client_id, item));
+        }
+    }
+
+    writer.flush()
+}
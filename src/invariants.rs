@@ -0,0 +1,487 @@
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use serde_json::{self, Value};
+use path_expression::PathExpression;
+
+/// How a state invariant is declared in the config.
+///
+/// A rule compares paths, literals and aggregates of paths, and may be guarded by a condition:
+///
+/// ```text
+/// cart.total == sum(cart.items[*].price)
+/// auth.loggedIn == true => auth.token != null
+/// count(todos[*]) <= 100 && user.name
+/// ```
+///
+/// Paths are written as described in `PathExpression`. The aggregates are `sum`, `count`, `min`
+/// and `max`. A value on its own holds when it is neither missing, null nor false.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InvariantConfig {
+    pub name: String,
+    /// Only states of this `StateType` are checked, every state when left out.
+    #[serde(default)]
+    pub state_type: Option<String>,
+    pub rule: String,
+}
+
+/// A parsed invariant, ready to check states against.
+#[derive(Debug, Clone)]
+pub struct Invariant {
+    pub name: String,
+    pub state_type: Option<String>,
+    pub rule: String,
+    expression: Expression,
+}
+
+/// An invariant a state broke, with the values the rule looked at.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub invariant: String,
+    pub rule: String,
+    pub values: Vec<(String, Value)>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let values: Vec<String> = self.values.iter()
+            .map(|&(ref operand, ref value)| format!("{} = {}", operand, value))
+            .collect();
+        write!(formatter, "{}: {} ({})", self.invariant, self.rule, values.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expression {
+    Implies(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Operand, Comparison, Operand),
+    Holds(Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregate {
+    Sum,
+    Count,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Literal(Value),
+    Path(String, PathExpression),
+    Aggregate(String, Aggregate, PathExpression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Literal(Value),
+    Symbol(&'static str),
+}
+
+/// Numbers closer than this are equal, so sums of prices compare as expected.
+const EPSILON: f64 = 1e-9;
+const SYMBOLS: &'static [&'static str] = &["=>", "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")"];
+
+impl Invariant {
+    pub fn new(config: &InvariantConfig) -> Result<Invariant, Error> {
+        let tokens = tokenize(&config.rule)?;
+        let mut parser = Parser { tokens: tokens, position: 0 };
+        let expression = parser.implication()?;
+        if parser.position < parser.tokens.len() {
+            return Err(invalid(format!("unexpected {:?} in rule {:?}", parser.tokens[parser.position], config.rule)));
+        }
+
+        Ok(Invariant {
+            name: config.name.clone(),
+            state_type: config.state_type.clone(),
+            rule: config.rule.clone(),
+            expression: expression,
+        })
+    }
+
+    /// Whether the invariant applies to states of `state_type`.
+    pub fn applies_to(&self, state_type: &str) -> bool {
+        self.state_type.as_ref().map_or(true, |wanted| wanted == state_type)
+    }
+
+    /// The violation of the invariant by `state`, if it breaks it.
+    pub fn check(&self, state: &Value) -> Option<Violation> {
+        let mut values = Vec::new();
+        if self.expression.holds(state, &mut values) {
+            return None;
+        }
+
+        Some(Violation {
+            invariant: self.name.clone(),
+            rule: self.rule.clone(),
+            values: values,
+        })
+    }
+}
+
+/// Parses every configured invariant, along with the errors of the ones that do not parse.
+pub fn parse_all(configs: &[InvariantConfig]) -> (Vec<Invariant>, Vec<String>) {
+    let mut invariants = Vec::new();
+    let mut errors = Vec::new();
+
+    for config in configs {
+        match Invariant::new(config) {
+            Ok(invariant) => invariants.push(invariant),
+            Err(err) => errors.push(format!("invariant {}: {}", config.name, err)),
+        };
+    }
+
+    (invariants, errors)
+}
+
+/// The invariants the json `content` of a state of `state_type` breaks. Content that is not json
+/// is not checked.
+pub fn check_all(invariants: &[Invariant], state_type: &str, content: &str) -> Vec<Violation> {
+    let applicable: Vec<&Invariant> = invariants.iter()
+        .filter(|invariant| invariant.applies_to(state_type))
+        .collect();
+    if applicable.is_empty() {
+        return Vec::new();
+    }
+
+    let state: Value = match serde_json::from_str(content) {
+        Ok(state) => state,
+        Err(_) => return Vec::new(),
+    };
+
+    applicable.iter()
+        .filter_map(|invariant| invariant.check(&state))
+        .collect()
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn tokenize(rule: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut rest = rule.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if rest.starts_with('"') {
+            let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<Value>();
+            let value = match stream.next() {
+                Some(Ok(value)) => value,
+                _ => return Err(invalid(format!("unterminated string in rule {:?}", rule))),
+            };
+            rest = &rest[stream.byte_offset()..];
+            tokens.push(Token::Literal(value));
+        } else {
+            let end = rest.find(|character: char| character.is_whitespace() || "=!<>&|()".contains(character))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err(invalid(format!("unexpected {:?} in rule {:?}", &rest[..1], rule)));
+            }
+
+            let word = &rest[..end];
+            tokens.push(match serde_json::from_str::<Value>(word) {
+                Ok(value) => Token::Literal(value),
+                Err(_) => Token::Word(String::from(word)),
+            });
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        match self.peek() {
+            Some(&Token::Symbol(found)) if found == symbol => {
+                self.position += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn implication(&mut self) -> Result<Expression, Error> {
+        let condition = self.or()?;
+        if self.eat("=>") {
+            return Ok(Expression::Implies(Box::new(condition), Box::new(self.or()?)));
+        }
+        Ok(condition)
+    }
+
+    fn or(&mut self) -> Result<Expression, Error> {
+        let mut expression = self.and()?;
+        while self.eat("||") {
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expression, Error> {
+        let mut expression = self.unary()?;
+        while self.eat("&&") {
+            expression = Expression::And(Box::new(expression), Box::new(self.unary()?));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression, Error> {
+        if self.eat("!") {
+            return Ok(Expression::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expression = self.implication()?;
+            if !self.eat(")") {
+                return Err(invalid(String::from("missing )")));
+            }
+            return Ok(expression);
+        }
+
+        let left = self.operand()?;
+        let comparison = match self.peek() {
+            Some(&Token::Symbol("==")) => Comparison::Equal,
+            Some(&Token::Symbol("!=")) => Comparison::NotEqual,
+            Some(&Token::Symbol("<")) => Comparison::Less,
+            Some(&Token::Symbol("<=")) => Comparison::LessOrEqual,
+            Some(&Token::Symbol(">")) => Comparison::Greater,
+            Some(&Token::Symbol(">=")) => Comparison::GreaterOrEqual,
+            _ => return Ok(Expression::Holds(left)),
+        };
+        self.position += 1;
+
+        Ok(Expression::Compare(left, comparison, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand, Error> {
+        let token = self.peek().cloned();
+        self.position += 1;
+
+        match token {
+            Some(Token::Literal(value)) => Ok(Operand::Literal(value)),
+            Some(Token::Word(word)) => {
+                let aggregate = match word.as_str() {
+                    "sum" => Some(Aggregate::Sum),
+                    "count" => Some(Aggregate::Count),
+                    "min" => Some(Aggregate::Min),
+                    "max" => Some(Aggregate::Max),
+                    _ => None,
+                };
+
+                match aggregate {
+                    Some(aggregate) if self.eat("(") => {
+                        let path = match self.peek().cloned() {
+                            Some(Token::Word(path)) => path,
+                            other => return Err(invalid(format!("expected a path in {}(), found {:?}", word, other))),
+                        };
+                        self.position += 1;
+                        if !self.eat(")") {
+                            return Err(invalid(format!("missing ) after {}({}", word, path)));
+                        }
+                        let text = format!("{}({})", word, path);
+                        Ok(Operand::Aggregate(text, aggregate, PathExpression::parse(&path)?))
+                    },
+                    _ => Ok(Operand::Path(word.clone(), PathExpression::parse(&word)?)),
+                }
+            },
+            other => Err(invalid(format!("expected a value, found {:?}", other))),
+        }
+    }
+}
+
+impl Expression {
+    fn holds(&self, state: &Value, values: &mut Vec<(String, Value)>) -> bool {
+        match *self {
+            Expression::Implies(ref condition, ref consequence) => {
+                !condition.holds(state, values) || consequence.holds(state, values)
+            },
+            Expression::Or(ref left, ref right) => left.holds(state, values) || right.holds(state, values),
+            Expression::And(ref left, ref right) => left.holds(state, values) && right.holds(state, values),
+            Expression::Not(ref inner) => !inner.holds(state, values),
+            Expression::Holds(ref operand) => match operand.value(state, values) {
+                Value::Null | Value::Bool(false) => false,
+                _ => true,
+            },
+            Expression::Compare(ref left, comparison, ref right) => {
+                let left = left.value(state, values);
+                let right = right.value(state, values);
+                compare(&left, comparison, &right)
+            },
+        }
+    }
+}
+
+impl Operand {
+    /// The value of the operand in `state`, which is also noted in `values` unless it is a
+    /// literal. A missing path is null, a path with wildcards is the array of what it matched.
+    fn value(&self, state: &Value, values: &mut Vec<(String, Value)>) -> Value {
+        let (text, value) = match *self {
+            Operand::Literal(ref value) => return value.clone(),
+            Operand::Path(ref text, ref path) => {
+                let value = if path.has_wildcard() {
+                    Value::Array(path.evaluate(state).into_iter().cloned().collect())
+                } else {
+                    path.evaluate_one(state).cloned().unwrap_or(Value::Null)
+                };
+                (text, value)
+            },
+            Operand::Aggregate(ref text, aggregate, ref path) => {
+                let mut matched = path.evaluate(state);
+                if let (false, Some(&Value::Array(ref items))) = (path.has_wildcard(), matched.first().cloned()) {
+                    matched = items.iter().collect();
+                }
+                (text, aggregate.apply(&matched))
+            },
+        };
+
+        if !values.iter().any(|&(ref noted, _)| noted == text) {
+            values.push((text.clone(), value.clone()));
+        }
+        value
+    }
+}
+
+impl Aggregate {
+    fn apply(&self, values: &[&Value]) -> Value {
+        let numbers = values.iter().filter_map(|value| value.as_f64());
+
+        match *self {
+            Aggregate::Count => Value::from(values.len()),
+            Aggregate::Sum => number(numbers.sum()),
+            Aggregate::Min => numbers.fold(None, |min: Option<f64>, number| Some(min.map_or(number, |min| min.min(number))))
+                .map_or(Value::Null, number),
+            Aggregate::Max => numbers.fold(None, |max: Option<f64>, number| Some(max.map_or(number, |max| max.max(number))))
+                .map_or(Value::Null, number),
+        }
+    }
+}
+
+fn number(value: f64) -> Value {
+    serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+fn compare(left: &Value, comparison: Comparison, right: &Value) -> bool {
+    if let (Some(left), Some(right)) = (left.as_f64(), right.as_f64()) {
+        let equal = (left - right).abs() <= EPSILON * f64::max(1.0, f64::max(left.abs(), right.abs()));
+        return match comparison {
+            Comparison::Equal => equal,
+            Comparison::NotEqual => !equal,
+            Comparison::Less => left < right && !equal,
+            Comparison::LessOrEqual => left < right || equal,
+            Comparison::Greater => left > right && !equal,
+            Comparison::GreaterOrEqual => left > right || equal,
+        };
+    }
+
+    match (comparison, left, right) {
+        (Comparison::Equal, _, _) => left == right,
+        (Comparison::NotEqual, _, _) => left != right,
+        (comparison, &Value::String(ref left), &Value::String(ref right)) => match comparison {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            _ => left >= right,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invariant(rule: &str) -> Invariant {
+        Invariant::new(&InvariantConfig { name: String::from("test"), state_type: None, rule: String::from(rule) }).unwrap()
+    }
+
+    fn error(rule: &str) -> String {
+        Invariant::new(&InvariantConfig { name: String::from("test"), state_type: None, rule: String::from(rule) })
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn compares_a_path_with_an_aggregate() {
+        let rule = invariant("cart.total == sum(cart.items[*].price)");
+        let items = json!([{"price": 0.1}, {"price": 0.2}]);
+
+        assert_eq!(rule.check(&json!({"cart": {"total": 0.3, "items": items}})), None);
+
+        let violation = rule.check(&json!({"cart": {"total": 4, "items": [{"price": 2}, {"price": 3}]}})).unwrap();
+        assert_eq!(violation.values, vec![
+            (String::from("cart.total"), json!(4)),
+            (String::from("sum(cart.items[*].price)"), json!(5.0)),
+        ]);
+        assert_eq!(violation.to_string(), "test: cart.total == sum(cart.items[*].price) (cart.total = 4, sum(cart.items[*].price) = 5.0)");
+    }
+
+    #[test]
+    fn checks_the_consequence_only_when_the_guard_holds() {
+        let rule = invariant("auth.loggedIn == true => auth.token != null");
+
+        assert_eq!(rule.check(&json!({"auth": {"loggedIn": false}})), None);
+        assert_eq!(rule.check(&json!({"auth": {"loggedIn": true, "token": "t"}})), None);
+        assert!(rule.check(&json!({"auth": {"loggedIn": true}})).is_some());
+    }
+
+    #[test]
+    fn combines_conditions_and_counts() {
+        let rule = invariant("count(todos[*]) <= 2 && user.name");
+
+        assert_eq!(rule.check(&json!({"todos": [1, 2], "user": {"name": "a"}})), None);
+        assert!(rule.check(&json!({"todos": [1, 2, 3], "user": {"name": "a"}})).is_some());
+        assert!(rule.check(&json!({"todos": [], "user": {}})).is_some());
+        assert_eq!(invariant("!(a || b)").check(&json!({"a": false})), None);
+        assert_eq!(invariant("max(values) > min(values)").check(&json!({"values": [3, 1, 2]})), None);
+        assert_eq!(invariant("name < \"b\"").check(&json!({"name": "a"})), None);
+    }
+
+    #[test]
+    fn reports_rules_that_do_not_parse() {
+        assert!(error("name == \"unterminated").contains("unterminated string"));
+        assert!(error("(a == 1").contains("missing )"));
+        assert!(error("a == 1 b").contains("unexpected"));
+        assert!(error("a ==").contains("expected a value"));
+        assert!(error("sum(1)").contains("expected a path"));
+        assert!(error("a[0 == 1").contains("unmatched ["));
+    }
+
+    #[test]
+    fn only_checks_states_of_its_type() {
+        let invariants = vec![Invariant::new(&InvariantConfig {
+            name: String::from("positive"),
+            state_type: Some(String::from("Counter")),
+            rule: String::from("count >= 0"),
+        }).unwrap()];
+
+        assert_eq!(check_all(&invariants, "Counter", "{\"count\": -1}").len(), 1);
+        assert!(check_all(&invariants, "Other", "{\"count\": -1}").is_empty());
+        assert!(check_all(&invariants, "Counter", "not json").is_empty());
+    }
+}
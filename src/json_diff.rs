@@ -0,0 +1,89 @@
+use serde_json::Value;
+use json_path;
+
+/// A difference between two json documents, at a json pointer.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Change {
+    Added { path: String, value: Value },
+    Removed { path: String, value: Value },
+    Changed { path: String, from: Value, to: Value },
+}
+
+/// The changes turning `before` into `after`, in document order. Objects are compared key by key
+/// and arrays item by item; anything else that differs is one change.
+pub fn diff(before: &Value, after: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    collect_changes(String::new(), before, after, &mut changes);
+    changes
+}
+
+fn collect_changes(path: String, before: &Value, after: &Value, changes: &mut Vec<Change>) {
+    match (before, after) {
+        (&Value::Object(ref before_fields), &Value::Object(ref after_fields)) => {
+            for (key, before_value) in before_fields {
+                let child_path = json_path::child(&path, key);
+                match after_fields.get(key) {
+                    Some(after_value) => collect_changes(child_path, before_value, after_value, changes),
+                    None => changes.push(Change::Removed { path: child_path, value: before_value.clone() }),
+                };
+            }
+            for (key, after_value) in after_fields {
+                if !before_fields.contains_key(key) {
+                    changes.push(Change::Added { path: json_path::child(&path, key), value: after_value.clone() });
+                }
+            }
+        },
+        (&Value::Array(ref before_items), &Value::Array(ref after_items)) => {
+            for (index, before_value) in before_items.iter().enumerate() {
+                let child_path = json_path::child(&path, &index.to_string());
+                match after_items.get(index) {
+                    Some(after_value) => collect_changes(child_path, before_value, after_value, changes),
+                    None => changes.push(Change::Removed { path: child_path, value: before_value.clone() }),
+                };
+            }
+            for (index, after_value) in after_items.iter().enumerate().skip(before_items.len()) {
+                changes.push(Change::Added { path: json_path::child(&path, &index.to_string()), value: after_value.clone() });
+            }
+        },
+        _ => {
+            if before != after {
+                changes.push(Change::Changed { path: path, from: before.clone(), to: after.clone() });
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_documents_have_no_changes() {
+        let document = json!({"a": [1, {"b": null}], "c": "d"});
+        assert!(diff(&document, &document.clone()).is_empty());
+    }
+
+    #[test]
+    fn reports_changes_by_json_pointer() {
+        let before = json!({"count": 1, "todos": ["a", "b"], "gone": true});
+        let after = json!({"count": 2, "todos": ["a"], "new": {"x": 1}});
+
+        assert_eq!(diff(&before, &after), vec![
+            Change::Changed { path: String::from("/count"), from: json!(1), to: json!(2) },
+            Change::Removed { path: String::from("/gone"), value: json!(true) },
+            Change::Removed { path: String::from("/todos/1"), value: json!("b") },
+            Change::Added { path: String::from("/new"), value: json!({"x": 1}) },
+        ]);
+    }
+
+    #[test]
+    fn escapes_keys_and_compares_differing_types_whole() {
+        assert_eq!(diff(&json!({"a/b": 1}), &json!({"a/b": [1]})), vec![
+            Change::Changed { path: String::from("/a~1b"), from: json!(1), to: json!([1]) },
+        ]);
+        assert_eq!(diff(&json!(1), &json!(2)), vec![
+            Change::Changed { path: String::new(), from: json!(1), to: json!(2) },
+        ]);
+    }
+}
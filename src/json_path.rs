@@ -0,0 +1,125 @@
+use serde_json::Value;
+
+/// Appends `key` to the json pointer `path`, escaping it as described in RFC 6901.
+pub fn child(path: &str, key: &str) -> String {
+    let escaped = key.replace("~", "~0").replace("/", "~1");
+    format!("{}/{}", path, escaped)
+}
+
+/// The number of segments in a json pointer, the root being at depth 0.
+pub fn depth(path: &str) -> usize {
+    path.matches('/').count()
+}
+
+/// Collects the pointers of every array and object in `value` shallower than `max_depth`.
+pub fn container_paths(value: &Value, max_depth: Option<usize>) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_container_paths(value, String::new(), max_depth, &mut paths);
+    paths
+}
+
+fn collect_container_paths(value: &Value, path: String, max_depth: Option<usize>, paths: &mut Vec<String>) {
+    if let Some(max_depth) = max_depth {
+        if depth(&path) >= max_depth {
+            return;
+        }
+    }
+
+    match *value {
+        Value::Array(ref items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_container_paths(item, child(&path, &index.to_string()), max_depth, paths);
+            }
+            paths.push(path);
+        },
+        Value::Object(ref items) => {
+            for (key, item) in items.iter() {
+                collect_container_paths(item, child(&path, key), max_depth, paths);
+            }
+            paths.push(path);
+        },
+        _ => (),
+    }
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// Splits a json pointer into the pointer of its parent and its own, unescaped, key.
+pub fn split_last(path: &str) -> Option<(&str, String)> {
+    path.rfind('/').map(|index| (&path[..index], unescape(&path[index + 1..])))
+}
+
+/// Replaces the value at `path`, returning whether it existed.
+pub fn set(document: &mut Value, path: &str, value: Value) -> bool {
+    match document.pointer_mut(path) {
+        Some(target) => {
+            *target = value;
+            true
+        },
+        None => false,
+    }
+}
+
+/// Removes the value at `path` from its parent array or object, returning whether it existed.
+pub fn remove(document: &mut Value, path: &str) -> bool {
+    let (parent, key) = match split_last(path) {
+        Some(split) => split,
+        None => return false,
+    };
+
+    match document.pointer_mut(parent) {
+        Some(&mut Value::Object(ref mut items)) => items.remove(&key).is_some(),
+        Some(&mut Value::Array(ref mut items)) => {
+            match key.parse::<usize>() {
+                Ok(index) if index < items.len() => {
+                    items.remove(index);
+                    true
+                },
+                _ => false,
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Appends a null to the array at `path`, or adds `key` with a null value to the object at
+/// `path`. Objects need a key that is not taken yet.
+pub fn add_child(document: &mut Value, path: &str, key: Option<&str>) -> bool {
+    match (document.pointer_mut(path), key) {
+        (Some(&mut Value::Array(ref mut items)), _) => {
+            items.push(Value::Null);
+            true
+        },
+        (Some(&mut Value::Object(ref mut items)), Some(key)) if !items.contains_key(key) => {
+            items.insert(String::from(key), Value::Null);
+            true
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_items_to_arrays_and_named_keys_to_objects() {
+        let mut document = json!({"todos": [], "user": {"name": "a"}});
+
+        assert!(add_child(&mut document, "/todos", None));
+        assert!(add_child(&mut document, "/user", Some("age")));
+        assert_eq!(document, json!({"todos": [null], "user": {"name": "a", "age": null}}));
+    }
+
+    #[test]
+    fn leaves_taken_keys_and_leaves_alone() {
+        let mut document = json!({"user": {"name": "a"}});
+
+        assert!(!add_child(&mut document, "/user", Some("name")));
+        assert!(!add_child(&mut document, "/user", None));
+        assert!(!add_child(&mut document, "/user/name", Some("x")));
+        assert_eq!(document, json!({"user": {"name": "a"}}));
+    }
+}
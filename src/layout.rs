@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind};
+use serde_json;
+
+/// The window size and the positions of the draggable dividers, kept between runs.
+///
+/// Divider positions are fractions of the space they split, so they carry over between window
+/// sizes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Layout {
+    pub window_width: u32,
+    pub window_height: u32,
+    /// The share of the window width taken by the action list.
+    pub list_fraction: f64,
+    /// The share of the inspector height taken by the amplitude, the state gets the rest.
+    pub payload_fraction: f64,
+    /// The share of the right column taken by the chart, when one is open.
+    pub chart_fraction: f64,
+}
+
+impl Default for Layout {
+    fn default() -> Layout {
+        Layout {
+            window_width: 1024,
+            window_height: 768,
+            list_fraction: 0.25,
+            payload_fraction: 0.35,
+            chart_fraction: 0.3,
+        }
+    }
+}
+
+/// Loads the layout saved by the previous run, using the defaults when there is none.
+pub fn load(path: &str) -> Result<Layout, Error> {
+    match File::open(path) {
+        Ok(file) => Ok(serde_json::from_reader(file)?),
+        Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(Layout::default()),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn save(layout: &Layout, path: &str) -> Result<(), Error> {
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, layout)?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Collects the actions and states that clients report over tcp, and keeps their history.
+//!
+//! `collector::start` runs the ingestion server and the state store, and hands out every new
+//! `state::State`. The window, the terminal frontend and the headless recorder all consume those
+//! states; the protocol lives in `message_parser`.
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[cfg(unix)]
+extern crate libc;
+
+pub mod tcp;
+pub mod actions;
+pub mod message_parser;
+pub mod state;
+pub mod json_path;
+pub mod path_expression;
+pub mod json_diff;
+pub mod comparison;
+pub mod timing;
+pub mod invariants;
+pub mod schema;
+pub mod growth;
+pub mod config;
+pub mod layout;
+pub mod session;
+pub mod collector;
+pub mod client;
+pub mod cli;
+pub mod headless;
+/// Only built for unix terminals.
+#[cfg(unix)]
+pub mod tui;
+pub mod query;
@@ -1,40 +1,51 @@
-use std::sync::mpsc::{channel};
-use std::thread;
+use std::env;
+use std::process;
 
-#[macro_use]
-extern crate serde_derive;
-extern crate serde;
 extern crate serde_json;
+extern crate vision_quest;
 
 #[macro_use]
 extern crate conrod;
 #[macro_use]
 extern crate conrod_derive;
 
-mod tcp;
-mod actions;
-mod message_parser;
-mod state;
+use vision_quest::{actions, state, schema, growth, timing, json_path, config, layout, cli, collector, headless};
+#[cfg(unix)]
+use vision_quest::tui;
+
 mod ui;
 
 fn main() {
-    let (ui_tx, ui_rx) = channel();
-
-    thread::spawn(move || {
-        let (tx, rx) = tcp::listen().expect("failed to make tcp connection");
-        let mut application_state = state::State::new(tx);
-
-        loop {
-            let action = rx.recv().expect("error receiving msg");
-            application_state = actions::run(action, application_state).expect("Failed to run action");
-            ui_tx.send(application_state.clone()).expect("Failed to send application state to the ui");
-
-            match application_state.status {
-                state::Status::ShuttingDown => break,
-                _ => (),
-            };
-        }
+    let options = cli::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, cli::USAGE);
+        process::exit(2);
     });
-
-    ui::run(ui_rx);
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    #[cfg(unix)]
+    {
+        if let Some((ref expected, ref actual)) = options.compare {
+            process::exit(tui::compare(expected, actual));
+        }
+    }
+
+    let config = config::load().expect("failed to load config");
+    let idle_timeout = options.idle_timeout.unwrap_or(config.idle_timeout);
+    if options.headless {
+        let updates = collector::start_headless(config).expect("failed to make tcp connection");
+        process::exit(headless::record(updates, options, idle_timeout));
+    }
+
+    let windowed = !options.tui;
+    let states = collector::start(config, windowed).expect("failed to make tcp connection");
+    #[cfg(unix)]
+    {
+        if options.tui {
+            return tui::run(states);
+        }
+    }
+    ui::run(states);
 }
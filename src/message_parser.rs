@@ -1,6 +1,6 @@
 use std::io::Error;
 use actions::{Message, Action};
-use state::{HistoryState, HistoryItem, HistoryAction};
+use state::{HistoryState, HistoryItem, HistoryAction, timestamp_now};
 
 extern crate serde_json;
 
@@ -17,6 +17,13 @@ struct HistoryItemDto {
     Amplitude: String,
     StateType: String,
     State: String,
+    Timestamp: Option<u64>,
+    #[serde(default)]
+    Id: Option<String>,
+    #[serde(default)]
+    CausedBy: Option<String>,
+    #[serde(default)]
+    Duration: Option<f64>,
 }
 
 impl Payload {
@@ -30,7 +37,10 @@ impl Payload {
                     content.Invocation.clone(),
                     content.Amplitude.clone()
                 );
-                let history_item = HistoryItem::new(history_action, history_state);
+                let mut history_item = HistoryItem::new(history_action, history_state, content.Timestamp, timestamp_now());
+                history_item.id = content.Id.clone();
+                history_item.caused_by = content.CausedBy.clone();
+                history_item.duration = content.Duration;
                 Ok(Message::HistoryItem(history_item))
             },
         }
@@ -45,7 +55,7 @@ struct Packet {
 }
 
 pub fn parse(raw : String, sender : String) -> Result<Action, Error> {
-    let packet : Packet = serde_json::from_str(&raw).expect("failed to parse");
+    let packet : Packet = serde_json::from_str(&raw)?;
     let message = packet.Payload.to_message()?;
 
     Ok(Action {
@@ -55,3 +65,36 @@ pub fn parse(raw : String, sender : String) -> Result<Action, Error> {
         sender: sender.clone(),
     })
 }
+
+/// Wraps `item` in the packet a client reports an action with, the inverse of `parse` for
+/// history items.
+pub fn serialize_history_item(item : &HistoryItem) -> Result<String, Error> {
+    let packet = Packet {
+        Domain: String::from("item"),
+        Invocation: String::from("add"),
+        Payload: Payload::HistoryItem(HistoryItemDto {
+            Domain: item.action.domain.clone(),
+            Invocation: item.action.invocation.clone(),
+            Amplitude: item.action.amplitude.clone(),
+            StateType: item.state.id.clone(),
+            State: item.state.content.clone(),
+            Timestamp: item.sent_at,
+            Id: item.id.clone(),
+            CausedBy: item.caused_by.clone(),
+            Duration: item.duration,
+        }),
+    };
+
+    Ok(serde_json::to_string(&packet)?)
+}
+
+/// Wraps `content` in a packet to send to a client, the inverse of `parse`.
+pub fn serialize(domain : String, invocation : String, content : String) -> Result<String, Error> {
+    let packet = Packet {
+        Domain: domain,
+        Invocation: invocation,
+        Payload: Payload::Json(content),
+    };
+
+    Ok(serde_json::to_string(&packet)?)
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use serde_json::Value;
+
+/// One step of a path expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+    /// Every item of an array or every value of an object.
+    Wildcard,
+}
+
+/// Points at values in a json document, written like `cart.items[*].price` or `todos[0]`.
+///
+/// Keys are separated by dots, array indices go in brackets, and `*` or `[*]` stands for every
+/// child. A json pointer such as `/cart/items/0` is accepted as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathExpression {
+    segments: Vec<Segment>,
+}
+
+impl PathExpression {
+    pub fn parse(text: &str) -> Result<PathExpression, Error> {
+        let text = text.trim();
+        if let Some(pointer) = text.strip_prefix('/') {
+            let segments = pointer.split('/')
+                .map(|key| Segment::Key(key.replace("~1", "/").replace("~0", "~")))
+                .collect();
+            return Ok(PathExpression { segments: segments });
+        }
+
+        let invalid = |reason: &str| Error::new(ErrorKind::InvalidInput, format!("{} in path {:?}", reason, text));
+        let mut segments = Vec::new();
+        let mut key = String::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(character) = chars.next() {
+            match character {
+                '.' => {
+                    if !key.is_empty() {
+                        segments.push(key_segment(&key));
+                        key.clear();
+                    }
+                },
+                '[' => {
+                    if !key.is_empty() {
+                        segments.push(key_segment(&key));
+                        key.clear();
+                    }
+                    let mut index = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(character) => index.push(character),
+                            None => return Err(invalid("unmatched [")),
+                        };
+                    }
+                    match index.trim() {
+                        "*" => segments.push(Segment::Wildcard),
+                        index => segments.push(Segment::Index(index.parse().map_err(|_| invalid("bad index"))?)),
+                    };
+                },
+                ']' => return Err(invalid("unmatched ]")),
+                character => key.push(character),
+            };
+        }
+        if !key.is_empty() {
+            segments.push(key_segment(&key));
+        }
+
+        Ok(PathExpression { segments: segments })
+    }
+
+    /// Whether the expression can point at more than one value.
+    pub fn has_wildcard(&self) -> bool {
+        self.segments.contains(&Segment::Wildcard)
+    }
+
+    /// The values the expression points at in `document`, in document order.
+    pub fn evaluate<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        let mut values = vec![document];
+
+        for segment in &self.segments {
+            values = values.into_iter()
+                .flat_map(|value| children(value, segment))
+                .collect();
+        }
+
+        values
+    }
+
+    /// The single value the expression points at, if it points at exactly one.
+    pub fn evaluate_one<'a>(&self, document: &'a Value) -> Option<&'a Value> {
+        let values = self.evaluate(document);
+        if values.len() == 1 { Some(values[0]) } else { None }
+    }
+}
+
+fn key_segment(key: &str) -> Segment {
+    match key {
+        "*" => Segment::Wildcard,
+        key => Segment::Key(String::from(key)),
+    }
+}
+
+fn children<'a>(value: &'a Value, segment: &Segment) -> Vec<&'a Value> {
+    match (segment, value) {
+        (&Segment::Key(ref key), &Value::Object(ref fields)) => fields.get(key).into_iter().collect(),
+        (&Segment::Key(ref key), &Value::Array(ref items)) => key.parse::<usize>().ok()
+            .and_then(|index| items.get(index))
+            .into_iter()
+            .collect(),
+        (&Segment::Index(index), &Value::Array(ref items)) => items.get(index).into_iter().collect(),
+        (&Segment::Wildcard, &Value::Array(ref items)) => items.iter().collect(),
+        (&Segment::Wildcard, &Value::Object(ref fields)) => fields.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl fmt::Display for PathExpression {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (position, segment) in self.segments.iter().enumerate() {
+            match *segment {
+                Segment::Key(ref key) if position == 0 => write!(formatter, "{}", key)?,
+                Segment::Key(ref key) => write!(formatter, ".{}", key)?,
+                Segment::Index(index) => write!(formatter, "[{}]", index)?,
+                Segment::Wildcard => write!(formatter, "[*]")?,
+            };
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> PathExpression {
+        PathExpression::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_keys_indices_and_wildcards() {
+        let expression = parse("cart.items[*].price");
+        assert_eq!(expression.segments, vec![
+            Segment::Key(String::from("cart")),
+            Segment::Key(String::from("items")),
+            Segment::Wildcard,
+            Segment::Key(String::from("price")),
+        ]);
+        assert_eq!(parse("todos[0]").segments, vec![Segment::Key(String::from("todos")), Segment::Index(0)]);
+        assert_eq!(parse("todos.*").segments, vec![Segment::Key(String::from("todos")), Segment::Wildcard]);
+    }
+
+    #[test]
+    fn parses_json_pointers() {
+        assert_eq!(parse("/a~1b/m~0n/0").segments, vec![
+            Segment::Key(String::from("a/b")),
+            Segment::Key(String::from("m~n")),
+            Segment::Key(String::from("0")),
+        ]);
+    }
+
+    #[test]
+    fn rejects_unmatched_brackets_and_bad_indices() {
+        assert!(PathExpression::parse("a[0").unwrap_err().to_string().contains("unmatched ["));
+        assert!(PathExpression::parse("a]").unwrap_err().to_string().contains("unmatched ]"));
+        assert!(PathExpression::parse("a[x]").unwrap_err().to_string().contains("bad index"));
+    }
+
+    #[test]
+    fn evaluates_against_a_document() {
+        let document = json!({"cart": {"items": [{"price": 2}, {"price": 3}]}});
+        assert_eq!(parse("cart.items[*].price").evaluate(&document), vec![&json!(2), &json!(3)]);
+        assert_eq!(parse("cart.items.1.price").evaluate_one(&document), Some(&json!(3)));
+        assert_eq!(parse("cart.items[5]").evaluate_one(&document), None);
+        assert!(parse("cart.items[*]").has_wildcard());
+    }
+
+    #[test]
+    fn displays_in_the_dotted_form() {
+        assert_eq!(parse("/cart/items").to_string(), "cart.items");
+        assert_eq!(parse("cart.items[*].price").to_string(), "cart.items[*].price");
+        assert_eq!(parse("todos[3]").to_string(), "todos[3]");
+    }
+}
@@ -0,0 +1,390 @@
+use std::io::{Error, ErrorKind, Write};
+use serde_json::{self, Value};
+use json_diff::{diff, Change};
+use comparison::{self, Step};
+use path_expression::PathExpression;
+use session;
+use timing::{self, Summary};
+use state::HistoryItem;
+
+pub const USAGE: &'static str = "usage: vision_quest_query COMMAND SESSION [ARGUMENTS] [OPTIONS]
+
+commands:
+  clients                 list the clients and how many items each recorded
+  actions                 list the actions with their index
+  state INDEX             print the state after the action at INDEX
+  diff INDEX INDEX        print what changed in the state between two actions
+  eval PATH               print the value at PATH after every action, e.g. cart.items[*].price
+  stats                   print how often each action happened and how long it took
+  compare OTHER           align the actions of SESSION and OTHER, and print where they diverge
+                          and where the states after matching actions differ
+
+options:
+  --client CLIENT         only look at the items of CLIENT; indices count within its items,
+                          not supported by compare
+  --domain DOMAIN         only list actions of DOMAIN, for the actions command
+  --invocation NAME       only list actions with this invocation, for the actions command
+  --json                  print json instead of plain text";
+
+/// What was asked for on the command line.
+#[derive(Default)]
+struct Query {
+    command: String,
+    session: String,
+    arguments: Vec<String>,
+    client: Option<String>,
+    domain: Option<String>,
+    invocation: Option<String>,
+    json: bool,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Query, Error> {
+    let mut query = Query::default();
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| invalid(format!("{} needs a value", name)));
+        match arg.as_str() {
+            "--client" => query.client = Some(value(&arg)?),
+            "--domain" => query.domain = Some(value(&arg)?),
+            "--invocation" => query.invocation = Some(value(&arg)?),
+            "--json" => query.json = true,
+            _ if arg.starts_with("--") => return Err(invalid(format!("unknown option {}", arg))),
+            _ => positional.push(arg),
+        };
+    }
+
+    let mut positional = positional.into_iter();
+    query.command = positional.next().ok_or_else(|| invalid(String::from("no command given")))?;
+    query.session = positional.next().ok_or_else(|| invalid(String::from("no session given")))?;
+    query.arguments = positional.collect();
+
+    // Options a command would ignore are rejected, so a filter is never silently left out.
+    if (query.domain.is_some() || query.invocation.is_some()) && query.command != "actions" {
+        return Err(invalid(format!("--domain and --invocation only filter actions, not {}", query.command)));
+    }
+    if query.client.is_some() && query.command == "compare" {
+        return Err(invalid(String::from("--client is not supported by compare")));
+    }
+
+    Ok(query)
+}
+
+/// Runs the query described by `args`, which exclude the program name, writing the answer to
+/// `out`.
+pub fn run<I: Iterator<Item = String>, W: Write>(args: I, out: &mut W) -> Result<(), Error> {
+    let query = parse(args)?;
+    if query.command == "compare" {
+        return compare(&query, out);
+    }
+
+    let items: Vec<(String, HistoryItem)> = session::load(&query.session)?.into_iter()
+        .filter(|&(ref client, _)| query.client.as_ref().map_or(true, |wanted| wanted == client))
+        .collect();
+
+    match query.command.as_str() {
+        "clients" => clients(&query, &items, out),
+        "actions" => actions(&query, &items, out),
+        "state" => state(&query, &items, out),
+        "diff" => state_diff(&query, &items, out),
+        "eval" => eval(&query, &items, out),
+        "stats" => stats(&query, &items, out),
+        command => Err(invalid(format!("unknown command {}", command))),
+    }
+}
+
+/// Parses a recorded json string, keeping text that is not json as a string.
+fn json(content: &str) -> Value {
+    serde_json::from_str(content).unwrap_or_else(|_| Value::String(String::from(content)))
+}
+
+fn argument(query: &Query, position: usize, name: &str) -> Result<String, Error> {
+    query.arguments.get(position)
+        .cloned()
+        .ok_or_else(|| invalid(format!("{} needs {}", query.command, name)))
+}
+
+fn item_at<'a>(query: &Query, items: &'a [(String, HistoryItem)], position: usize) -> Result<(usize, &'a (String, HistoryItem)), Error> {
+    let index = argument(query, position, "an index")?;
+    let index = index.parse::<usize>().map_err(|_| invalid(format!("{} is not an index", index)))?;
+
+    items.get(index)
+        .map(|item| (index, item))
+        .ok_or_else(|| invalid(format!("there is no item {}, the session has {}", index, items.len())))
+}
+
+fn write_json<W: Write>(out: &mut W, value: &Value) -> Result<(), Error> {
+    writeln!(out, "{}", serde_json::to_string_pretty(value)?)
+}
+
+fn clients<W: Write>(query: &Query, items: &[(String, HistoryItem)], out: &mut W) -> Result<(), Error> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for &(ref client, _) in items {
+        match counts.iter().position(|&(ref counted, _)| counted == client) {
+            Some(position) => counts[position].1 += 1,
+            None => counts.push((client.clone(), 1)),
+        };
+    }
+
+    if query.json {
+        let clients: Vec<Value> = counts.iter()
+            .map(|&(ref client, amount)| json!({ "client": client, "items": amount }))
+            .collect();
+        return write_json(out, &Value::Array(clients));
+    }
+
+    for (client, amount) in counts {
+        writeln!(out, "{}  {} items", client, amount)?;
+    }
+    Ok(())
+}
+
+fn actions<W: Write>(query: &Query, items: &[(String, HistoryItem)], out: &mut W) -> Result<(), Error> {
+    let matching = items.iter().enumerate().filter(|&(_, &(_, ref item))| {
+        query.domain.as_ref().map_or(true, |domain| *domain == item.action.domain)
+            && query.invocation.as_ref().map_or(true, |invocation| *invocation == item.action.invocation)
+    });
+
+    if query.json {
+        let actions: Vec<Value> = matching
+            .map(|(index, &(ref client, ref item))| json!({
+                "index": index,
+                "client": client,
+                "domain": item.action.domain,
+                "invocation": item.action.invocation,
+                "amplitude": json(&item.action.amplitude),
+                "timestamp": item.timestamp(),
+            }))
+            .collect();
+        return write_json(out, &Value::Array(actions));
+    }
+
+    for (index, &(ref client, ref item)) in matching {
+        writeln!(out, "{}  {}  {}:{}  {}", index, client, item.action.domain, item.action.invocation, item.action.amplitude)?;
+    }
+    Ok(())
+}
+
+fn state<W: Write>(query: &Query, items: &[(String, HistoryItem)], out: &mut W) -> Result<(), Error> {
+    let (index, &(ref client, ref item)) = item_at(query, items, 0)?;
+    let state = json(&item.state.content);
+
+    if query.json {
+        return write_json(out, &json!({
+            "index": index,
+            "client": client,
+            "stateType": item.state.id,
+            "state": state,
+        }));
+    }
+
+    write_json(out, &state)
+}
+
+fn state_diff<W: Write>(query: &Query, items: &[(String, HistoryItem)], out: &mut W) -> Result<(), Error> {
+    let (_, &(_, ref before)) = item_at(query, items, 0)?;
+    let (_, &(_, ref after)) = item_at(query, items, 1)?;
+    let changes = diff(&json(&before.state.content), &json(&after.state.content));
+
+    if query.json {
+        return write_json(out, &serde_json::to_value(&changes)?);
+    }
+
+    for change in changes {
+        match change {
+            Change::Added { path, value } => writeln!(out, "+ {}: {}", path, value)?,
+            Change::Removed { path, value } => writeln!(out, "- {}: {}", path, value)?,
+            Change::Changed { path, from, to } => writeln!(out, "~ {}: {} -> {}", path, from, to)?,
+        };
+    }
+    Ok(())
+}
+
+fn stats<W: Write>(query: &Query, items: &[(String, HistoryItem)], out: &mut W) -> Result<(), Error> {
+    let history: Vec<HistoryItem> = items.iter().map(|&(_, ref item)| item.clone()).collect();
+    let timings = timing::stats(&history);
+
+    if query.json {
+        return write_json(out, &serde_json::to_value(&timings)?);
+    }
+
+    let format = |summary: &Option<Summary>, pick: fn(&Summary) -> f64| {
+        summary.as_ref().map_or(String::from("-"), |summary| timing::format_ms(pick(summary)))
+    };
+    writeln!(out, "{:<32}{:>8}{:>12}{:>12}{:>12}{:>12}", "action", "count", "mean", "p95", "max", "mean gap")?;
+    for action in timings {
+        writeln!(out, "{:<32}{:>8}{:>12}{:>12}{:>12}{:>12}",
+            action.action,
+            action.count,
+            format(&action.duration, |summary| summary.mean),
+            format(&action.duration, |summary| summary.p95),
+            format(&action.duration, |summary| summary.max),
+            format(&action.interval, |summary| summary.mean),
+        )?;
+    }
+    Ok(())
+}
+
+/// Compares the session against another recording of the same scenario, failing when they
+/// differ so scripts can check against a golden recording.
+fn compare<W: Write>(query: &Query, out: &mut W) -> Result<(), Error> {
+    let other = argument(query, 0, "a second session")?;
+    let report = comparison::compare_sessions(session::load(&query.session)?, session::load(&other)?);
+
+    if query.json {
+        write_json(out, &serde_json::to_value(&report)?)?;
+    } else {
+        for client in &report.clients {
+            let expected = client.expected_client.as_ref().map_or("-", |id| id.as_str());
+            let actual = client.actual_client.as_ref().map_or("-", |id| id.as_str());
+            match client.divergence {
+                Some(ref divergence) => writeln!(out, "{} / {}: diverges at {} / {}", expected, actual, divergence.expected, divergence.actual)?,
+                None => writeln!(out, "{} / {}: same actions", expected, actual)?,
+            };
+            writeln!(out, "  {} missing, {} extra, {} differing states", client.missing, client.extra, client.differing_states)?;
+            if !client.aligned {
+                writeln!(out, "  too different to align, everything between the shared start and end is listed")?;
+            }
+
+            for step in client.steps.iter().filter(|step| !step.is_equal()) {
+                match *step {
+                    Step::Missing { expected, ref action } => writeln!(out, "- {} / -  {}", expected, action)?,
+                    Step::Extra { actual, ref action } => writeln!(out, "+ - / {}  {}", actual, action)?,
+                    Step::Matched { expected, actual, ref action, ref changes } => {
+                        writeln!(out, "~ {} / {}  {}", expected, actual, action)?;
+                        for change in changes {
+                            match *change {
+                                Change::Added { ref path, ref value } => writeln!(out, "    + {}: {}", path, value)?,
+                                Change::Removed { ref path, ref value } => writeln!(out, "    - {}: {}", path, value)?,
+                                Change::Changed { ref path, ref from, ref to } => writeln!(out, "    ~ {}: {} -> {}", path, from, to)?,
+                            };
+                        }
+                    },
+                };
+            }
+        }
+    }
+
+    if report.passed {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::Other, format!("{} and {} differ", query.session, other)))
+    }
+}
+
+fn eval<W: Write>(query: &Query, items: &[(String, HistoryItem)], out: &mut W) -> Result<(), Error> {
+    let path = PathExpression::parse(&argument(query, 0, "a path")?)?;
+    let mut results = Vec::new();
+
+    for (index, &(ref client, ref item)) in items.iter().enumerate() {
+        let state = json(&item.state.content);
+        let values: Vec<Value> = path.evaluate(&state).into_iter().cloned().collect();
+        if values.is_empty() {
+            continue;
+        }
+
+        if query.json {
+            results.push(json!({
+                "index": index,
+                "client": client,
+                "domain": item.action.domain,
+                "invocation": item.action.invocation,
+                "values": values,
+            }));
+        } else {
+            let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            writeln!(out, "{}  {}  {}:{}  {}", index, client, item.action.domain, item.action.invocation, values.join(", "))?;
+        }
+    }
+
+    if query.json {
+        write_json(out, &Value::Array(results))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::{self, File};
+    use state::{HistoryAction, HistoryState};
+
+    fn item(domain: &str, invocation: &str, state: &str, duration: Option<f64>) -> HistoryItem {
+        let action = HistoryAction::new(String::from(domain), String::from(invocation), String::from("{}"));
+        let mut item = HistoryItem::new(action, HistoryState::new(String::from("Cart"), String::from(state)), None, 0);
+        item.duration = duration;
+        item
+    }
+
+    /// A session of a shop and its api, written to a file named after `name`.
+    fn session(name: &str) -> String {
+        let path = env::temp_dir().join(format!("vision_quest_query_{}_{}.jsonl", name, ::std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        let items = vec![
+            ("shop", item("cart", "add", "{\"items\":[{\"price\":2}]}", Some(4.0))),
+            ("api", item("sync", "push", "{\"synced\":1}", None)),
+            ("shop", item("cart", "add", "{\"items\":[{\"price\":2},{\"price\":3}]}", Some(6.0))),
+            ("shop", item("cart", "clear", "{\"items\":[]}", Some(1.0))),
+        ];
+        for (client, item) in items {
+            session::write_item(&mut file, client, &item).unwrap();
+        }
+        path.to_string_lossy().into_owned()
+    }
+
+    fn query(args: &[&str]) -> Result<String, Error> {
+        let mut out = Vec::new();
+        run(args.iter().map(|arg| String::from(*arg)), &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn lists_clients_and_filtered_actions() {
+        let path = session("actions");
+
+        assert_eq!(query(&["clients", &path]).unwrap(), "shop  3 items\napi  1 items\n");
+        assert_eq!(query(&["actions", &path, "--invocation", "add"]).unwrap(), "0  shop  cart:add  {}\n2  shop  cart:add  {}\n");
+        assert_eq!(query(&["actions", &path, "--client", "api"]).unwrap(), "0  api  sync:push  {}\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_states_within_a_client() {
+        let path = session("states");
+
+        assert_eq!(query(&["state", &path, "1", "--client", "shop"]).unwrap(), "{\n  \"items\": [\n    {\n      \"price\": 2\n    },\n    {\n      \"price\": 3\n    }\n  ]\n}\n");
+        assert_eq!(query(&["diff", &path, "0", "2", "--client", "shop"]).unwrap(), "- /items/0: {\"price\":2}\n");
+        assert_eq!(query(&["eval", &path, "items[*].price"]).unwrap(), "0  shop  cart:add  2\n2  shop  cart:add  2, 3\n");
+        assert!(query(&["state", &path, "4"]).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn summarizes_timings_and_compares_sessions() {
+        let path = session("stats");
+
+        let stats: Value = serde_json::from_str(&query(&["stats", &path, "--json"]).unwrap()).unwrap();
+        assert_eq!(stats[0]["action"], json!("cart:add"));
+        assert_eq!(stats[0]["count"], json!(2));
+        assert_eq!(stats[0]["duration"]["max"], json!(6.0));
+        assert!(query(&["compare", &path, &path]).unwrap().contains("shop / shop: same actions"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_filters_a_command_would_ignore() {
+        for args in vec![
+            vec!["eval", "session.jsonl", "items", "--domain", "cart"],
+            vec!["stats", "session.jsonl", "--invocation", "add"],
+            vec!["state", "session.jsonl", "0", "--domain", "cart"],
+            vec!["compare", "session.jsonl", "other.jsonl", "--client", "shop"],
+        ] {
+            assert_eq!(query(&args).unwrap_err().kind(), ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use serde_json::{self, Value};
+use config::Config;
+use json_path;
+use state::HistoryItem;
+
+/// Where in a history item a value does not match its schema.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// `state` or `amplitude`.
+    pub scope: String,
+    /// The json pointer of the offending value.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(formatter, "{}: {}", self.scope, self.message)
+        } else {
+            write!(formatter, "{} {}: {}", self.scope, self.path, self.message)
+        }
+    }
+}
+
+/// The JSON Schemas states are checked against by `StateType`, and amplitudes by
+/// `domain:invocation`.
+///
+/// The common validation keywords are supported: `type`, `enum`, `const`, `properties`,
+/// `required`, `additionalProperties`, `items`, `minItems`, `maxItems`, `uniqueItems`, `minimum`,
+/// `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`, `minLength`, `maxLength`,
+/// `allOf`, `anyOf`, `oneOf`, `not` and local `$ref`s. Schemas using other validation keywords
+/// are reported when they are loaded, since values would pass them unchecked.
+#[derive(Debug, Clone, Default)]
+pub struct Schemas {
+    states: HashMap<String, Value>,
+    amplitudes: HashMap<String, Value>,
+}
+
+impl Schemas {
+    /// The schemas named in `config`, along with the errors of the ones that could not be read.
+    /// A schema is either written in the config itself or is the path of a json file.
+    pub fn load(config: &Config) -> (Schemas, Vec<String>) {
+        let mut errors = Vec::new();
+        let states = load_all(&config.state_schemas, &mut errors);
+        let amplitudes = load_all(&config.amplitude_schemas, &mut errors);
+
+        let schemas = Schemas {
+            states: states,
+            amplitudes: amplitudes,
+        };
+        (schemas, errors)
+    }
+
+    /// Validates the state of `item` by its type and the amplitude by its action. Content that is
+    /// not json is not checked.
+    pub fn check(&self, item: &HistoryItem) -> Vec<SchemaError> {
+        let mut errors = Vec::new();
+
+        if let Some(schema) = self.states.get(&item.state.id) {
+            errors.extend(check_content(schema, "state", &item.state.content));
+        }
+        let action = format!("{}:{}", item.action.domain, item.action.invocation);
+        if let Some(schema) = self.amplitudes.get(&action) {
+            errors.extend(check_content(schema, "amplitude", &item.action.amplitude));
+        }
+
+        errors
+    }
+}
+
+/// Reads the schemas in the order of their keys, so their errors are always reported in the same
+/// order.
+fn load_all(sources: &HashMap<String, Value>, errors: &mut Vec<String>) -> HashMap<String, Value> {
+    let mut schemas = HashMap::new();
+    let mut keys: Vec<&String> = sources.keys().collect();
+    keys.sort();
+
+    for key in keys {
+        let source = &sources[key];
+        let schema = match *source {
+            Value::String(ref path) => File::open(path)
+                .map_err(|err| err.to_string())
+                .and_then(|file| serde_json::from_reader(file).map_err(|err| err.to_string())),
+            ref schema => Ok(schema.clone()),
+        };
+
+        match schema {
+            Ok(schema) => {
+                let mut unsupported = Vec::new();
+                find_unsupported(&schema, String::new(), &mut unsupported);
+                for (path, keyword) in unsupported {
+                    errors.push(format!("schema for {}: {} at {:?} is not supported and would be ignored", key, keyword, path));
+                }
+                schemas.insert(key.clone(), schema);
+            },
+            Err(err) => errors.push(format!("schema for {}: {}", key, err)),
+        };
+    }
+
+    schemas
+}
+
+/// Validation keywords `validate` does not check.
+const UNSUPPORTED_KEYWORDS: &'static [&'static str] = &[
+    "pattern", "format", "patternProperties", "propertyNames", "minProperties", "maxProperties",
+    "dependencies", "dependentRequired", "dependentSchemas", "if", "then", "else",
+    "additionalItems", "prefixItems", "contains", "minContains", "maxContains",
+    "unevaluatedItems", "unevaluatedProperties",
+];
+
+/// Collects the json pointer and name of every unsupported keyword in `schema` and the schemas
+/// inside it.
+fn find_unsupported(schema: &Value, path: String, unsupported: &mut Vec<(String, String)>) {
+    let schema = match *schema {
+        Value::Object(ref schema) => schema,
+        _ => return,
+    };
+
+    for (keyword, value) in schema {
+        let child_path = json_path::child(&path, keyword);
+        if UNSUPPORTED_KEYWORDS.contains(&keyword.as_str()) {
+            unsupported.push((path.clone(), keyword.clone()));
+            continue;
+        }
+
+        match (keyword.as_str(), value) {
+            ("properties", &Value::Object(ref schemas))
+            | ("definitions", &Value::Object(ref schemas))
+            | ("$defs", &Value::Object(ref schemas)) => {
+                for (name, sub_schema) in schemas {
+                    find_unsupported(sub_schema, json_path::child(&child_path, name), unsupported);
+                }
+            },
+            ("items", &Value::Array(ref schemas))
+            | ("allOf", &Value::Array(ref schemas))
+            | ("anyOf", &Value::Array(ref schemas))
+            | ("oneOf", &Value::Array(ref schemas)) => {
+                for (index, sub_schema) in schemas.iter().enumerate() {
+                    find_unsupported(sub_schema, json_path::child(&child_path, &index.to_string()), unsupported);
+                }
+            },
+            ("items", sub_schema) | ("additionalProperties", sub_schema) | ("not", sub_schema) => {
+                find_unsupported(sub_schema, child_path, unsupported);
+            },
+            _ => (),
+        };
+    }
+}
+
+fn check_content(schema: &Value, scope: &str, content: &str) -> Vec<SchemaError> {
+    let value: Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(_) => return Vec::new(),
+    };
+
+    validate(schema, &value).into_iter()
+        .map(|(path, message)| SchemaError { scope: String::from(scope), path: path, message: message })
+        .collect()
+}
+
+/// The json pointers of the errors in `scope` and of every node containing one, so collapsed
+/// parents show that something inside them is wrong.
+pub fn highlighted_paths(errors: &[SchemaError], scope: &str) -> HashSet<String> {
+    let mut paths = HashSet::new();
+
+    for error in errors.iter().filter(|error| error.scope == scope) {
+        let mut path = error.path.as_str();
+        paths.insert(String::from(path));
+        while let Some(index) = path.rfind('/') {
+            path = &path[..index];
+            paths.insert(String::from(path));
+        }
+    }
+
+    paths
+}
+
+/// Validates `value` against `schema`, returning the json pointer and description of every
+/// mismatch.
+pub fn validate(schema: &Value, value: &Value) -> Vec<(String, String)> {
+    let mut errors = Vec::new();
+    Validator { root: schema }.validate(schema, value, String::new(), &[], &mut errors);
+    errors
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match *value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(ref number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, wanted: &str) -> bool {
+    let actual = type_name(value);
+    actual == wanted
+        || (wanted == "number" && actual == "integer")
+        || (wanted == "integer" && value.as_f64().map_or(false, |number| number.fract() == 0.0))
+}
+
+/// Decodes the `%XX` escapes of a uri fragment, or `None` when one is malformed or they do not
+/// make valid utf-8.
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let byte = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())?;
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+impl<'a> Validator<'a> {
+    /// The schema a local `$ref` points at. The fragment is percent-decoded before it is followed
+    /// as a json pointer, which unescapes `~0` and `~1`.
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        let pointer = percent_decode(reference.strip_prefix('#')?)?;
+        if pointer.is_empty() {
+            return Some(self.root);
+        }
+        self.root.pointer(&pointer)
+    }
+
+    fn is_valid(&self, schema: &Value, value: &Value, refs: &[String]) -> bool {
+        let mut errors = Vec::new();
+        self.validate(schema, value, String::new(), refs, &mut errors);
+        errors.is_empty()
+    }
+
+    /// `refs` holds the `$ref`s followed since the last step into `value`, so a reference that
+    /// comes back to itself without looking at a smaller value is reported instead of followed.
+    fn validate(&self, schema: &Value, value: &Value, path: String, refs: &[String], errors: &mut Vec<(String, String)>) {
+        let schema = match *schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                errors.push((path, String::from("no value is allowed here")));
+                return;
+            },
+            Value::Object(ref schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(|reference| reference.as_str()) {
+            if refs.iter().any(|followed| followed == reference) {
+                errors.push((path, format!("{} refers back to itself", reference)));
+                return;
+            }
+            let mut followed = refs.to_vec();
+            followed.push(String::from(reference));
+
+            match self.resolve(reference) {
+                Some(resolved) => self.validate(resolved, value, path, &followed, errors),
+                None => errors.push((path, format!("cannot resolve {}", reference))),
+            };
+            return;
+        }
+
+        if let Some(wanted) = schema.get("type") {
+            let allowed: Vec<&str> = match *wanted {
+                Value::String(ref name) => vec![name.as_str()],
+                Value::Array(ref names) => names.iter().filter_map(|name| name.as_str()).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.is_empty() && !allowed.iter().any(|name| has_type(value, name)) {
+                errors.push((path, format!("expected {}, found {}", allowed.join(" or "), type_name(value))));
+                return;
+            }
+        }
+
+        if let Some(options) = schema.get("enum").and_then(|options| options.as_array()) {
+            if !options.contains(value) {
+                errors.push((path.clone(), format!("{} is not one of {}", value, Value::Array(options.clone()))));
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if constant != value {
+                errors.push((path.clone(), format!("expected {}, found {}", constant, value)));
+            }
+        }
+
+        match *value {
+            Value::Object(ref fields) => self.validate_object(schema, fields, &path, errors),
+            Value::Array(ref items) => self.validate_array(schema, items, &path, errors),
+            Value::Number(ref number) => validate_number(schema, number.as_f64().unwrap_or(0.0), &path, errors),
+            Value::String(ref text) => validate_string(schema, text, &path, errors),
+            _ => (),
+        };
+
+        if let Some(schemas) = schema.get("allOf").and_then(|schemas| schemas.as_array()) {
+            for sub_schema in schemas {
+                self.validate(sub_schema, value, path.clone(), refs, errors);
+            }
+        }
+        if let Some(schemas) = schema.get("anyOf").and_then(|schemas| schemas.as_array()) {
+            if !schemas.iter().any(|sub_schema| self.is_valid(sub_schema, value, refs)) {
+                errors.push((path.clone(), String::from("matches none of the anyOf schemas")));
+            }
+        }
+        if let Some(schemas) = schema.get("oneOf").and_then(|schemas| schemas.as_array()) {
+            let matching = schemas.iter().filter(|sub_schema| self.is_valid(sub_schema, value, refs)).count();
+            if matching != 1 {
+                errors.push((path.clone(), format!("matches {} of the oneOf schemas instead of 1", matching)));
+            }
+        }
+        if let Some(sub_schema) = schema.get("not") {
+            if self.is_valid(sub_schema, value, refs) {
+                errors.push((path, String::from("matches the schema it must not match")));
+            }
+        }
+    }
+
+    fn validate_object(&self, schema: &serde_json::Map<String, Value>, fields: &serde_json::Map<String, Value>, path: &str, errors: &mut Vec<(String, String)>) {
+        if let Some(required) = schema.get("required").and_then(|required| required.as_array()) {
+            for key in required.iter().filter_map(|key| key.as_str()) {
+                if !fields.contains_key(key) {
+                    errors.push((String::from(path), format!("missing required property {}", key)));
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(|properties| properties.as_object());
+        for (key, field) in fields {
+            let child_path = json_path::child(path, key);
+            match (properties.and_then(|properties| properties.get(key)), schema.get("additionalProperties")) {
+                (Some(property), _) => self.validate(property, field, child_path, &[], errors),
+                (None, Some(&Value::Bool(false))) => errors.push((child_path, format!("property {} is not allowed", key))),
+                (None, Some(additional)) => self.validate(additional, field, child_path, &[], errors),
+                (None, None) => (),
+            };
+        }
+    }
+
+    fn validate_array(&self, schema: &serde_json::Map<String, Value>, items: &[Value], path: &str, errors: &mut Vec<(String, String)>) {
+        if let Some(minimum) = schema.get("minItems").and_then(|minimum| minimum.as_u64()) {
+            if (items.len() as u64) < minimum {
+                errors.push((String::from(path), format!("has {} items, fewer than {}", items.len(), minimum)));
+            }
+        }
+        if let Some(maximum) = schema.get("maxItems").and_then(|maximum| maximum.as_u64()) {
+            if items.len() as u64 > maximum {
+                errors.push((String::from(path), format!("has {} items, more than {}", items.len(), maximum)));
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = items.iter().enumerate().any(|(index, item)| items[..index].contains(item));
+            if duplicate {
+                errors.push((String::from(path), String::from("has duplicate items")));
+            }
+        }
+
+        match schema.get("items") {
+            Some(&Value::Array(ref schemas)) => {
+                for (index, (item, item_schema)) in items.iter().zip(schemas.iter()).enumerate() {
+                    self.validate(item_schema, item, json_path::child(path, &index.to_string()), &[], errors);
+                }
+            },
+            Some(item_schema) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.validate(item_schema, item, json_path::child(path, &index.to_string()), &[], errors);
+                }
+            },
+            None => (),
+        };
+    }
+}
+
+fn validate_number(schema: &serde_json::Map<String, Value>, number: f64, path: &str, errors: &mut Vec<(String, String)>) {
+    let bound = |keyword: &str| schema.get(keyword).and_then(|bound| bound.as_f64());
+    // Draft 4 writes exclusive bounds as flags on `minimum` and `maximum`.
+    let exclusive_flag = |keyword: &str| schema.get(keyword) == Some(&Value::Bool(true));
+
+    if let Some(minimum) = bound("minimum") {
+        if number < minimum || (exclusive_flag("exclusiveMinimum") && number == minimum) {
+            errors.push((String::from(path), format!("{} is below the minimum {}", number, minimum)));
+        }
+    }
+    if let Some(maximum) = bound("maximum") {
+        if number > maximum || (exclusive_flag("exclusiveMaximum") && number == maximum) {
+            errors.push((String::from(path), format!("{} is above the maximum {}", number, maximum)));
+        }
+    }
+    if let Some(minimum) = bound("exclusiveMinimum") {
+        if number <= minimum {
+            errors.push((String::from(path), format!("{} is not above {}", number, minimum)));
+        }
+    }
+    if let Some(maximum) = bound("exclusiveMaximum") {
+        if number >= maximum {
+            errors.push((String::from(path), format!("{} is not below {}", number, maximum)));
+        }
+    }
+    if let Some(divisor) = bound("multipleOf").filter(|divisor| *divisor > 0.0) {
+        let quotient = number / divisor;
+        if (quotient - quotient.round()).abs() > 1e-9 {
+            errors.push((String::from(path), format!("{} is not a multiple of {}", number, divisor)));
+        }
+    }
+}
+
+fn validate_string(schema: &serde_json::Map<String, Value>, text: &str, path: &str, errors: &mut Vec<(String, String)>) {
+    let length = text.chars().count() as u64;
+
+    if let Some(minimum) = schema.get("minLength").and_then(|minimum| minimum.as_u64()) {
+        if length < minimum {
+            errors.push((String::from(path), format!("is {} characters long, shorter than {}", length, minimum)));
+        }
+    }
+    if let Some(maximum) = schema.get("maxLength").and_then(|maximum| maximum.as_u64()) {
+        if length > maximum {
+            errors.push((String::from(path), format!("is {} characters long, longer than {}", length, maximum)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(schema: Value, value: Value) -> Vec<(String, String)> {
+        validate(&schema, &value)
+    }
+
+    #[test]
+    fn accepts_matching_values() {
+        let schema = json!({
+            "type": "object",
+            "required": ["count"],
+            "properties": {
+                "count": {"type": "integer", "minimum": 0},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}, "uniqueItems": true},
+            },
+            "additionalProperties": false,
+        });
+        assert!(errors(schema, json!({"count": 2, "tags": ["a", "b"]})).is_empty());
+        assert!(errors(json!(true), json!(null)).is_empty());
+        assert!(errors(json!({"type": "number"}), json!(1)).is_empty());
+        assert!(errors(json!({"type": "integer"}), json!(2.0)).is_empty());
+    }
+
+    #[test]
+    fn reports_mismatches_by_json_pointer() {
+        let schema = json!({
+            "type": "object",
+            "required": ["count"],
+            "properties": {"tags": {"items": {"type": "string"}}},
+            "additionalProperties": false,
+        });
+        assert_eq!(errors(schema, json!({"tags": ["a", 1], "extra": 1})), vec![
+            (String::from(""), String::from("missing required property count")),
+            (String::from("/extra"), String::from("property extra is not allowed")),
+            (String::from("/tags/1"), String::from("expected string, found integer")),
+        ]);
+        assert_eq!(errors(json!({"enum": [1, 2]}), json!(3)), vec![(String::new(), String::from("3 is not one of [1,2]"))]);
+        assert_eq!(errors(json!({"exclusiveMaximum": 3}), json!(3)), vec![(String::new(), String::from("3 is not below 3"))]);
+        assert_eq!(errors(json!({"oneOf": [{"type": "number"}, {"type": "integer"}]}), json!(1)).len(), 1);
+        assert_eq!(errors(json!({"not": {"type": "null"}}), json!(null)).len(), 1);
+        assert_eq!(errors(json!(false), json!(1)).len(), 1);
+    }
+
+    #[test]
+    fn follows_local_refs_with_escaped_pointers() {
+        let schema = json!({
+            "definitions": {"a/b": {"type": "string"}, "c~d": {"type": "integer"}, "e f": {"type": "null"}},
+            "properties": {
+                "x": {"$ref": "#/definitions/a~1b"},
+                "y": {"$ref": "#/definitions/c~0d"},
+                "z": {"$ref": "#/definitions/e%20f"},
+            },
+        });
+        assert!(errors(schema.clone(), json!({"x": "s", "y": 1, "z": null})).is_empty());
+        assert_eq!(errors(schema, json!({"x": 1})), vec![(String::from("/x"), String::from("expected string, found integer"))]);
+        assert_eq!(errors(json!({"$ref": "#/missing"}), json!(1)), vec![(String::new(), String::from("cannot resolve #/missing"))]);
+    }
+
+    #[test]
+    fn stops_at_refs_that_loop_without_reaching_a_value() {
+        assert_eq!(errors(json!({"$ref": "#"}), json!(1)), vec![(String::new(), String::from("# refers back to itself"))]);
+
+        let looping = json!({"definitions": {"a": {"$ref": "#/definitions/b"}, "b": {"allOf": [{"$ref": "#/definitions/a"}]}}, "$ref": "#/definitions/a"});
+        assert_eq!(errors(looping, json!(1)).len(), 1);
+
+        // A recursive schema that steps into the value each time is fine.
+        let tree = json!({"type": "object", "properties": {"children": {"type": "array", "items": {"$ref": "#"}}}});
+        assert!(errors(tree, json!({"children": [{"children": []}]})).is_empty());
+    }
+
+    #[test]
+    fn reports_unsupported_keywords_when_loading() {
+        let mut config = Config::default();
+        config.state_schemas.insert(String::from("Todo"), json!({
+            "properties": {"name": {"type": "string", "pattern": "^a"}},
+            "items": [{"format": "email"}],
+        }));
+        let (schemas, errors) = Schemas::load(&config);
+
+        assert!(schemas.states.contains_key("Todo"));
+        assert_eq!(errors, vec![
+            String::from("schema for Todo: format at \"/items/0\" is not supported and would be ignored"),
+            String::from("schema for Todo: pattern at \"/properties/name\" is not supported and would be ignored"),
+        ]);
+    }
+
+    #[test]
+    fn highlights_errors_and_their_parents() {
+        let errors = vec![SchemaError { scope: String::from("state"), path: String::from("/a/0"), message: String::new() }];
+        let paths = highlighted_paths(&errors, "state");
+
+        assert_eq!(paths.len(), 3);
+        assert!(paths.contains("") && paths.contains("/a") && paths.contains("/a/0"));
+        assert!(highlighted_paths(&errors, "amplitude").is_empty());
+    }
+}
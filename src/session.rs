@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind, Write, BufRead, BufReader, BufWriter};
+use serde_json;
+use state::{State, HistoryItem, HistoryAction, HistoryState};
+
+/// One history item of one client, stored as a line of json in a session file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionItemDto {
+    Client: String,
+    Domain: String,
+    Invocation: String,
+    Amplitude: String,
+    StateType: String,
+    State: String,
+    Timestamp: Option<u64>,
+    ReceivedAt: u64,
+    #[serde(default)]
+    Synthetic: bool,
+    #[serde(default)]
+    Id: Option<String>,
+    #[serde(default)]
+    CausedBy: Option<String>,
+    #[serde(default)]
+    Duration: Option<f64>,
+}
+
+impl SessionItemDto {
+    pub fn new(client: &str, item: &HistoryItem) -> SessionItemDto {
+        SessionItemDto {
+            Client: String::from(client),
+            Domain: item.action.domain.clone(),
+            Invocation: item.action.invocation.clone(),
+            Amplitude: item.action.amplitude.clone(),
+            StateType: item.state.id.clone(),
+            State: item.state.content.clone(),
+            Timestamp: item.sent_at,
+            ReceivedAt: item.received_at,
+            Synthetic: item.synthetic,
+            Id: item.id.clone(),
+            CausedBy: item.caused_by.clone(),
+            Duration: item.duration,
+        }
+    }
+
+    /// The client the item belongs to, and the item.
+    pub fn into_history_item(self) -> (String, HistoryItem) {
+        let action = HistoryAction::new(self.Domain, self.Invocation, self.Amplitude);
+        let state = HistoryState::new(self.StateType, self.State);
+        let mut item = HistoryItem::new(action, state, self.Timestamp, self.ReceivedAt);
+        item.synthetic = self.Synthetic;
+        item.id = self.Id;
+        item.caused_by = self.CausedBy;
+        item.duration = self.Duration;
+
+        (self.Client, item)
+    }
+}
+
+/// Writes `item` of `client` to `writer` as one json line.
+pub fn write_item<W: Write>(writer: &mut W, client: &str, item: &HistoryItem) -> Result<(), Error> {
+    let line = serde_json::to_string(&SessionItemDto::new(client, item))?;
+    writeln!(writer, "{}", line)
+}
+
+/// Writes the history of every client to `path` as json lines, returning the amount written.
+pub fn save(state: &State, path: &str) -> Result<usize, Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut amount = 0;
+
+    for client_id in state.client_ids() {
+        let client = &state.clients[&client_id];
+        for item in &client.history {
+            write_item(&mut writer, &client.id, item)?;
+            amount += 1;
+        }
+    }
+
+    writer.flush()?;
+    Ok(amount)
+}
+
+/// Reads a session written by `save` or by headless mode, as client ids and their items in the
+/// order they were written.
+pub fn load(path: &str) -> Result<Vec<(String, HistoryItem)>, Error> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut items = Vec::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let dto: SessionItemDto = serde_json::from_str(&line)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{}:{}: {}", path, number + 1, err)))?;
+        items.push(dto.into_history_item());
+    }
+
+    Ok(items)
+}
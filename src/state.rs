@@ -1,6 +1,28 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::net::TcpStream;
+use std::io::{Error, ErrorKind};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::mem;
 use ::actions::Action;
+use ::json_path;
+use ::config::Config;
+use ::layout::Layout;
+use ::invariants::{self, Invariant, Violation};
+use ::schema::{SchemaError, Schemas};
+use ::growth::{self, GrowthTracker, SizeAlert};
+use ::timing::Timings;
+use serde_json;
+
+pub fn timestamp_now() -> u64 {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the unix epoch")
+        ;
+
+    elapsed.as_secs() * 1000 + elapsed.subsec_millis() as u64
+}
 
 pub struct HistoryAction {
     pub domain: String,
@@ -45,6 +67,11 @@ impl HistoryState {
             selected: false,
         }
     }
+
+    /// The size of the serialized state in bytes.
+    pub fn size(&self) -> usize {
+        self.content.len()
+    }
 }
 
 impl Clone for HistoryState {
@@ -61,15 +88,95 @@ pub struct HistoryItem {
     pub action: HistoryAction,
     pub state: HistoryState,
     pub selected: bool,
+    pub sent_at: Option<u64>,
+    pub received_at: u64,
+    /// Made by the inspector itself rather than reported by the client.
+    pub synthetic: bool,
+    /// The id the client gave the action, so later actions can refer to it.
+    pub id: Option<String>,
+    /// The id of the action that set this one off, which may belong to another client.
+    pub caused_by: Option<String>,
+    /// How many milliseconds the client spent handling the action, if it measured that.
+    pub duration: Option<f64>,
+    /// The state is the same as after the item before it, so the action changed nothing.
+    pub unchanged: bool,
+    /// The action is the same as the one before it, amplitude included.
+    pub repeated: bool,
+    /// The amount of values in the state, 0 when it is not json.
+    pub node_count: usize,
+    /// How the size of the state stands out from the states before it.
+    pub size_alerts: Vec<SizeAlert>,
+    /// The configured invariants the state breaks.
+    pub violations: Vec<Violation>,
+    /// Where the state or amplitude does not match its configured schema.
+    pub schema_errors: Vec<SchemaError>,
 }
 
 impl HistoryItem {
-    pub fn new(action: HistoryAction, state: HistoryState) -> HistoryItem {
+    pub fn new(action: HistoryAction, state: HistoryState, sent_at: Option<u64>, received_at: u64) -> HistoryItem {
         HistoryItem {
             action: action,
             state: state,
             selected: false,
+            sent_at: sent_at,
+            received_at: received_at,
+            synthetic: false,
+            id: None,
+            caused_by: None,
+            duration: None,
+            unchanged: false,
+            repeated: false,
+            node_count: 0,
+            size_alerts: Vec::new(),
+            violations: Vec::new(),
+            schema_errors: Vec::new(),
+        }
+    }
+
+    /// Milliseconds since the unix epoch, preferring the time the client sent the item.
+    pub fn timestamp(&self) -> u64 {
+        self.sent_at.unwrap_or(self.received_at)
+    }
+
+    /// Roughly how many bytes the item takes up in memory.
+    pub fn size(&self) -> usize {
+        mem::size_of::<HistoryItem>()
+            + self.action.domain.len()
+            + self.action.invocation.len()
+            + self.action.amplitude.len()
+            + self.state.id.len()
+            + self.state.content.len()
+            + self.id.as_ref().map_or(0, |id| id.len())
+            + self.caused_by.as_ref().map_or(0, |id| id.len())
+    }
+
+    /// Whether the item looks like wasted work: an action that changed nothing or repeated the
+    /// one before it.
+    pub fn is_redundant(&self) -> bool {
+        self.unchanged || self.repeated
+    }
+
+    /// The names of the checks the item fails and of what makes it redundant, shown next to its
+    /// action.
+    pub fn badges(&self) -> Vec<&'static str> {
+        let mut badges = Vec::new();
+        if !self.violations.is_empty() {
+            badges.push("invariant");
+        }
+        if !self.schema_errors.is_empty() {
+            badges.push("schema");
+        }
+        if !self.size_alerts.is_empty() {
+            badges.push("growth");
         }
+        if self.unchanged {
+            badges.push("no-op");
+        }
+        if self.repeated {
+            badges.push("repeat");
+        }
+
+        badges
     }
 
     pub fn select(&mut self) {
@@ -91,14 +198,256 @@ impl Clone for HistoryItem {
             action: self.action.clone(),
             state: self.state.clone(),
             selected: self.selected,
+            sent_at: self.sent_at,
+            received_at: self.received_at,
+            synthetic: self.synthetic,
+            id: self.id.clone(),
+            caused_by: self.caused_by.clone(),
+            duration: self.duration,
+            unchanged: self.unchanged,
+            repeated: self.repeated,
+            node_count: self.node_count,
+            size_alerts: self.size_alerts.clone(),
+            violations: self.violations.clone(),
+            schema_errors: self.schema_errors.clone(),
+        }
+    }
+}
+
+pub enum ChartAxis {
+    Action,
+    Time,
+}
+
+impl Clone for ChartAxis {
+    fn clone(&self) -> ChartAxis {
+        match self {
+            ChartAxis::Action => ChartAxis::Action,
+            ChartAxis::Time => ChartAxis::Time,
+        }
+    }
+}
+
+/// What a chart plots for every history item.
+pub enum ChartSource {
+    /// A numeric value at a json pointer into the state.
+    Path(String),
+    /// The serialized size of the state in bytes.
+    Size,
+    /// The amount of values in the state.
+    Nodes,
+}
+
+impl Clone for ChartSource {
+    fn clone(&self) -> ChartSource {
+        match self {
+            ChartSource::Path(path) => ChartSource::Path(path.clone()),
+            ChartSource::Size => ChartSource::Size,
+            ChartSource::Nodes => ChartSource::Nodes,
+        }
+    }
+}
+
+/// A value of the state, sampled for every history item.
+pub struct Chart {
+    pub source: ChartSource,
+    pub axis: ChartAxis,
+    pub values: Vec<Option<f64>>,
+}
+
+impl Chart {
+    pub fn new(source: ChartSource, history: &[HistoryItem]) -> Chart {
+        let values = history.iter()
+            .map(|item| Chart::sample(&source, item))
+            .collect();
+
+        Chart {
+            source: source,
+            axis: ChartAxis::Action,
+            values: values,
+        }
+    }
+
+    /// Numbers at a path are plotted as is, arrays and objects by their length.
+    pub fn sample(source: &ChartSource, item: &HistoryItem) -> Option<f64> {
+        let path = match *source {
+            ChartSource::Path(ref path) => path,
+            ChartSource::Size => return Some(item.state.size() as f64),
+            ChartSource::Nodes => return Some(item.node_count as f64),
+        };
+        let content: serde_json::Value = serde_json::from_str(item.state.content.as_str()).ok()?;
+
+        match content.pointer(path)? {
+            serde_json::Value::Number(ref v) => v.as_f64(),
+            serde_json::Value::Array(ref v) => Some(v.len() as f64),
+            serde_json::Value::Object(ref v) => Some(v.len() as f64),
+            _ => None,
+        }
+    }
+
+    pub fn push(&mut self, item: &HistoryItem) {
+        let value = Chart::sample(&self.source, item);
+        self.values.push(value);
+    }
+
+    pub fn toggle_axis(&mut self) {
+        self.axis = match self.axis {
+            ChartAxis::Action => ChartAxis::Time,
+            ChartAxis::Time => ChartAxis::Action,
+        };
+    }
+
+    pub fn points(&self, history: &[HistoryItem]) -> Vec<[f64; 2]> {
+        let start = history.first().map(|item| item.timestamp()).unwrap_or(0);
+
+        self.values.iter()
+            .zip(history.iter())
+            .enumerate()
+            .filter_map(|(index, (value, item))| {
+                let x = match self.axis {
+                    ChartAxis::Action => index as f64,
+                    ChartAxis::Time => item.timestamp().saturating_sub(start) as f64,
+                };
+                value.map(|y| [x, y])
+            })
+            .collect()
+    }
+}
+
+impl Clone for Chart {
+    fn clone(&self) -> Chart {
+        Chart {
+            source: self.source.clone(),
+            axis: self.axis.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+/// A change to a state document made in the inspector, addressed by json pointer.
+pub enum Edit {
+    Set(String, serde_json::Value),
+    Remove(String),
+    /// Adds a null to the array at the path, or under the key to the object at the path.
+    AddChild(String, Option<String>),
+}
+
+impl Edit {
+    pub fn apply(&self, document: &mut serde_json::Value) -> bool {
+        match *self {
+            Edit::Set(ref path, ref value) => json_path::set(document, path, value.clone()),
+            Edit::Remove(ref path) => json_path::remove(document, path),
+            Edit::AddChild(ref path, ref key) => json_path::add_child(document, path, key.as_ref().map(|key| key.as_str())),
+        }
+    }
+
+    /// The amplitude of the synthetic history item recording this edit.
+    pub fn describe(&self) -> serde_json::Value {
+        let mut description = serde_json::Map::new();
+        let (operation, path) = match *self {
+            Edit::Set(ref path, ref value) => {
+                description.insert(String::from("value"), value.clone());
+                ("set", path)
+            },
+            Edit::Remove(ref path) => ("remove", path),
+            Edit::AddChild(ref path, ref key) => {
+                if let Some(ref key) = *key {
+                    description.insert(String::from("key"), serde_json::Value::String(key.clone()));
+                }
+                ("add", path)
+            },
+        };
+        description.insert(String::from("operation"), serde_json::Value::String(String::from(operation)));
+        description.insert(String::from("path"), serde_json::Value::String(path.clone()));
+
+        serde_json::Value::Object(description)
+    }
+}
+
+pub enum Expansion {
+    All,
+    Nothing,
+    Depth(usize),
+}
+
+/// Runs of consecutive actions of one domain shorter than this are listed as they are.
+const MIN_RUN_LENGTH: usize = 3;
+/// How far the action list indents an action under the actions that caused it.
+const MAX_CAUSAL_DEPTH: usize = 4;
+
+/// A line of the action list: a single action, or the header of a run of consecutive actions of
+/// the same domain, which lists its actions below it when expanded.
+pub enum ListRow {
+    Action { index: usize, grouped: bool },
+    Run { start: usize, last: usize, length: usize, expanded: bool },
+}
+
+impl ListRow {
+    /// The history index shown by an action, or the first one of a run.
+    pub fn index(&self) -> usize {
+        match *self {
+            ListRow::Action { index, .. } => index,
+            ListRow::Run { start, .. } => start,
         }
     }
 }
 
+/// Moves the selection of a single action through the filtered action list.
+pub enum Navigation {
+    Previous,
+    Next,
+    First,
+    Last,
+}
+
 pub struct Client {
     pub id: String,
     pub history: Vec<HistoryItem>,
     pub selections: HashSet<usize>,
+    pub chart: Option<Chart>,
+    /// Json pointers of the opened nodes, per inspected document ("state" or "amplitude").
+    pub expanded: HashMap<String, HashSet<String>>,
+    /// The first child shown of long arrays and objects, per inspected document.
+    pub pages: HashMap<String, HashMap<String, usize>>,
+    /// The write half of the client's socket, used to push commands back to it.
+    pub connection: Option<Arc<Mutex<TcpStream>>>,
+    /// Only actions whose `domain:invocation` contains this text are listed.
+    pub filter: String,
+    /// Only no-op and repeated actions are listed.
+    pub redundant_only: bool,
+    /// The first history index of each expanded run of grouped actions.
+    pub expanded_runs: HashSet<usize>,
+    pub connected: bool,
+    /// The summed size of the history items.
+    pub history_bytes: usize,
+    /// How often each action happened and how long it took.
+    pub timings: Timings,
+    /// The amount of history items whose state grew past a configured limit.
+    pub growing: usize,
+    /// The amount of actions that left the state unchanged.
+    pub unchanged: usize,
+    /// The amount of actions repeating the one before them.
+    pub repeated: usize,
+    /// The amount of history items whose state or amplitude does not match its schema.
+    pub mismatching: usize,
+    /// The amount of history items breaking an invariant.
+    pub violating: usize,
+    /// A name given to the tab, shown instead of the address.
+    pub name: Option<String>,
+    /// The name typed so far while the tab is being renamed.
+    pub rename_draft: Option<String>,
+    /// Pinned tabs are listed before the others.
+    pub pinned: bool,
+    /// Follows the size of the states to flag leaks.
+    pub growth: GrowthTracker,
+    /// The parsed state of the last item, so the next one is compared by value rather than text.
+    last_document: Option<serde_json::Value>,
+    /// The history indices of the items with each id.
+    items_by_id: HashMap<String, Vec<usize>>,
+    /// The history indices of the items caused by each id.
+    items_by_cause: HashMap<String, Vec<usize>>,
+    /// How many causes deep each history item is.
+    depths: Vec<usize>,
 }
 
 impl Client {
@@ -107,15 +456,205 @@ impl Client {
             id: id,
             history: Vec::new(),
             selections: HashSet::new(),
+            chart: None,
+            expanded: HashMap::new(),
+            pages: HashMap::new(),
+            connection: None,
+            filter: String::new(),
+            redundant_only: false,
+            expanded_runs: HashSet::new(),
+            connected: true,
+            history_bytes: 0,
+            timings: Timings::default(),
+            growing: 0,
+            unchanged: 0,
+            repeated: 0,
+            mismatching: 0,
+            violating: 0,
+            name: None,
+            rename_draft: None,
+            pinned: false,
+            growth: GrowthTracker::default(),
+            last_document: None,
+            items_by_id: HashMap::new(),
+            items_by_cause: HashMap::new(),
+            depths: Vec::new(),
         }
     }
 
-    pub fn push(&mut self, item: HistoryItem) {
+    /// Adds `item` to the history, noting whether it changed the state or repeated the action
+    /// before it, and how many values the state holds.
+    pub fn push(&mut self, mut item: HistoryItem) {
+        let document: Option<serde_json::Value> = serde_json::from_str(&item.state.content).ok();
+        item.node_count = document.as_ref().map_or(0, |document| growth::node_count(document));
+        if let Some(previous) = self.history.last() {
+            item.unchanged = previous.state.id == item.state.id && match (&self.last_document, &document) {
+                (&Some(ref previous_document), &Some(ref document)) => previous_document == document,
+                _ => previous.state.content == item.state.content,
+            };
+            item.repeated = previous.action.domain == item.action.domain
+                && previous.action.invocation == item.action.invocation
+                && previous.action.amplitude == item.action.amplitude;
+        }
+        if let Some(ref mut chart) = self.chart {
+            chart.push(&item);
+        }
+        self.history_bytes += item.size();
+        self.timings.push(&item);
+        self.growing += (!item.size_alerts.is_empty()) as usize;
+        self.unchanged += item.unchanged as usize;
+        self.repeated += item.repeated as usize;
+        self.mismatching += (!item.schema_errors.is_empty()) as usize;
+        self.violating += (!item.violations.is_empty()) as usize;
+        self.index_causes(&item);
         self.history.push(item);
+        self.last_document = document;
+    }
+
+    /// Files the item about to be pushed under its id and cause, and works out its depth. An item
+    /// caused by an earlier item of this client sits one below it, an item caused by an action
+    /// elsewhere sits at depth one.
+    fn index_causes(&mut self, item: &HistoryItem) {
+        let index = self.history.len();
+        let depth = match item.caused_by {
+            Some(ref cause) => self.items_by_id.get(cause).and_then(|indices| indices.last())
+                .map_or(1, |parent| usize::min(self.depths[*parent] + 1, MAX_CAUSAL_DEPTH)),
+            None => 0,
+        };
+        self.depths.push(depth);
+
+        if let Some(ref id) = item.id {
+            self.items_by_id.entry(id.clone()).or_insert_with(Vec::new).push(index);
+        }
+        if let Some(ref cause) = item.caused_by {
+            self.items_by_cause.entry(cause.clone()).or_insert_with(Vec::new).push(index);
+        }
+    }
+
+    /// Flags the size of the state of `item` when it grew too much, by the limits in `config`.
+    /// Has to see every item before it is pushed.
+    pub fn watch_growth(&mut self, item: &mut HistoryItem, config: &Config) {
+        let growth_limit = config.state_growth_kb as usize * 1024;
+        let jump_limit = config.action_growth_kb as usize * 1024;
+        item.size_alerts = self.growth.observe(item.state.size(), growth_limit, jump_limit);
     }
 
     pub fn clear(&mut self) {
+        self.growth = GrowthTracker::default();
+        self.last_document = None;
+        self.items_by_id.clear();
+        self.items_by_cause.clear();
+        self.depths.clear();
         self.history.clear();
+        self.history_bytes = 0;
+        self.timings = Timings::default();
+        self.growing = 0;
+        self.unchanged = 0;
+        self.repeated = 0;
+        self.mismatching = 0;
+        self.violating = 0;
+        self.selections.clear();
+        self.expanded_runs.clear();
+        if let Some(ref mut chart) = self.chart {
+            chart.values.clear();
+        }
+    }
+
+    /// The tab label: the given name, or the address the client connected from.
+    pub fn display_name(&self) -> &str {
+        match self.name {
+            Some(ref name) => name,
+            None => &self.id,
+        }
+    }
+
+    /// Names the tab, or goes back to the address when `name` is blank.
+    pub fn rename(&mut self, name: String) {
+        self.name = if name.trim().is_empty() { None } else { Some(name) };
+    }
+
+    pub fn select_chart(&mut self, source: ChartSource) {
+        self.chart = Some(Chart::new(source, &self.history));
+    }
+
+    pub fn toggle_chart_axis(&mut self) {
+        if let Some(ref mut chart) = self.chart {
+            chart.toggle_axis();
+        }
+    }
+
+    pub fn close_chart(&mut self) {
+        self.chart = None;
+    }
+
+    pub fn expanded_paths(&self, scope: &str) -> Option<&HashSet<String>> {
+        self.expanded.get(scope)
+    }
+
+    pub fn toggle_path(&mut self, scope: String, path: String) {
+        let paths = self.expanded.entry(scope).or_insert_with(HashSet::new);
+        if !paths.remove(&path) {
+            paths.insert(path);
+        }
+    }
+
+    /// Builds the synthetic history item holding the state at `index` with `edit` applied.
+    pub fn edited_history_item(&self, index: usize, edit: &Edit) -> Result<HistoryItem, Error> {
+        let item = self.history.get(index)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "history item index out of range"))?;
+
+        let mut document: serde_json::Value = serde_json::from_str(item.state.content.as_str())?;
+        if !edit.apply(&mut document) {
+            return Err(Error::new(ErrorKind::NotFound, "edited path not found in the state, or the new key is taken"));
+        }
+
+        let action = HistoryAction::new(
+            String::from("vision_quest"),
+            String::from("set state"),
+            edit.describe().to_string()
+        );
+        let history_state = HistoryState::new(item.state.id.clone(), serde_json::to_string(&document)?);
+        let mut edited_item = HistoryItem::new(action, history_state, None, timestamp_now());
+        edited_item.synthetic = true;
+
+        Ok(edited_item)
+    }
+
+    pub fn page_starts(&self, scope: &str) -> Option<&HashMap<String, usize>> {
+        self.pages.get(scope)
+    }
+
+    pub fn set_page(&mut self, scope: String, path: String, start: usize) {
+        self.pages
+            .entry(scope)
+            .or_insert_with(HashMap::new)
+            .insert(path, start)
+            ;
+    }
+
+    /// Opens or closes paths based on the document of the selected history item.
+    pub fn expand(&mut self, scope: String, expansion: Expansion) {
+        let document = self.selected_history_item()
+            .and_then(|item| {
+                let content = match scope.as_str() {
+                    "amplitude" => &item.action.amplitude,
+                    _ => &item.state.content,
+                };
+                serde_json::from_str::<serde_json::Value>(content.as_str()).ok()
+            });
+
+        let paths = self.expanded.entry(scope).or_insert_with(HashSet::new);
+        match (expansion, document) {
+            (Expansion::Nothing, _) => paths.clear(),
+            (Expansion::All, Some(document)) => {
+                paths.extend(json_path::container_paths(&document, None));
+            },
+            (Expansion::Depth(depth), Some(document)) => {
+                paths.clear();
+                paths.extend(json_path::container_paths(&document, Some(depth)));
+            },
+            (_, None) => (),
+        }
     }
 
     pub fn update_selections(&mut self, selections: HashSet<usize>) {
@@ -138,6 +677,103 @@ impl Client {
         self.history.iter()
             .find(|item| (**item).selected)
     }
+
+    pub fn selected_history_index(&self) -> Option<usize> {
+        self.history.iter()
+            .position(|item| item.selected)
+    }
+
+    /// The indices of the history items matching the filter, in order.
+    pub fn visible_indices(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+
+        self.history.iter()
+            .enumerate()
+            .filter(|&(_, item)| {
+                let name = format!("{}:{}", item.action.domain, item.action.invocation);
+                name.to_lowercase().contains(&filter) && (item.is_redundant() || !self.redundant_only)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The lines of the action list. When `group` is set, long runs of actions of one domain are
+    /// folded under a header.
+    pub fn list_rows(&self, group: bool) -> Vec<ListRow> {
+        let visible = self.visible_indices();
+        let mut rows = Vec::new();
+        let mut position = 0;
+
+        while position < visible.len() {
+            let domain = &self.history[visible[position]].action.domain;
+            let length = visible[position..].iter()
+                .take_while(|index| self.history[**index].action.domain == *domain)
+                .count();
+
+            if group && length >= MIN_RUN_LENGTH {
+                let start = visible[position];
+                let expanded = self.expanded_runs.contains(&start);
+                let last = visible[position + length - 1];
+                rows.push(ListRow::Run { start: start, last: last, length: length, expanded: expanded });
+                if expanded {
+                    for index in &visible[position..position + length] {
+                        rows.push(ListRow::Action { index: *index, grouped: true });
+                    }
+                }
+            } else {
+                for index in &visible[position..position + length] {
+                    rows.push(ListRow::Action { index: *index, grouped: false });
+                }
+            }
+
+            position += length;
+        }
+
+        rows
+    }
+
+    pub fn toggle_run(&mut self, start: usize) {
+        if !self.expanded_runs.remove(&start) {
+            self.expanded_runs.insert(start);
+        }
+    }
+
+    /// Moves the selection through the listed actions, stepping over collapsed runs at once.
+    pub fn navigate(&mut self, navigation: Navigation, group: bool) {
+        let mut visible: Vec<usize> = self.list_rows(group).iter()
+            .map(|row| row.index())
+            .collect();
+        visible.dedup();
+        if visible.is_empty() {
+            return;
+        }
+
+        let current = self.selected_history_index()
+            .and_then(|selected| visible.iter().position(|index| *index == selected));
+        let last = visible.len() - 1;
+        let position = match (navigation, current) {
+            (Navigation::First, _) => 0,
+            (Navigation::Last, _) | (Navigation::Previous, None) => last,
+            (Navigation::Next, None) => 0,
+            (Navigation::Previous, Some(current)) => current.saturating_sub(1),
+            (Navigation::Next, Some(current)) => usize::min(current + 1, last),
+        };
+
+        let mut selections = HashSet::new();
+        selections.insert(visible[position]);
+        self.update_selections(selections);
+    }
+
+    pub fn timestamps(&self) -> Vec<u64> {
+        self.history.iter()
+            .map(|item| item.timestamp())
+            .collect()
+    }
+
+    /// How many causes deep each history item is.
+    pub fn causal_depths(&self) -> &[usize] {
+        &self.depths
+    }
 }
 
 impl Clone for Client {
@@ -146,6 +782,29 @@ impl Clone for Client {
             id: self.id.clone(),
             history: self.history.iter().map(|x| x.clone()).collect(),
             selections: self.selections.clone(),
+            chart: self.chart.clone(),
+            expanded: self.expanded.clone(),
+            pages: self.pages.clone(),
+            connection: self.connection.clone(),
+            filter: self.filter.clone(),
+            redundant_only: self.redundant_only,
+            expanded_runs: self.expanded_runs.clone(),
+            connected: self.connected,
+            history_bytes: self.history_bytes,
+            timings: self.timings.clone(),
+            growing: self.growing,
+            unchanged: self.unchanged,
+            repeated: self.repeated,
+            mismatching: self.mismatching,
+            violating: self.violating,
+            name: self.name.clone(),
+            rename_draft: self.rename_draft.clone(),
+            pinned: self.pinned,
+            growth: self.growth.clone(),
+            last_document: self.last_document.clone(),
+            items_by_id: self.items_by_id.clone(),
+            items_by_cause: self.items_by_cause.clone(),
+            depths: self.depths.clone(),
         }
     }
 }
@@ -168,45 +827,277 @@ impl Clone for Status {
     }
 }
 
+/// Messages are counted over this many milliseconds to work out the throughput.
+pub const THROUGHPUT_WINDOW: u64 = 5000;
+
 pub struct State {
     pub clients: HashMap<String, Client>,
+    /// The client ids in the order they connected, which keeps the tab order stable.
+    pub client_order: Vec<String>,
     pub status: Status,
     pub dispatcher: Sender<Action>,
+    pub config: Config,
+    pub layout: Layout,
+    /// The client whose tab is shown, if it was picked explicitly.
+    pub active_client: Option<String>,
+    /// Whether history was recorded since the session was last saved.
+    pub unsaved: bool,
+    pub confirming_quit: bool,
+    /// Whether typed characters go to the active client's action filter.
+    pub search_focused: bool,
+    /// Whether the merged timeline of several clients is shown instead of a client's tab.
+    pub merged_view: bool,
+    /// The clients left out of the merged timeline.
+    pub merged_excluded: HashSet<String>,
+    /// The client and history index of the item picked in the merged timeline.
+    pub merged_selection: Option<(String, usize)>,
+    /// Whether timing statistics cover the inspectors of the shown client.
+    pub stats_view: bool,
+    pub listen_addresses: Vec<String>,
+    /// When each message of the last `THROUGHPUT_WINDOW` milliseconds arrived.
+    pub received: VecDeque<u64>,
+    pub last_error: Option<String>,
+    /// Why invariants or schemas from the config could not be used, one message each.
+    pub config_errors: Vec<String>,
+    /// The invariants from the config that parsed.
+    pub invariants: Vec<Invariant>,
+    /// The schemas from the config that could be read.
+    pub schemas: Schemas,
 }
 
 impl State {
-    pub fn new(dispatcher: Sender<Action>) -> State {
+    pub fn new(dispatcher: Sender<Action>, config: Config, layout: Layout) -> State {
+        let (invariants, mut config_errors) = invariants::parse_all(&config.invariants);
+        let (schemas, schema_errors) = Schemas::load(&config);
+        config_errors.extend(schema_errors);
+        let last_error = match config_errors.len() {
+            0 => None,
+            _ => Some(config_errors.join("; ")),
+        };
+
         State {
             clients: HashMap::new(),
+            client_order: Vec::new(),
             status: Status::Initializing,
             dispatcher: dispatcher,
+            config: config,
+            layout: layout,
+            active_client: None,
+            unsaved: false,
+            confirming_quit: false,
+            search_focused: false,
+            merged_view: false,
+            merged_excluded: HashSet::new(),
+            merged_selection: None,
+            stats_view: false,
+            listen_addresses: Vec::new(),
+            received: VecDeque::new(),
+            last_error: last_error,
+            config_errors: config_errors,
+            invariants: invariants,
+            schemas: schemas,
         }
     }
 
-    pub fn add_client(&mut self, client: String) {
-        self.clients
+    pub fn add_client(&mut self, client: String, connection: Arc<Mutex<TcpStream>>) {
+        if !self.client_order.contains(&client) {
+            self.client_order.push(client.clone());
+        }
+
+        let client = self.clients
             .entry(client.clone())
             .or_insert(Client::new(client))
             ;
+        client.connection = Some(connection);
+        client.connected = true;
     }
 
-    pub fn add_history_item(&mut self, client: String, item: HistoryItem) {
-        self.clients
-            .entry(client)
-            .and_modify(|client| client.push(item))
-            ;
+    /// Keeps the history of a client whose connection closed, but stops writing to it.
+    pub fn disconnect_client(&mut self, client: String) {
+        if let Some(client) = self.clients.get_mut(&client) {
+            client.connection = None;
+            client.connected = false;
+        }
+    }
+
+    pub fn add_history_item(&mut self, client: String, mut item: HistoryItem) {
+        if let Some(client) = self.clients.get_mut(&client) {
+            item.violations = invariants::check_all(&self.invariants, &item.state.id, &item.state.content);
+            item.schema_errors = self.schemas.check(&item);
+            client.watch_growth(&mut item, &self.config);
+            client.push(item);
+            self.unsaved = true;
+        }
     }
 
     pub fn client_amount(&self) -> usize {
         self.clients.len()
     }
 
+    /// The client ids in the order their tabs are shown: pinned tabs first, then the others, each
+    /// in the order they connected.
+    pub fn client_ids(&self) -> Vec<String> {
+        let is_pinned = |id: &String| self.clients.get(id).map(|client| client.pinned).unwrap_or(false);
+        let (mut pinned, unpinned): (Vec<String>, Vec<String>) = self.client_order.iter()
+            .filter(|id| self.clients.contains_key(*id))
+            .cloned()
+            .partition(|id| is_pinned(id));
+
+        pinned.extend(unpinned);
+        pinned
+    }
+
+    /// The client whose tab is shown, falling back to the first tab.
+    pub fn active_client_id(&self) -> Option<String> {
+        match self.active_client {
+            Some(ref id) if self.clients.contains_key(id) => Some(id.clone()),
+            _ => self.client_ids().into_iter().next(),
+        }
+    }
+
+    /// The items of every client in the merged timeline, as client id and history index, ordered by
+    /// timestamp. Items with the same timestamp keep the tab order.
+    pub fn merged_rows(&self) -> Vec<(String, usize)> {
+        let mut rows: Vec<(u64, String, usize)> = Vec::new();
+
+        for client_id in self.client_ids() {
+            if self.merged_excluded.contains(&client_id) {
+                continue;
+            }
+            for (index, item) in self.clients[&client_id].history.iter().enumerate() {
+                rows.push((item.timestamp(), client_id.clone(), index));
+            }
+        }
+
+        rows.sort_by_key(|&(timestamp, _, _)| timestamp);
+        rows.into_iter()
+            .map(|(_, client_id, index)| (client_id, index))
+            .collect()
+    }
+
+    pub fn merged_item(&self, row: &(String, usize)) -> Option<&HistoryItem> {
+        self.clients.get(&row.0).and_then(|client| client.history.get(row.1))
+    }
+
+    /// The items linked to `origin` through `id` and `caused_by`, in any client: the actions that
+    /// led up to it and the actions it set off. `origin` itself is left out.
+    pub fn causal_relatives(&self, origin: &(String, usize)) -> HashSet<(String, usize)> {
+        let mut relatives = HashSet::new();
+        let origin_item = match self.merged_item(origin) {
+            Some(item) => item,
+            None => return relatives,
+        };
+        if origin_item.id.is_none() && origin_item.caused_by.is_none() {
+            return relatives;
+        }
+
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut cause = origin_item.caused_by.as_ref();
+        while let Some(id) = cause {
+            if !visited.insert(id) {
+                break;
+            }
+            cause = None;
+            for parent in self.linked_items(id, |client| &client.items_by_id) {
+                cause = cause.or(self.merged_item(&parent).and_then(|item| item.caused_by.as_ref()));
+                relatives.insert(parent);
+            }
+        }
+
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut pending: Vec<&str> = origin_item.id.iter().map(|id| id.as_str()).collect();
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            for child in self.linked_items(id, |client| &client.items_by_cause) {
+                if let Some(ref child_id) = self.merged_item(&child).and_then(|item| item.id.as_ref()) {
+                    pending.push(child_id);
+                }
+                relatives.insert(child);
+            }
+        }
+
+        relatives.remove(origin);
+        relatives
+    }
+
+    /// The items filed under `id` in the index `pick` chooses, in any client.
+    fn linked_items(&self, id: &str, pick: fn(&Client) -> &HashMap<String, Vec<usize>>) -> Vec<(String, usize)> {
+        self.clients.iter()
+            .flat_map(|(client_id, client)| {
+                pick(client).get(id).into_iter().flat_map(move |indices| indices.iter().map(move |index| (client_id.clone(), *index)))
+            })
+            .collect()
+    }
+
+    pub fn record_message(&mut self, now: u64) {
+        self.received.push_back(now);
+        while self.received.front().map(|time| *time + THROUGHPUT_WINDOW < now).unwrap_or(false) {
+            self.received.pop_front();
+        }
+    }
+
+    /// The messages received per second over the last `THROUGHPUT_WINDOW` milliseconds.
+    pub fn messages_per_second(&self, now: u64) -> f64 {
+        let recent = self.received.iter()
+            .filter(|time| **time + THROUGHPUT_WINDOW >= now)
+            .count();
+
+        recent as f64 * 1000.0 / THROUGHPUT_WINDOW as f64
+    }
+
+    pub fn item_amount(&self) -> usize {
+        self.clients.values().map(|client| client.history.len()).sum()
+    }
+
+    /// The amount of history items breaking an invariant.
+    pub fn violating_amount(&self) -> usize {
+        self.clients.values().map(|client| client.violating).sum()
+    }
+
+    /// The amount of actions that left the state unchanged, and of actions repeating the one
+    /// before them.
+    pub fn redundant_amounts(&self) -> (usize, usize) {
+        self.clients.values().fold((0, 0), |(unchanged, repeated), client| {
+            (unchanged + client.unchanged, repeated + client.repeated)
+        })
+    }
+
+    /// The amount of history items whose state or amplitude does not match its schema.
+    pub fn mismatching_amount(&self) -> usize {
+        self.clients.values().map(|client| client.mismatching).sum()
+    }
+
+    /// The amount of history items whose state grew past a configured limit.
+    pub fn growing_amount(&self) -> usize {
+        self.clients.values().map(|client| client.growing).sum()
+    }
+
+    pub fn history_bytes(&self) -> usize {
+        self.clients.values().map(|client| client.history_bytes).sum()
+    }
+
+    pub fn connected_amount(&self) -> usize {
+        self.clients.values().filter(|client| client.connected).count()
+    }
+
+    pub fn report_error(&mut self, error: String) {
+        self.last_error = Some(error);
+    }
+
+    pub fn has_history(&self) -> bool {
+        self.clients.values().any(|client| !client.history.is_empty())
+    }
+
     pub fn remove_client(&mut self, client: String) {
         self.clients
             .entry(client.clone())
             .and_modify(|client| client.clear())
             ;
         self.clients.remove(&client);
+        self.client_order.retain(|id| *id != client);
+        self.merged_excluded.remove(&client);
     }
 }
 
@@ -214,8 +1105,203 @@ impl Clone for State {
     fn clone(&self) -> State {
         State {
             clients: self.clients.clone(),
+            client_order: self.client_order.clone(),
             status: self.status.clone(),
             dispatcher: self.dispatcher.clone(),
+            config: self.config.clone(),
+            layout: self.layout.clone(),
+            active_client: self.active_client.clone(),
+            unsaved: self.unsaved,
+            confirming_quit: self.confirming_quit,
+            search_focused: self.search_focused,
+            merged_view: self.merged_view,
+            merged_excluded: self.merged_excluded.clone(),
+            merged_selection: self.merged_selection.clone(),
+            stats_view: self.stats_view,
+            listen_addresses: self.listen_addresses.clone(),
+            received: self.received.clone(),
+            last_error: self.last_error.clone(),
+            config_errors: self.config_errors.clone(),
+            invariants: self.invariants.clone(),
+            schemas: self.schemas.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    fn item(domain: &str, invocation: &str, state: &str) -> HistoryItem {
+        let action = HistoryAction::new(String::from(domain), String::from(invocation), String::from("{}"));
+        HistoryItem::new(action, HistoryState::new(String::from("Page"), String::from(state)), None, 0)
+    }
+
+    /// A client whose history holds an action of each `domain:invocation`.
+    fn client(actions: &[&str]) -> Client {
+        let mut client = Client::new(String::from("app"));
+        for (index, action) in actions.iter().enumerate() {
+            let mut parts = action.split(':');
+            client.push(item(parts.next().unwrap(), parts.next().unwrap(), &index.to_string()));
         }
+        client
+    }
+
+    /// The rows as `run start..last (length)`, the index of an action, or `- index` for an action
+    /// listed under its run.
+    fn rows(client: &Client, group: bool) -> Vec<String> {
+        client.list_rows(group).iter()
+            .map(|row| match *row {
+                ListRow::Run { start, last, length, expanded } => format!("run {}..{} ({}){}", start, last, length, if expanded { " open" } else { "" }),
+                ListRow::Action { index, grouped: true } => format!("- {}", index),
+                ListRow::Action { index, grouped: false } => index.to_string(),
+            })
+            .collect()
+    }
+
+    fn navigate(client: &mut Client, navigation: Navigation) -> Option<usize> {
+        client.navigate(navigation, true);
+        client.selected_history_index()
+    }
+
+    #[test]
+    fn folds_long_runs_of_a_domain() {
+        let mut client = client(&["ui:click", "ui:hover", "ui:scroll", "net:get", "net:put", "ui:click"]);
+
+        assert_eq!(rows(&client, false), vec!["0", "1", "2", "3", "4", "5"]);
+        assert_eq!(rows(&client, true), vec!["run 0..2 (3)", "3", "4", "5"]);
+        client.toggle_run(0);
+        assert_eq!(rows(&client, true), vec!["run 0..2 (3) open", "- 0", "- 1", "- 2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn folds_the_filtered_actions() {
+        let mut client = client(&["ui:click", "ui:hover", "net:get", "ui:scroll", "net:put"]);
+
+        client.filter = String::from("UI:");
+        assert_eq!(rows(&client, false), vec!["0", "1", "3"]);
+        assert_eq!(rows(&client, true), vec!["run 0..3 (3)"]);
+        client.filter = String::from("net");
+        assert_eq!(rows(&client, true), vec!["2", "4"]);
+    }
+
+    #[test]
+    fn navigates_over_collapsed_runs() {
+        let mut client = client(&["ui:click", "ui:hover", "ui:scroll", "net:get", "net:put"]);
+
+        assert_eq!(navigate(&mut client, Navigation::Next), Some(0));
+        assert_eq!(navigate(&mut client, Navigation::Next), Some(3));
+        assert_eq!(navigate(&mut client, Navigation::Previous), Some(0));
+        assert_eq!(navigate(&mut client, Navigation::Previous), Some(0));
+        assert_eq!(navigate(&mut client, Navigation::Last), Some(4));
+        assert_eq!(navigate(&mut client, Navigation::Next), Some(4));
+
+        client.toggle_run(0);
+        assert_eq!(navigate(&mut client, Navigation::First), Some(0));
+        assert_eq!(navigate(&mut client, Navigation::Next), Some(1));
+        assert_eq!(client.selections.len(), 1);
+    }
+
+    #[test]
+    fn navigates_within_the_filter() {
+        let mut client = client(&["ui:click", "net:get", "ui:hover", "net:put"]);
+
+        client.filter = String::from("net");
+        assert_eq!(navigate(&mut client, Navigation::Previous), Some(3));
+        assert_eq!(navigate(&mut client, Navigation::Previous), Some(1));
+        assert_eq!(navigate(&mut client, Navigation::First), Some(1));
+
+        client.filter = String::from("nothing");
+        client.navigate(Navigation::Next, true);
+        assert_eq!(client.selected_history_index(), Some(1));
+    }
+
+    #[test]
+    fn applies_edits_to_a_document() {
+        let mut document = json!({"todos": [{"done": false}], "owner": {"name": "ann"}});
+
+        assert!(Edit::Set(String::from("/todos/0/done"), json!(true)).apply(&mut document));
+        assert!(Edit::AddChild(String::from("/todos"), None).apply(&mut document));
+        assert!(Edit::AddChild(String::from("/owner"), Some(String::from("age"))).apply(&mut document));
+        assert!(Edit::Remove(String::from("/owner/name")).apply(&mut document));
+        assert_eq!(document, json!({"todos": [{"done": true}, null], "owner": {"age": null}}));
+
+        assert!(!Edit::AddChild(String::from("/owner"), Some(String::from("age"))).apply(&mut document));
+        assert!(!Edit::AddChild(String::from("/owner"), None).apply(&mut document));
+        assert!(!Edit::Set(String::from("/missing/path"), json!(1)).apply(&mut document));
+        assert!(!Edit::Remove(String::from("/todos/5")).apply(&mut document));
+        assert_eq!(document, json!({"todos": [{"done": true}, null], "owner": {"age": null}}));
+    }
+
+    #[test]
+    fn records_an_edit_as_a_synthetic_item() {
+        let mut client = client(&["todo:add"]);
+        client.history[0].state.content = String::from("{\"todos\":{}}");
+
+        let edit = Edit::AddChild(String::from("/todos"), Some(String::from("milk")));
+        let item = client.edited_history_item(0, &edit).unwrap();
+        assert!(item.synthetic);
+        assert_eq!((item.action.domain.as_str(), item.action.invocation.as_str()), ("vision_quest", "set state"));
+        let amplitude: serde_json::Value = serde_json::from_str(&item.action.amplitude).unwrap();
+        assert_eq!(amplitude, json!({"operation": "add", "path": "/todos", "key": "milk"}));
+        assert_eq!(item.state.id, "Page");
+        assert_eq!(item.state.content, "{\"todos\":{\"milk\":null}}");
+        assert_eq!(client.history[0].state.content, "{\"todos\":{}}");
+
+        assert_eq!(client.edited_history_item(1, &edit).err().map(|err| err.kind()), Some(ErrorKind::NotFound));
+        assert_eq!(client.edited_history_item(0, &Edit::Remove(String::from("/done"))).err().map(|err| err.kind()), Some(ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn samples_numbers_and_lengths_at_a_path() {
+        let sample = |path: &str, content: &str| Chart::sample(&ChartSource::Path(String::from(path)), &item("todo", "add", content));
+        let content = "{\"total\":2.5,\"todos\":[1,2,3],\"owner\":{\"name\":\"ann\"}}";
+
+        assert_eq!(sample("/total", content), Some(2.5));
+        assert_eq!(sample("/todos", content), Some(3.0));
+        assert_eq!(sample("/owner", content), Some(1.0));
+        assert_eq!(sample("/owner/name", content), None);
+        assert_eq!(sample("/missing", content), None);
+        assert_eq!(sample("/total", "not json"), None);
+        let todos = item("todo", "add", content);
+        assert_eq!(Chart::sample(&ChartSource::Size, &todos), Some(todos.state.size() as f64));
+    }
+
+    #[test]
+    fn plots_the_sampled_items_by_action_or_time() {
+        let mut client = Client::new(String::from("app"));
+        client.select_chart(ChartSource::Path(String::from("/count")));
+        for &(sent_at, content) in [(1000, "{\"count\":1}"), (1500, "{}"), (4000, "{\"count\":3}")].iter() {
+            let mut item = item("counter", "add", content);
+            item.sent_at = Some(sent_at);
+            client.push(item);
+        }
+
+        let chart = client.chart.clone().unwrap();
+        assert_eq!(chart.values, vec![Some(1.0), None, Some(3.0)]);
+        assert_eq!(chart.points(&client.history), vec![[0.0, 1.0], [2.0, 3.0]]);
+        client.toggle_chart_axis();
+        assert_eq!(client.chart.as_ref().unwrap().points(&client.history), vec![[0.0, 1.0], [3000.0, 3.0]]);
+    }
+
+    #[test]
+    fn counts_messages_over_the_throughput_window() {
+        let (dispatcher, _) = channel();
+        let mut state = State::new(dispatcher, Config::default(), Layout::default());
+        assert_eq!(state.messages_per_second(0), 0.0);
+
+        for now in vec![0, 1000, 4000] {
+            state.record_message(now);
+        }
+        assert_eq!(state.messages_per_second(4000), 0.6);
+        assert_eq!(state.messages_per_second(5500), 0.4);
+
+        state.record_message(THROUGHPUT_WINDOW);
+        assert_eq!(state.received, vec![0, 1000, 4000, THROUGHPUT_WINDOW]);
+        state.record_message(6500);
+        assert_eq!(state.received, vec![4000, THROUGHPUT_WINDOW, 6500]);
+        assert_eq!(state.messages_per_second(6500), 0.6);
+        assert_eq!(state.messages_per_second(20000), 0.0);
     }
 }
@@ -2,18 +2,30 @@ use std::thread;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::net::{TcpListener, TcpStream};
-use std::io::{Error, Read};
-use std::str::from_utf8;
+use std::io::{Error, Read, Write};
 use actions::{Action, Message};
 
-pub fn listen() -> Result<(Sender<Action>, Receiver<Action>), Error> {
+/// Listens on every address, returning the channel actions arrive on and the bound addresses.
+pub fn listen(addresses: &[String]) -> Result<(Sender<Action>, Receiver<Action>, Vec<String>), Error> {
     let (tx, rx) = channel();
-    let tx_clone = tx.clone();
-    let listener = TcpListener::bind("127.0.0.1:7033")?;
+    let mut bound = Vec::new();
 
+    for address in addresses {
+        let listener = TcpListener::bind(address.as_str())?;
+        bound.push(format!("{}", listener.local_addr()?));
+        accept(listener, tx.clone());
+    }
+
+    Ok((tx, rx, bound))
+}
+
+fn accept(listener: TcpListener, tx: Sender<Action>) {
     thread::spawn(move || {
         loop {
-            let (socket, address) = listener.accept().unwrap();
+            let (socket, address) = match listener.accept() {
+                Ok(connection) => connection,
+                Err(_) => continue,
+            };
 
             let action = Action {
                 domain: String::from("client"),
@@ -22,56 +34,59 @@ pub fn listen() -> Result<(Sender<Action>, Receiver<Action>), Error> {
                 sender: format!("{}", address),
             };
 
-            match tx.send(action) {
-                Ok(()) => (),
-                Err(err) => {
-                    println!("{:?}", err);
-                    break;
-                },
-            };
+            if tx.send(action).is_err() {
+                break;
+            }
         }
     });
-
-    Ok((tx_clone, rx))
 }
 
 pub fn receive(socket : Arc<Mutex<TcpStream>>, tx : Sender<Action>, sender : String) -> Result<(), Error> {
     thread::spawn(move || {
-        let mut data = String::new();
+        let mut data: Vec<u8> = Vec::new();
 
         loop {
             let mut buffer = [0; 128];
 
             match socket.lock().unwrap().read(&mut buffer[..]) {
-                Ok(0) => (),
+                Ok(0) | Err(_) => break,
                 Ok(size) => {
-                    data.push_str(from_utf8(&buffer[..size]).expect("failed to convert tcp data to string"));
-
-                    let data_clone = data.clone();
-                    let chunks : Vec<&str> = data_clone.split("\n").collect();
-                    let (&rest, messages) = chunks.split_last().unwrap();
+                    data.extend_from_slice(&buffer[..size]);
 
-                    for &message in messages {
+                    // Lines are split on bytes, so characters cut in half by a read stay intact.
+                    while let Some(end) = data.iter().position(|byte| *byte == b'\n') {
+                        let line: Vec<u8> = data.drain(..end + 1).collect();
                         let action = Action {
                             domain: String::from("client"),
                             invocation: String::from("receive"),
-                            message: Message::Raw(String::from(message)),
+                            message: Message::Raw(String::from_utf8_lossy(&line[..end]).into_owned()),
                             sender: sender.clone(),
                         };
 
-                        tx.send(action).unwrap();
+                        if tx.send(action).is_err() {
+                            return;
+                        }
                     }
-
-                    data.truncate(0);
-                    data.push_str(rest);
                 },
-                Err(err) => {
-                    println!("{:?}", err);
-                    break;
-                }
             }
         }
+
+        let action = Action {
+            domain: String::from("client"),
+            invocation: String::from("disconnect"),
+            message: Message::Empty,
+            sender: sender,
+        };
+
+        tx.send(action).ok();
     });
 
     Ok(())
 }
+
+pub fn send(socket : &Arc<Mutex<TcpStream>>, message : String) -> Result<(), Error> {
+    let mut socket = socket.lock().unwrap();
+    socket.write_all(message.as_bytes())?;
+    socket.write_all(b"\n")?;
+    socket.flush()
+}
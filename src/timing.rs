@@ -0,0 +1,224 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use state::HistoryItem;
+
+/// The mean, 95th percentile and maximum of a series of milliseconds.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub mean: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    /// The summary of `values`, or `None` when there are none. The percentile is the nearest
+    /// rank, so it is always one of the values.
+    pub fn of(values: Vec<f64>) -> Option<Summary> {
+        let mut samples = Samples::default();
+        for value in values {
+            samples.push(value);
+        }
+        samples.summary()
+    }
+}
+
+/// How often an action happened and how long it took, as `domain:invocation`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ActionTiming {
+    pub action: String,
+    pub count: usize,
+    /// The durations the client reported, when it reported any.
+    pub duration: Option<Summary>,
+    /// The time since the item before each of the action's items, small for action storms.
+    pub interval: Option<Summary>,
+}
+
+/// Milliseconds since the item before each item came in, `None` for the first one. The time the
+/// inspector received them is used, since clocks of clients may differ and jump.
+pub fn intervals(history: &[HistoryItem]) -> Vec<Option<u64>> {
+    let mut intervals = Vec::with_capacity(history.len());
+    let mut previous: Option<u64> = None;
+
+    for item in history {
+        intervals.push(previous.map(|previous| item.received_at.saturating_sub(previous)));
+        previous = Some(item.received_at);
+    }
+
+    intervals
+}
+
+/// A series of milliseconds with a running sum and maximum, so adding to it stays cheap.
+#[derive(Debug, Clone, Default)]
+struct Samples {
+    values: Vec<f64>,
+    sum: f64,
+    max: f64,
+}
+
+impl Samples {
+    fn push(&mut self, value: f64) {
+        self.max = if self.values.is_empty() { value } else { f64::max(self.max, value) };
+        self.sum += value;
+        self.values.push(value);
+    }
+
+    /// Only the percentile needs the values sorted, which is left for when it is asked for.
+    fn summary(&self) -> Option<Summary> {
+        if self.values.is_empty() {
+            return None;
+        }
+
+        let mut values = self.values.clone();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let rank = ((values.len() as f64 * 0.95).ceil() as usize).max(1);
+
+        Some(Summary {
+            mean: self.sum / values.len() as f64,
+            p95: values[rank - 1],
+            max: self.max,
+        })
+    }
+}
+
+/// The samples of one action.
+#[derive(Debug, Clone)]
+struct ActionSamples {
+    action: String,
+    count: usize,
+    durations: Samples,
+    intervals: Samples,
+}
+
+/// The timing of a history, kept up to date one item at a time. Pushing an item only adds to the
+/// running numbers of its action; the summaries are worked out when they are asked for.
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    /// The samples of each action, in the order the actions first came in.
+    actions: Vec<ActionSamples>,
+    /// The position of each action in `actions`.
+    positions: HashMap<String, usize>,
+    previous: Option<u64>,
+}
+
+impl Timings {
+    /// Adds the item after the ones seen so far.
+    pub fn push(&mut self, item: &HistoryItem) {
+        let action = format!("{}:{}", item.action.domain, item.action.invocation);
+        let actions = &mut self.actions;
+        let position = *self.positions.entry(action.clone()).or_insert_with(|| {
+            actions.push(ActionSamples { action: action, count: 0, durations: Samples::default(), intervals: Samples::default() });
+            actions.len() - 1
+        });
+
+        let samples = &mut self.actions[position];
+        samples.count += 1;
+        if let Some(duration) = item.duration {
+            samples.durations.push(duration);
+        }
+        if let Some(previous) = self.previous {
+            samples.intervals.push(item.received_at.saturating_sub(previous) as f64);
+        }
+        self.previous = Some(item.received_at);
+    }
+
+    /// The timing of every action, slowest first so expensive handlers stand out. Actions without
+    /// a reported duration follow, the most frequent first.
+    pub fn actions(&self) -> Vec<ActionTiming> {
+        let mut order: Vec<&ActionSamples> = self.actions.iter().collect();
+        // The sort is stable, so ties keep the order the actions first came in.
+        order.sort_by(|a, b| {
+            let a_max = if a.durations.values.is_empty() { -1.0 } else { a.durations.max };
+            let b_max = if b.durations.values.is_empty() { -1.0 } else { b.durations.max };
+            b_max.partial_cmp(&a_max)
+                .unwrap_or(Ordering::Equal)
+                .then(b.count.cmp(&a.count))
+        });
+
+        order.into_iter()
+            .map(|samples| ActionTiming {
+                action: samples.action.clone(),
+                count: samples.count,
+                duration: samples.durations.summary(),
+                interval: samples.intervals.summary(),
+            })
+            .collect()
+    }
+}
+
+/// The timing of every action in `history`, ordered like `Timings::actions`.
+pub fn stats(history: &[HistoryItem]) -> Vec<ActionTiming> {
+    let mut timings = Timings::default();
+    for item in history {
+        timings.push(item);
+    }
+
+    timings.actions()
+}
+
+/// Milliseconds for display, with more precision for the short ones.
+pub fn format_ms(milliseconds: f64) -> String {
+    if milliseconds < 10.0 {
+        format!("{:.2} ms", milliseconds)
+    } else if milliseconds < 1000.0 {
+        format!("{:.1} ms", milliseconds)
+    } else {
+        format!("{:.2} s", milliseconds / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state::{HistoryAction, HistoryState};
+
+    fn item(invocation: &str, sent_at: Option<u64>, received_at: u64, duration: Option<f64>) -> HistoryItem {
+        let action = HistoryAction::new(String::from("todo"), String::from(invocation), String::from("null"));
+        let mut item = HistoryItem::new(action, HistoryState::new(String::from("Todos"), String::from("[]")), sent_at, received_at);
+        item.duration = duration;
+        item
+    }
+
+    #[test]
+    fn summarizes_with_the_nearest_rank_percentile() {
+        let values: Vec<f64> = (1..21).map(|value| value as f64).collect();
+        assert_eq!(Summary::of(values), Some(Summary { mean: 10.5, p95: 19.0, max: 20.0 }));
+        assert_eq!(Summary::of(vec![3.0, 1.0, 2.0]), Some(Summary { mean: 2.0, p95: 3.0, max: 3.0 }));
+        assert_eq!(Summary::of(vec![7.0]), Some(Summary { mean: 7.0, p95: 7.0, max: 7.0 }));
+        assert_eq!(Summary::of(Vec::new()), None);
+    }
+
+    #[test]
+    fn intervals_use_the_time_items_came_in() {
+        let history = vec![item("add", Some(900), 1000, None), item("add", Some(100), 1040, None), item("add", None, 1030, None)];
+        assert_eq!(intervals(&history), vec![None, Some(40), Some(0)]);
+    }
+
+    #[test]
+    fn timings_follow_pushed_items() {
+        let history = vec![item("add", None, 0, Some(1.0)), item("toggle", None, 10, None), item("remove", None, 20, Some(5.0))];
+        let mut timings = Timings::default();
+        for item in &history {
+            timings.push(item);
+        }
+
+        assert_eq!(format!("{:?}", timings.actions()), format!("{:?}", stats(&history)));
+        timings.push(&item("add", None, 30, Some(9.0)));
+        assert_eq!(timings.actions()[0].action, "todo:add");
+        assert_eq!(timings.actions()[0].interval, Some(Summary { mean: 10.0, p95: 10.0, max: 10.0 }));
+    }
+
+    #[test]
+    fn stats_list_the_slowest_actions_first() {
+        let history = vec![
+            item("add", None, 0, Some(1.0)),
+            item("toggle", None, 10, None),
+            item("remove", None, 20, Some(5.0)),
+            item("toggle", None, 50, None),
+        ];
+        let timings = stats(&history);
+        let order: Vec<&str> = timings.iter().map(|timing| timing.action.as_str()).collect();
+        assert_eq!(order, vec!["todo:remove", "todo:add", "todo:toggle"]);
+        assert_eq!(timings[2].count, 2);
+        assert_eq!(timings[2].interval, Some(Summary { mean: 20.0, p95: 30.0, max: 30.0 }));
+    }
+}
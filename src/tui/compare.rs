@@ -0,0 +1,187 @@
+use std::io::Error;
+use ::comparison::{self, Comparison, Report, Step};
+use ::json_diff::Change;
+use ::session;
+use super::terminal::{self, Terminal, Key};
+use super::{cell, scroll, SELECTED, TITLE, FOCUSED_TITLE, WARNING};
+
+const HELP: &'static str = "↑/↓ move  n/N next/previous difference  tab pair  q quit";
+
+/// Which pair of clients is shown, and the selected step of each pair.
+struct View {
+    pair: usize,
+    cursors: Vec<usize>,
+}
+
+/// Loads the sessions at `expected` and `actual`, aligns their actions and lets them be browsed
+/// in the terminal, starting at the first step where they part ways.
+pub fn run(expected: &str, actual: &str) -> Result<(), Error> {
+    let report = comparison::compare_sessions(session::load(expected)?, session::load(actual)?);
+    let title = format!("{} vs {}", expected, actual);
+    let terminal = Terminal::new()?;
+
+    let mut view = View {
+        pair: 0,
+        cursors: report.clients.iter()
+            .map(|client| client.steps.iter().position(|step| !step.is_equal()).unwrap_or(0))
+            .collect(),
+    };
+
+    loop {
+        terminal.draw(&render(&report, &title, &view, terminal.size()))?;
+
+        for key in terminal::read_keys()? {
+            if !handle_key(key, &report, &mut view) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Applies `key` to `view`, returning false when it asks to quit.
+fn handle_key(key: Key, report: &Report, view: &mut View) -> bool {
+    let steps = match report.clients.get(view.pair) {
+        Some(client) => &client.steps,
+        None => return !(key == Key::Char('q') || key == Key::Ctrl('c')),
+    };
+    let last = steps.len().saturating_sub(1);
+    let cursor = view.cursors[view.pair];
+
+    view.cursors[view.pair] = match key {
+        Key::Char('q') | Key::Ctrl('c') => return false,
+        Key::Tab => {
+            view.pair = (view.pair + 1) % report.clients.len();
+            return true;
+        },
+        Key::BackTab => {
+            view.pair = (view.pair + report.clients.len() - 1) % report.clients.len();
+            return true;
+        },
+        Key::Up | Key::Char('k') => cursor.saturating_sub(1),
+        Key::Down | Key::Char('j') => usize::min(cursor + 1, last),
+        Key::Home => 0,
+        Key::End => last,
+        Key::Char('n') => (cursor + 1..steps.len()).find(|index| !steps[*index].is_equal()).unwrap_or(cursor),
+        Key::Char('N') => (0..cursor).rev().find(|index| !steps[*index].is_equal()).unwrap_or(cursor),
+        _ => cursor,
+    };
+    true
+}
+
+fn render(report: &Report, title: &str, view: &View, (width, height): (usize, usize)) -> Vec<String> {
+    let mut lines = Vec::with_capacity(height);
+    lines.push(render_pairs(report, width, view.pair));
+
+    let body_height = height.saturating_sub(2);
+    match report.clients.get(view.pair) {
+        Some(client) => {
+            let list_width = width / 2;
+            let right_width = width.saturating_sub(list_width + 1);
+            let cursor = view.cursors[view.pair];
+
+            let left = render_steps(client, cursor, list_width, body_height);
+            let right = render_step(client.steps.get(cursor), right_width, body_height);
+            for (left, right) in left.iter().zip(right.iter()) {
+                lines.push(format!("{}│{}", left, right));
+            }
+        },
+        None => {
+            lines.push(cell("Both sessions are empty", width, ""));
+            for _ in 1..body_height {
+                lines.push(String::new());
+            }
+        },
+    };
+
+    let status = if report.passed { "same" } else { "different" };
+    lines.push(cell(&format!("{} | {} | {}", title, status, HELP), width, ""));
+    lines
+}
+
+fn render_pairs(report: &Report, width: usize, selected: usize) -> String {
+    let mut line = String::new();
+    let mut used = 0;
+
+    for (index, client) in report.clients.iter().enumerate() {
+        let expected = client.expected_client.as_ref().map_or("-", |id| id.as_str());
+        let actual = client.actual_client.as_ref().map_or("-", |id| id.as_str());
+        let mark = if client.passed() { "" } else { " ≠" };
+        let label = format!(" {}: {} / {}{} ", index + 1, expected, actual, mark);
+        let length = label.chars().count();
+        if used + length > width {
+            break;
+        }
+
+        line.push_str(&cell(&label, length, if index == selected { SELECTED } else { "" }));
+        used += length;
+    }
+
+    line
+}
+
+/// The aligned actions of a pair of clients, numbered by their index in either session.
+fn render_steps(client: &Comparison, cursor: usize, width: usize, height: usize) -> Vec<String> {
+    let title = format!("Actions ({} missing, {} extra, {} differing states)", client.missing, client.extra, client.differing_states);
+    let mut lines = vec![cell(&title, width, FOCUSED_TITLE)];
+    if !client.aligned {
+        lines.push(cell("! too different to align, the middle is listed as missing and extra", width, WARNING));
+    }
+
+    let list_height = height.saturating_sub(lines.len());
+    for (index, step) in client.steps.iter().enumerate().skip(scroll(cursor, list_height)).take(list_height) {
+        let diverges = client.divergence.as_ref().map_or(false, |divergence| match *step {
+            Step::Matched { expected, actual, .. } => expected == divergence.expected && actual == divergence.actual,
+            Step::Missing { expected, .. } => expected == divergence.expected,
+            Step::Extra { actual, .. } => actual == divergence.actual,
+        });
+        let text = match *step {
+            Step::Matched { expected, actual, ref action, ref changes } => {
+                let mark = if changes.is_empty() { " " } else { "~" };
+                format!("{} {:>5} {:>5}  {}", mark, expected, actual, action)
+            },
+            Step::Missing { expected, ref action } => format!("- {:>5} {:>5}  {}", expected, "", action),
+            Step::Extra { actual, ref action } => format!("+ {:>5} {:>5}  {}", "", actual, action),
+        };
+        let text = if diverges { format!("{}  ← diverges", text) } else { text };
+
+        let style = match (index == cursor, step.is_equal()) {
+            (true, _) => SELECTED,
+            (false, false) => WARNING,
+            (false, true) => "",
+        };
+        lines.push(cell(&text, width, style));
+    }
+
+    while lines.len() < height {
+        lines.push(cell("", width, ""));
+    }
+    lines
+}
+
+/// What differs at the selected step.
+fn render_step(step: Option<&Step>, width: usize, height: usize) -> Vec<String> {
+    let mut lines = vec![cell("Differences", width, TITLE)];
+
+    match step {
+        Some(&Step::Matched { ref changes, .. }) if changes.is_empty() => lines.push(cell("The states after the action are equal", width, "")),
+        Some(&Step::Matched { ref changes, .. }) => {
+            for change in changes {
+                let text = match *change {
+                    Change::Added { ref path, ref value } => format!("+ {}: {}", path, value),
+                    Change::Removed { ref path, ref value } => format!("- {}: {}", path, value),
+                    Change::Changed { ref path, ref from, ref to } => format!("~ {}: {} -> {}", path, from, to),
+                };
+                lines.push(cell(&text, width, ""));
+            }
+        },
+        Some(&Step::Missing { .. }) => lines.push(cell("Only the expected session has this action", width, WARNING)),
+        Some(&Step::Extra { .. }) => lines.push(cell("Only the actual session has this action", width, WARNING)),
+        None => (),
+    };
+
+    while lines.len() < height {
+        lines.push(cell("", width, ""));
+    }
+    lines.truncate(height);
+    lines
+}
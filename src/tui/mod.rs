@@ -0,0 +1,512 @@
+mod terminal;
+mod tree;
+mod compare;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use serde_json;
+use ::state::{State, Status, Client, ListRow, Navigation};
+use ::actions::{Action, Message};
+use ::schema;
+use ::timing;
+use ::growth;
+use self::terminal::{Terminal, Key};
+
+const HELP: &'static str = "←/→ pane  ↑/↓ move  enter open  tab client  g group  r no-ops  t timing  p pause  s save  q quit";
+const SELECTED: &'static str = "\x1b[7m";
+const TITLE: &'static str = "\x1b[1m";
+const FOCUSED_TITLE: &'static str = "\x1b[1;4m";
+const WARNING: &'static str = "\x1b[33m";
+
+enum Input {
+    State(Box<State>),
+    Keys(Vec<Key>),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Actions,
+    Amplitude,
+    State,
+}
+
+impl Pane {
+    fn scope(&self) -> &'static str {
+        match *self {
+            Pane::Actions => "",
+            Pane::Amplitude => "amplitude",
+            Pane::State => "state",
+        }
+    }
+}
+
+/// What the terminal shows besides the application state: the focused pane and the cursor of
+/// each json tree.
+struct View {
+    pane: Pane,
+    cursors: HashMap<&'static str, usize>,
+}
+
+impl View {
+    fn cursor(&self, pane: Pane) -> usize {
+        self.cursors.get(pane.scope()).cloned().unwrap_or(0)
+    }
+}
+
+/// Shows the application state in the terminal, as an alternative to the window of `ui::run`
+/// for machines without a display.
+pub fn run(rx: Receiver<State>) {
+    let mut state = rx.recv().expect("Tui failed to receive application state");
+    let terminal = Terminal::new().expect("Tui failed to set up the terminal");
+
+    let (tx, inputs) = channel();
+    let key_tx = tx.clone();
+    thread::spawn(move || forward_states(rx, tx));
+    thread::spawn(move || forward_keys(key_tx));
+
+    let mut view = View {
+        pane: Pane::Actions,
+        cursors: HashMap::new(),
+    };
+
+    loop {
+        if let Status::ShuttingDown = state.status {
+            break;
+        }
+
+        let lines = render(&state, &view, terminal.size());
+        terminal.draw(&lines).expect("Tui failed to draw");
+
+        // The state is redrawn at least once a second, which also picks up a resized terminal.
+        // Everything queued up since the last draw is taken in first, so only the latest state
+        // is drawn when states arrive faster than the terminal shows them.
+        let first = match inputs.recv_timeout(Duration::from_secs(1)) {
+            Ok(input) => input,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        for input in Some(first).into_iter().chain(inputs.try_iter()) {
+            match input {
+                Input::State(new_state) => state = *new_state,
+                Input::Keys(keys) => {
+                    for key in keys {
+                        handle_key(key, &state, &mut view);
+                    }
+                },
+            };
+        }
+    }
+}
+
+/// Shows the comparison of the sessions at `expected` and `actual` in the terminal instead of
+/// recording. Returns the exit code: 1 when a session could not be read.
+pub fn compare(expected: &str, actual: &str) -> i32 {
+    match compare::run(expected, actual) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("failed to compare {} with {}: {}", expected, actual, err);
+            1
+        },
+    }
+}
+
+fn forward_states(rx: Receiver<State>, tx: Sender<Input>) {
+    while let Ok(state) = rx.recv() {
+        if tx.send(Input::State(Box::new(state))).is_err() {
+            break;
+        }
+    }
+}
+
+fn forward_keys(tx: Sender<Input>) {
+    while let Ok(keys) = terminal::read_keys() {
+        if tx.send(Input::Keys(keys)).is_err() {
+            break;
+        }
+    }
+}
+
+fn dispatch(state: &State, domain: &str, invocation: &str, message: Message) {
+    let action = Action {
+        domain: String::from(domain),
+        invocation: String::from(invocation),
+        message: message,
+        sender: String::from("tui"),
+    };
+
+    state.dispatcher.send(action).expect("Failed to send tui action to the application state");
+}
+
+fn active_client(state: &State) -> Option<&Client> {
+    state.active_client_id().and_then(|client_id| state.clients.get(&client_id))
+}
+
+/// The visible nodes of the amplitude or state of the selected action.
+fn tree_lines(client: &Client, pane: Pane) -> Vec<tree::Line> {
+    let item = match client.selected_history_item() {
+        Some(item) => item,
+        None => return Vec::new(),
+    };
+    let content = match pane {
+        Pane::Amplitude => &item.action.amplitude,
+        _ => &item.state.content,
+    };
+    let value = serde_json::from_str(content).unwrap_or_else(|_| serde_json::Value::String(content.clone()));
+
+    let no_paths = HashSet::new();
+    let no_pages = HashMap::new();
+    let expanded = client.expanded_paths(pane.scope()).unwrap_or(&no_paths);
+    let pages = client.page_starts(pane.scope()).unwrap_or(&no_pages);
+
+    tree::lines(&value, pane.scope(), expanded, pages)
+}
+
+fn handle_key(key: Key, state: &State, view: &mut View) {
+    if state.confirming_quit {
+        match key {
+            Key::Char('s') => dispatch(state, "session", "save and quit", Message::Empty),
+            Key::Char('q') | Key::Ctrl('c') => dispatch(state, "application", "quit", Message::Empty),
+            Key::Escape | Key::Char('c') => dispatch(state, "application", "cancel quit", Message::Empty),
+            _ => (),
+        };
+        return;
+    }
+
+    let client_ids = state.client_ids();
+    let active = state.active_client_id();
+    let active_index = active.as_ref().and_then(|active| client_ids.iter().position(|id| id == active));
+
+    let tab = match (key, active_index) {
+        (_, None) => None,
+        (Key::Tab, Some(index)) => Some((index + 1) % client_ids.len()),
+        (Key::BackTab, Some(index)) => Some((index + client_ids.len() - 1) % client_ids.len()),
+        (Key::Char(digit @ '1'..='9'), _) => Some(digit as usize - '1' as usize).filter(|index| *index < client_ids.len()),
+        _ => None,
+    };
+    if let Some(index) = tab {
+        dispatch(state, "tab", "select", Message::ClientId(client_ids[index].clone()));
+        return;
+    }
+
+    match key {
+        Key::Char('q') | Key::Ctrl('c') => dispatch(state, "application", "request quit", Message::Empty),
+        Key::Char('p') => dispatch(state, "application", "toggle pause", Message::Empty),
+        Key::Char('s') => dispatch(state, "session", "save", Message::Empty),
+        Key::Char('g') => dispatch(state, "action", "toggle grouping", Message::Empty),
+        Key::Char('t') => dispatch(state, "stats", "toggle view", Message::Empty),
+        Key::Char('r') => if let Some(client_id) = active {
+            dispatch(state, "action", "toggle redundant", Message::ClientId(client_id));
+        },
+        Key::Left => view.pane = match view.pane {
+            Pane::Actions => Pane::State,
+            Pane::Amplitude => Pane::Actions,
+            Pane::State => Pane::Amplitude,
+        },
+        Key::Right => view.pane = match view.pane {
+            Pane::Actions => Pane::Amplitude,
+            Pane::Amplitude => Pane::State,
+            Pane::State => Pane::Actions,
+        },
+        _ => match (view.pane, active_client(state)) {
+            (Pane::Actions, Some(client)) => handle_action_key(key, state, client),
+            (pane, Some(client)) => handle_tree_key(key, state, client, pane, view),
+            (_, None) => (),
+        },
+    };
+}
+
+fn handle_action_key(key: Key, state: &State, client: &Client) {
+    let navigation = match key {
+        Key::Up | Key::Char('k') => Some(Navigation::Previous),
+        Key::Down | Key::Char('j') => Some(Navigation::Next),
+        Key::Home => Some(Navigation::First),
+        Key::End => Some(Navigation::Last),
+        _ => None,
+    };
+    if let Some(navigation) = navigation {
+        dispatch(state, "action", "navigate", Message::Navigate((client.id.clone(), navigation)));
+        return;
+    }
+
+    if let (Key::Enter, Some(selected)) = (key, client.selected_history_index()) {
+        let run = client.list_rows(state.config.group_domains).into_iter()
+            .filter_map(|row| match row {
+                ListRow::Run { start, last, .. } if selected >= start && selected <= last => Some(start),
+                _ => None,
+            })
+            .next();
+
+        if let Some(start) = run {
+            let mut starts = ::std::collections::HashSet::new();
+            starts.insert(start);
+            dispatch(state, "action", "toggle run", Message::SelectAction((client.id.clone(), starts)));
+        }
+    }
+}
+
+fn handle_tree_key(key: Key, state: &State, client: &Client, pane: Pane, view: &mut View) {
+    let lines = tree_lines(client, pane);
+    let last = lines.len().saturating_sub(1);
+    let cursor = usize::min(view.cursor(pane), last);
+
+    let cursor = match key {
+        Key::Up | Key::Char('k') => cursor.saturating_sub(1),
+        Key::Down | Key::Char('j') => usize::min(cursor + 1, last),
+        Key::Home => 0,
+        Key::End => last,
+        Key::Enter | Key::Char(' ') => match lines.get(cursor) {
+            Some(&tree::Line { ref path, page: Some(start), .. }) => {
+                let message = Message::InspectorPage((client.id.clone(), String::from(pane.scope()), path.clone(), start));
+                dispatch(state, "inspector", "page", message);
+                // Back to the line that opens the parent, as the page it leads to may be shorter.
+                lines.iter().position(|line| line.container && line.path == *path).unwrap_or(cursor)
+            },
+            Some(line) if line.container => {
+                let message = Message::InspectorPath((client.id.clone(), String::from(pane.scope()), line.path.clone()));
+                dispatch(state, "inspector", "toggle", message);
+                cursor
+            },
+            _ => cursor,
+        },
+        _ => cursor,
+    };
+
+    view.cursors.insert(pane.scope(), cursor);
+}
+
+/// `text` cut or padded to exactly `width` characters, in `style`.
+fn cell(text: &str, width: usize, style: &str) -> String {
+    let mut cell: String = text.chars().take(width).collect();
+    let length = cell.chars().count();
+    cell.push_str(&" ".repeat(width - length));
+
+    if style.is_empty() {
+        cell
+    } else {
+        format!("{}{}\x1b[0m", style, cell)
+    }
+}
+
+/// The first line to show of `amount` lines so that `cursor` is in view in `height` lines.
+fn scroll(cursor: usize, height: usize) -> usize {
+    if height == 0 || cursor < height { 0 } else { cursor + 1 - height }
+}
+
+fn render(state: &State, view: &View, (width, height): (usize, usize)) -> Vec<String> {
+    let mut lines = Vec::with_capacity(height);
+    lines.push(render_tabs(state, width));
+
+    let body_height = height.saturating_sub(2);
+    match active_client(state) {
+        Some(client) => {
+            let list_width = width / 3;
+            let right_width = width.saturating_sub(list_width + 1);
+            let amplitude_height = body_height / 3;
+
+            let left = render_actions(state, client, view, list_width, body_height);
+            let right = if state.stats_view {
+                render_stats(client, right_width, body_height)
+            } else {
+                let mut right = render_tree(client, view, Pane::Amplitude, right_width, amplitude_height);
+                right.extend(render_tree(client, view, Pane::State, right_width, body_height - amplitude_height));
+                right
+            };
+
+            for (left, right) in left.iter().zip(right.iter()) {
+                lines.push(format!("{}│{}", left, right));
+            }
+        },
+        None => {
+            let waiting = format!("Waiting for clients on {}", state.listen_addresses.join(", "));
+            lines.push(cell(&waiting, width, ""));
+            for _ in 1..body_height {
+                lines.push(String::new());
+            }
+        },
+    };
+
+    lines.push(render_status(state, width));
+    lines
+}
+
+fn render_tabs(state: &State, width: usize) -> String {
+    let active = state.active_client_id();
+    let mut line = String::new();
+    let mut used = 0;
+
+    for (index, client_id) in state.client_ids().iter().enumerate() {
+        let client = &state.clients[client_id];
+        let pin = if client.pinned { "• " } else { "" };
+        let label = format!(" {}{}: {} ", pin, index + 1, client.display_name());
+        let length = label.chars().count();
+        if used + length > width {
+            break;
+        }
+
+        let style = if active.as_ref() == Some(client_id) { SELECTED } else { "" };
+        line.push_str(&cell(&label, length, style));
+        used += length;
+    }
+
+    line
+}
+
+fn render_actions(state: &State, client: &Client, view: &View, width: usize, height: usize) -> Vec<String> {
+    let title_style = if view.pane == Pane::Actions { FOCUSED_TITLE } else { TITLE };
+    let title = if client.redundant_only {
+        format!("Actions, no-ops only ({})", client.visible_indices().len())
+    } else {
+        format!("Actions ({})", client.history.len())
+    };
+    let mut lines = vec![cell(&title, width, title_style)];
+
+    let rows = client.list_rows(state.config.group_domains);
+    let depths = client.causal_depths();
+    let selected = client.selected_history_index();
+    let is_selected = |row: &ListRow| match (row, selected) {
+        (&ListRow::Action { index, .. }, Some(selected)) => index == selected,
+        (&ListRow::Run { start, last, expanded: false, .. }, Some(selected)) => selected >= start && selected <= last,
+        _ => false,
+    };
+
+    let list_height = height.saturating_sub(1);
+    let cursor = rows.iter().position(&is_selected).unwrap_or(0);
+    for row in rows.iter().skip(scroll(cursor, list_height)).take(list_height) {
+        let text = match *row {
+            ListRow::Action { index, grouped } => {
+                let action = &client.history[index].action;
+                let indentation = if grouped { "    " } else { "" };
+                let cause = if depths[index] > 0 { format!("{}↳ ", "  ".repeat(depths[index] - 1)) } else { String::new() };
+                let duration = client.history[index].duration
+                    .map_or(String::new(), |duration| format!("  {}", timing::format_ms(duration)));
+                let badges: String = client.history[index].badges().iter()
+                    .map(|badge| format!("  [{}]", badge))
+                    .collect();
+                format!("{}{}{}:{}{}{}", indentation, cause, action.domain, action.invocation, duration, badges)
+            },
+            ListRow::Run { start, length, expanded, .. } => {
+                let arrow = if expanded { "▾" } else { "▸" };
+                format!("{} {} ×{}", arrow, client.history[start].action.domain, length)
+            },
+        };
+        lines.push(cell(&text, width, if is_selected(row) { SELECTED } else { "" }));
+    }
+
+    while lines.len() < height {
+        lines.push(cell("", width, ""));
+    }
+    lines
+}
+
+fn render_tree(client: &Client, view: &View, pane: Pane, width: usize, height: usize) -> Vec<String> {
+    let focused = view.pane == pane;
+    let title = match (pane, client.selected_history_item()) {
+        (Pane::Amplitude, _) => String::from("Amplitude"),
+        (_, Some(item)) => format!("State ({}, {} nodes)", growth::format_bytes(item.state.size()), item.node_count),
+        (_, None) => String::from("State"),
+    };
+    let mut lines = vec![cell(&title, width, if focused { FOCUSED_TITLE } else { TITLE })];
+
+    let mut highlighted = HashSet::new();
+    if let Some(item) = client.selected_history_item() {
+        if let Pane::State = pane {
+            for violation in &item.violations {
+                lines.push(cell(&format!("! {}", violation), width, WARNING));
+            }
+            for alert in &item.size_alerts {
+                lines.push(cell(&format!("! {}", alert), width, WARNING));
+            }
+        }
+        for error in item.schema_errors.iter().filter(|error| error.scope == pane.scope()) {
+            lines.push(cell(&format!("! {}", error), width, WARNING));
+        }
+        highlighted = schema::highlighted_paths(&item.schema_errors, pane.scope());
+    }
+
+    let tree = tree_lines(client, pane);
+    let tree_height = height.saturating_sub(lines.len());
+    let cursor = usize::min(view.cursor(pane), tree.len().saturating_sub(1));
+    for (index, line) in tree.iter().enumerate().skip(scroll(cursor, tree_height)).take(tree_height) {
+        let style = match (focused && index == cursor, line.page.is_none() && highlighted.contains(&line.path) && (line.container || !line.path.is_empty())) {
+            (true, _) => SELECTED,
+            (false, true) => WARNING,
+            (false, false) => "",
+        };
+        lines.push(cell(&line.text, width, style));
+    }
+
+    while lines.len() < height {
+        lines.push(cell("", width, ""));
+    }
+    lines.truncate(height);
+    lines
+}
+
+/// The timing of each action of `client`, in place of the json trees.
+fn render_stats(client: &Client, width: usize, height: usize) -> Vec<String> {
+    let format = |summary: &Option<timing::Summary>, pick: fn(&timing::Summary) -> f64| {
+        summary.as_ref().map_or(String::from("-"), |summary| timing::format_ms(pick(summary)))
+    };
+    let row = |action: &str, count: &str, mean: &str, p95: &str, max: &str, gap: &str| {
+        let action_width = width.saturating_sub(6 + 4 * 11);
+        format!("{}{:>6}{:>11}{:>11}{:>11}{:>11}", cell(action, action_width, ""), count, mean, p95, max, gap)
+    };
+
+    let mut lines = vec![
+        cell("Timing", width, TITLE),
+        cell(&row("action", "count", "mean", "p95", "max", "mean gap"), width, TITLE),
+    ];
+    for action in client.timings.actions().into_iter().take(height.saturating_sub(2)) {
+        let text = row(
+            &action.action,
+            &action.count.to_string(),
+            &format(&action.duration, |summary| summary.mean),
+            &format(&action.duration, |summary| summary.p95),
+            &format(&action.duration, |summary| summary.max),
+            &format(&action.interval, |summary| summary.mean),
+        );
+        lines.push(cell(&text, width, ""));
+    }
+
+    while lines.len() < height {
+        lines.push(cell("", width, ""));
+    }
+    lines
+}
+
+fn render_status(state: &State, width: usize) -> String {
+    if state.confirming_quit {
+        return cell("The history is not saved.  s save and quit  q quit  esc cancel", width, WARNING);
+    }
+
+    let status = match state.status {
+        Status::Initializing => "Starting",
+        Status::Running => "Running",
+        Status::Paused => "Paused",
+        Status::ShuttingDown => "Shutting down",
+    };
+    let violating = match state.violating_amount() {
+        0 => String::new(),
+        amount => format!("{} breaking invariants | ", amount),
+    };
+    let mismatching = match state.mismatching_amount() {
+        0 => String::new(),
+        amount => format!("{} not matching schemas | ", amount),
+    };
+    let growing = match state.growing_amount() {
+        0 => String::new(),
+        amount => format!("{} size alerts | ", amount),
+    };
+    let redundant = match state.redundant_amounts() {
+        (0, 0) => String::new(),
+        (unchanged, repeated) => format!("{} no-ops, {} repeats | ", unchanged, repeated),
+    };
+    let summary = format!("{} | {} clients | {} items | {}{}{}{}", status, state.client_amount(), state.item_amount(), violating, mismatching, growing, redundant);
+
+    match state.last_error {
+        Some(ref error) => format!("{}{}", summary, cell(error, width.saturating_sub(summary.chars().count()), WARNING)),
+        None => cell(&format!("{}{}", summary, HELP), width, ""),
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Read, Write};
+use std::mem;
+use libc;
+
+/// A key read from the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Escape,
+    Char(char),
+    /// A letter typed while holding Ctrl.
+    Ctrl(char),
+}
+
+/// Puts the terminal in raw mode on the alternate screen, and restores it when dropped.
+pub struct Terminal {
+    original: libc::termios,
+}
+
+impl Terminal {
+    pub fn new() -> Result<Terminal, io::Error> {
+        let original = unsafe {
+            let mut original: libc::termios = mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            original
+        };
+
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+
+        Ok(Terminal {
+            original: original,
+        })
+    }
+
+    /// The width and height of the terminal in characters.
+    pub fn size(&self) -> (usize, usize) {
+        unsafe {
+            let mut size: libc::winsize = mem::zeroed();
+            if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+                (size.ws_col as usize, size.ws_row as usize)
+            } else {
+                (80, 24)
+            }
+        }
+    }
+
+    /// Replaces the screen with `lines`.
+    pub fn draw(&self, lines: &[String]) -> Result<(), io::Error> {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+
+        write!(stdout, "\x1b[H")?;
+        for (row, line) in lines.iter().enumerate() {
+            write!(stdout, "\x1b[{};1H\x1b[2K{}\x1b[0m", row + 1, line)?;
+        }
+        write!(stdout, "\x1b[J")?;
+        stdout.flush()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        io::stdout().flush().ok();
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Blocks until input arrives, and returns the keys in it.
+pub fn read_keys() -> Result<Vec<Key>, io::Error> {
+    let mut buffer = [0; 64];
+    let amount = io::stdin().read(&mut buffer)?;
+    if amount == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the terminal closed"));
+    }
+
+    Ok(parse_keys(&buffer[..amount]))
+}
+
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut chars = text.chars().peekable();
+    let mut keys = Vec::new();
+
+    while let Some(character) = chars.next() {
+        let key = match character {
+            '\x1b' => match chars.peek().cloned() {
+                Some('[') | Some('O') => {
+                    chars.next();
+                    let mut sequence = String::new();
+                    for part in chars.by_ref() {
+                        sequence.push(part);
+                        if part.is_ascii_alphabetic() || part == '~' {
+                            break;
+                        }
+                    }
+                    match sequence.as_str() {
+                        "A" => Key::Up,
+                        "B" => Key::Down,
+                        "C" => Key::Right,
+                        "D" => Key::Left,
+                        "H" | "1~" | "7~" => Key::Home,
+                        "F" | "4~" | "8~" => Key::End,
+                        "Z" => Key::BackTab,
+                        _ => continue,
+                    }
+                },
+                _ => Key::Escape,
+            },
+            '\r' | '\n' => Key::Enter,
+            '\t' => Key::Tab,
+            '\x7f' | '\x08' => Key::Backspace,
+            character if (character as u32) < 27 => Key::Ctrl((b'a' + character as u8 - 1) as char),
+            character => Key::Char(character),
+        };
+        keys.push(key);
+    }
+
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_plain_and_control_keys() {
+        assert_eq!(parse_keys(b"q\r\t\x7f\x03"), vec![Key::Char('q'), Key::Enter, Key::Tab, Key::Backspace, Key::Ctrl('c')]);
+        assert_eq!(parse_keys("é".as_bytes()), vec![Key::Char('é')]);
+    }
+
+    #[test]
+    fn reads_escape_sequences() {
+        assert_eq!(parse_keys(b"\x1b[A\x1b[B\x1bOC\x1b[D"), vec![Key::Up, Key::Down, Key::Right, Key::Left]);
+        assert_eq!(parse_keys(b"\x1b[H\x1b[4~\x1b[1~\x1bOF\x1b[Z"), vec![Key::Home, Key::End, Key::Home, Key::End, Key::BackTab]);
+        assert_eq!(parse_keys(b"\x1b"), vec![Key::Escape]);
+    }
+
+    #[test]
+    fn skips_unknown_sequences() {
+        assert_eq!(parse_keys(b"\x1b[15~j\x1b[1;5Ak"), vec![Key::Char('j'), Key::Char('k')]);
+    }
+}
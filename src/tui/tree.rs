@@ -0,0 +1,69 @@
+use std::collections::{HashMap, HashSet};
+use serde_json::Value;
+use ::json_path;
+
+/// The amount of children of an array or object listed at once, the same as in the window's
+/// inspector.
+const PAGE_SIZE: usize = 100;
+
+/// One visible node of a json tree.
+pub struct Line {
+    pub text: String,
+    /// The json pointer of the node, shared with the window's inspectors so both open the same
+    /// nodes. For the lines standing in for the children of another page, the pointer of their
+    /// parent.
+    pub path: String,
+    pub container: bool,
+    /// The first child of the page the line stands in for, which it opens when picked.
+    pub page: Option<usize>,
+}
+
+/// Flattens `value` into a line per node, listing the children of the nodes in `expanded` only,
+/// a page at a time starting at the child in `pages`.
+pub fn lines(value: &Value, key: &str, expanded: &HashSet<String>, pages: &HashMap<String, usize>) -> Vec<Line> {
+    let mut lines = Vec::new();
+    push_lines(0, key, String::new(), value, expanded, pages, &mut lines);
+    lines
+}
+
+fn push_lines(depth: usize, key: &str, path: String, value: &Value, expanded: &HashSet<String>, pages: &HashMap<String, usize>, lines: &mut Vec<Line>) {
+    let indentation = "  ".repeat(depth);
+    let opened = expanded.contains(&path);
+    let marker = if opened { "▾" } else { "▸" };
+
+    let (children, summary): (Vec<(String, &Value)>, String) = match *value {
+        Value::Array(ref items) => (
+            items.iter().enumerate().map(|(index, item)| (index.to_string(), item)).collect(),
+            format!("[{} items]", items.len()),
+        ),
+        Value::Object(ref items) => (
+            items.iter().map(|(key, item)| (key.clone(), item)).collect(),
+            format!("{{{} keys}}", items.len()),
+        ),
+        ref leaf => {
+            lines.push(Line { text: format!("{}  {}: {}", indentation, key, leaf), path: path, container: false, page: None });
+            return;
+        },
+    };
+
+    lines.push(Line { text: format!("{}{} {}: {}", indentation, marker, key, summary), path: path.clone(), container: true, page: None });
+    if !opened {
+        return;
+    }
+
+    let total = children.len();
+    let start = usize::min(pages.get(&path).cloned().unwrap_or(0), total.saturating_sub(1));
+    let end = usize::min(start + PAGE_SIZE, total);
+
+    if start > 0 {
+        let text = format!("{}    … {} before, enter for the previous page", indentation, start);
+        lines.push(Line { text: text, path: path.clone(), container: false, page: Some(start.saturating_sub(PAGE_SIZE)) });
+    }
+    for &(ref child_key, child) in &children[start..end] {
+        push_lines(depth + 1, child_key, json_path::child(&path, child_key), child, expanded, pages, lines);
+    }
+    if end < total {
+        let text = format!("{}    … {} more, enter for the next page", indentation, total - end);
+        lines.push(Line { text: text, path: path.clone(), container: false, page: Some(end) });
+    }
+}
@@ -1,21 +1,79 @@
 use ::state::HistoryAction;
-use conrod::{widget, color, UiCell, Labelable, Borderable, Colorable};
+use ::timing;
+use ::ui::theme::Palette;
+use conrod::{widget, Color, UiCell, Labelable, Borderable, Colorable};
 use conrod::widget::list::{Item, Down, Fixed};
 
-pub fn render(parent: Item<Down, Fixed>, ui_cell: &mut UiCell, ref action: &HistoryAction) {
-    let text = format!("{}:{}", action.domain, action.invocation);
-    let (color, text_color) = match action.selected {
-        true => (color::BLUE, color::BLACK),
-        false => (color::LIGHT_BLUE, color::BLACK),
+/// The marks shown in front of an action of the given run grouping and causal depth.
+fn indentation(grouped: bool, depth: usize) -> String {
+    let mut indentation = String::from(if grouped { "    " } else { "" });
+    if depth > 0 {
+        indentation.push_str(&"  ".repeat(depth - 1));
+        indentation.push_str("→ ");
+    }
+
+    indentation
+}
+
+/// Renders an action in its domain's colour. Actions listed inside a run are indented, as are
+/// actions caused by another action. Causes and effects of the selected action are outlined, and
+/// actions failing a check carry a badge per check. The time the client took to handle the
+/// action follows its name when it was reported.
+pub fn render(parent: Item<Down, Fixed>, ui_cell: &mut UiCell, ref action: &HistoryAction, color: Color, grouped: bool, depth: usize, related: bool, duration: Option<f64>, badges: &[&str], ref palette: &Palette) {
+    let mut text = format!("{}{}:{}", indentation(grouped, depth), action.domain, action.invocation);
+    if let Some(duration) = duration {
+        text.push_str(&format!("  {}", timing::format_ms(duration)));
+    }
+    for badge in badges {
+        text.push_str(&format!("  [{}]", badge));
+    }
+
+    let button = button(ui_cell, &text, color, action.selected, related, palette);
+    let button = if !badges.is_empty() && !action.selected && !related {
+        button.border(3.0).border_color(palette.warning)
+    } else {
+        button
     };
 
-    let button = widget::Button::new()
-        .border(1.0)
-        .color(color)
-        .label(&text)
-        .label_font_size(16)
-        .label_color(text_color)
-        ;
+    parent.set(button, ui_cell);
+}
+
+/// Renders the header of a run of `length` consecutive actions of `domain`.
+pub fn render_run(parent: Item<Down, Fixed>, ui_cell: &mut UiCell, domain: &str, length: usize, expanded: bool, selected: bool, color: Color, ref palette: &Palette) {
+    let arrow = if expanded { "▼" } else { "►" };
+    let text = format!("{} {} ×{}", arrow, domain, length);
+
+    render_button(parent, ui_cell, &text, color, selected, false, palette);
+}
 
+/// Renders an action of the merged timeline, tagged with its client and the time since the row
+/// before it.
+pub fn render_merged(parent: Item<Down, Fixed>, ui_cell: &mut UiCell, client_name: &str, ref action: &HistoryAction, delay: Option<u64>, selected: bool, related: bool, color: Color, ref palette: &Palette) {
+    let text = match delay {
+        Some(delay) => format!("[{}] {}:{}  +{} ms", client_name, action.domain, action.invocation, delay),
+        None => format!("[{}] {}:{}", client_name, action.domain, action.invocation),
+    };
+
+    render_button(parent, ui_cell, &text, color, selected, related, palette);
+}
+
+fn render_button(parent: Item<Down, Fixed>, ui_cell: &mut UiCell, text: &str, color: Color, selected: bool, related: bool, ref palette: &Palette) {
+    let button = button(ui_cell, text, color, selected, related, palette);
     parent.set(button, ui_cell);
 }
+
+fn button<'a>(ui_cell: &UiCell, text: &'a str, color: Color, selected: bool, related: bool, ref palette: &Palette) -> widget::Button<'a, widget::button::Flat> {
+    let (border, border_color) = match (selected, related) {
+        (true, _) => (3.0, palette.highlight),
+        (false, true) => (3.0, palette.accent),
+        (false, false) => (1.0, palette.background),
+    };
+
+    widget::Button::new()
+        .border(border)
+        .border_color(border_color)
+        .color(color)
+        .label(text)
+        .label_font_size(ui_cell.theme.font_size_medium)
+        .label_color(palette.button_text)
+}
@@ -1,6 +1,7 @@
-use ::state::{State, Client};
+use ::state::{State, Client, ListRow};
 use ::ui::IdState;
-use ::ui::action;
+use ::ui::{action, theme};
+use ::ui::panes::{Panes, MARGIN};
 use ::actions::{Action, Message};
 use std::collections::HashSet;
 use conrod::{widget, UiCell, Positionable, Widget, Sizeable};
@@ -13,35 +14,101 @@ pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client,
     let ids = id_state.ids.action_lists.to_vec();
     let id = id_state.next(&ids, "action_lists").unwrap();
 
-    let (mut events, _scrollbar) = widget::ListSelect::multiple(client.history_item_amount())
+    let panes = Panes::of(ui_cell, state, client);
+    let rows = client.list_rows(state.config.group_domains);
+    let (mut events, _scrollbar) = widget::ListSelect::multiple(rows.len())
          .flow_down()
          .item_size(50.0)
          .scrollbar_next_to()
-         .w_h(250.0, 700.0)
-         .top_left_with_margins_on(parent_id, 10.0, 10.0)
+         .w_h(panes.list[0], panes.list[1])
+         .top_left_with_margins_on(parent_id, MARGIN, MARGIN)
          .set(id, ui_cell)
          ;
 
+    let palette = theme::palette(&state.config.theme);
+    let depths = client.causal_depths();
+    let relatives = match client.selected_history_index() {
+        Some(index) => state.causal_relatives(&(client.id.clone(), index)),
+        None => HashSet::new(),
+    };
     let selections = client.selections.clone();
-    while let Some(event) = events.next(ui_cell, |i| selections.contains(&i)) {
-        match event {
-            Event::Item(item) => action::render(item, ui_cell, &client.history[item.i].action),
-            Event::Selection(selection) => {
-                let mut selections = HashSet::new();
-                selection.update_index_set(&mut selections);
+    let is_selected = |position: usize| match rows[position] {
+        ListRow::Action { index, .. } => selections.contains(&index),
+        ListRow::Run { start, last, .. } => selections.iter().any(|index| *index >= start && *index <= last),
+    };
 
-                let action = Action {
-                    domain: String::from("action"),
-                    invocation: String::from("select"),
-                    message: Message::SelectAction((client.id.clone(), selections)),
-                    sender: String::from("ui"),
-                };
+    // The list works with row positions, the client with history indices. Only actions count
+    // as selected, so a run header showing up in the selection was just clicked.
+    let mut positions: HashSet<usize> = (0..rows.len())
+        .filter(|position| match rows[*position] {
+            ListRow::Action { index, .. } => selections.contains(&index),
+            ListRow::Run { .. } => false,
+        })
+        .collect();
+    let mut changed = false;
+    let mut from_keyboard = false;
 
-                state.dispatcher.send(action).unwrap();
+    while let Some(event) = events.next(ui_cell, &is_selected) {
+        match event {
+            Event::Item(item) => {
+                match rows[item.i] {
+                    ListRow::Action { index, grouped } => {
+                        let history_action = &client.history[index].action;
+                        let color = theme::domain_color(&state.config, &history_action.domain);
+                        let related = relatives.contains(&(client.id.clone(), index));
+                        let badges = client.history[index].badges();
+                        let duration = client.history[index].duration;
+                        action::render(item, ui_cell, history_action, color, grouped, depths[index], related, duration, &badges, &palette);
+                    },
+                    ListRow::Run { start, length, expanded, .. } => {
+                        let domain = &client.history[start].action.domain;
+                        let color = theme::domain_color(&state.config, domain);
+                        action::render_run(item, ui_cell, domain, length, expanded, is_selected(item.i), color, &palette);
+                    },
+                }
+            },
+            // Keyboard navigation is handled by the shortcuts, so the list's own is ignored.
+            Event::Press(press) => from_keyboard = press.key().is_some(),
+            Event::Click(_) => from_keyboard = false,
+            Event::Selection(selection) => {
+                if !from_keyboard {
+                    selection.update_index_set(&mut positions);
+                    changed = true;
+                }
             },
             _ => (),
         }
     }
 
+    if !changed {
+        return id_state;
+    }
+
+    let toggled_runs: HashSet<usize> = positions.iter()
+        .filter_map(|position| match rows[*position] {
+            ListRow::Run { start, .. } => Some(start),
+            ListRow::Action { .. } => None,
+        })
+        .collect();
+
+    let action = if toggled_runs.is_empty() {
+        let selections = positions.iter().map(|position| rows[*position].index()).collect();
+        Action {
+            domain: String::from("action"),
+            invocation: String::from("select"),
+            message: Message::SelectAction((client.id.clone(), selections)),
+            sender: String::from("ui"),
+        }
+    } else {
+        Action {
+            domain: String::from("action"),
+            invocation: String::from("toggle run"),
+            message: Message::SelectAction((client.id.clone(), toggled_runs)),
+            sender: String::from("ui"),
+        }
+    };
+
+    state.dispatcher.send(action).unwrap();
+
     id_state
 }
@@ -0,0 +1,187 @@
+use std::f64;
+use conrod::{self, widget, Widget, Colorable, Labelable, Positionable, Sizeable};
+
+const PADDING: f64 = 10.0;
+const HEADER_HEIGHT: f64 = 25.0;
+
+#[derive(WidgetCommon)]
+pub struct LineChart<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    style: Style,
+    title: &'a str,
+    x_label: &'a str,
+    points: &'a [[f64; 2]],
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    #[conrod(default = "conrod::color::DARK_CHARCOAL")]
+    pub color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::LIGHT_BLUE")]
+    pub line_color: Option<conrod::Color>,
+    #[conrod(default = "theme.label_color")]
+    pub label_color: Option<conrod::Color>,
+    #[conrod(default = "theme.font_size_small")]
+    pub label_font_size: Option<conrod::FontSize>,
+}
+
+widget_ids! {
+    struct Ids {
+        background,
+        title,
+        min_label,
+        max_label,
+        empty_label,
+        path,
+        axis_button,
+        close_button,
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+pub enum Event {
+    ToggleAxis,
+    Close,
+}
+
+impl<'a> LineChart<'a> {
+    pub fn new(title: &'a str, x_label: &'a str, points: &'a [[f64; 2]]) -> Self {
+        LineChart {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            title: title,
+            x_label: x_label,
+            points: points,
+        }
+    }
+
+    fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let fold = |(min, max): (f64, f64), value: f64| (f64::min(min, value), f64::max(max, value));
+        let (min_x, max_x) = self.points.iter().map(|p| p[0]).fold((f64::MAX, f64::MIN), &fold);
+        let (min_y, max_y) = self.points.iter().map(|p| p[1]).fold((f64::MAX, f64::MIN), &fold);
+
+        ([min_x, max_x], [min_y, max_y])
+    }
+}
+
+fn map_range(value: f64, from: [f64; 2], to: [f64; 2]) -> f64 {
+    let span = from[1] - from[0];
+    if span == 0.0 {
+        (to[0] + to[1]) / 2.0
+    } else {
+        to[0] + (value - from[0]) / span * (to[1] - to[0])
+    }
+}
+
+impl<'a> Widget for LineChart<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = Option<Event>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
+        let label_color = style.label_color(&ui.theme);
+        let font_size = style.label_font_size(&ui.theme);
+        let mut event = None;
+
+        widget::Rectangle::fill(rect.dim())
+            .middle_of(id)
+            .color(style.color(&ui.theme))
+            .graphics_for(id)
+            .set(state.ids.background, ui)
+            ;
+
+        widget::Text::new(self.title)
+            .top_left_with_margins_on(id, 6.0, PADDING)
+            .color(label_color)
+            .font_size(font_size)
+            .set(state.ids.title, ui)
+            ;
+
+        let close = widget::Button::new()
+            .w_h(25.0, 20.0)
+            .top_right_with_margins_on(id, 3.0, 3.0)
+            .label("x")
+            .label_font_size(font_size)
+            .set(state.ids.close_button, ui)
+            ;
+        if close.was_clicked() {
+            event = Some(Event::Close);
+        }
+
+        let axis = widget::Button::new()
+            .w_h(100.0, 20.0)
+            .left_from(state.ids.close_button, 3.0)
+            .label(&format!("by {}", self.x_label))
+            .label_font_size(font_size)
+            .set(state.ids.axis_button, ui)
+            ;
+        if axis.was_clicked() {
+            event = Some(Event::ToggleAxis);
+        }
+
+        if self.points.is_empty() {
+            widget::Text::new("no numeric values at this path")
+                .middle_of(id)
+                .color(label_color)
+                .font_size(font_size)
+                .set(state.ids.empty_label, ui)
+                ;
+
+            return event;
+        }
+
+        let (x_bounds, y_bounds) = self.bounds();
+        let x_range = [rect.left() + PADDING, rect.right() - PADDING];
+        let y_range = [rect.bottom() + PADDING, rect.top() - HEADER_HEIGHT - PADDING];
+        let points: Vec<[f64; 2]> = self.points.iter()
+            .map(|p| [map_range(p[0], x_bounds, x_range), map_range(p[1], y_bounds, y_range)])
+            .collect();
+
+        widget::PointPath::new(points)
+            .wh(rect.dim())
+            .xy(rect.xy())
+            .color(style.line_color(&ui.theme))
+            .thickness(1.5)
+            .parent(id)
+            .graphics_for(id)
+            .set(state.ids.path, ui)
+            ;
+
+        widget::Text::new(&format!("max {}", y_bounds[1]))
+            .top_left_with_margins_on(id, HEADER_HEIGHT, PADDING)
+            .color(label_color)
+            .font_size(font_size)
+            .graphics_for(id)
+            .set(state.ids.max_label, ui)
+            ;
+
+        widget::Text::new(&format!("min {}", y_bounds[0]))
+            .bottom_left_with_margins_on(id, 3.0, PADDING)
+            .color(label_color)
+            .font_size(font_size)
+            .graphics_for(id)
+            .set(state.ids.min_label, ui)
+            ;
+
+        event
+    }
+}
+
+impl<'a> Colorable for LineChart<'a> {
+    builder_method!(color { style.color = Some(conrod::Color) });
+}
@@ -1,5 +1,5 @@
 use ::state::{State, Client};
-use ::ui::{action_list, payload, item_state};
+use ::ui::{action_list, payload, item_state, history_timeline, state_chart, dividers, stats};
 use ::ui::id_state::IdState;
 use conrod::UiCell;
 
@@ -7,14 +7,20 @@ pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client,
     let canvases = id_state.ids.client_canvases.to_vec();
     id_state.next(&canvases, "client_canvases").unwrap();
 
+    id_state = history_timeline::render(id_state, ui_cell, client, state);
     id_state = action_list::render(id_state, ui_cell, client, state);
     id_state = match client.selected_history_item() {
         Some(item) => {
-            let mut id_state = payload::render(id_state, ui_cell, item, state);
-            item_state::render(id_state, ui_cell, item, state)
+            let mut id_state = payload::render(id_state, ui_cell, client, item, state);
+            item_state::render(id_state, ui_cell, client, item, state)
         },
         None => id_state,
     };
+    id_state = state_chart::render(id_state, ui_cell, client, state);
+    id_state = dividers::render(id_state, ui_cell, client, state);
+    if state.stats_view {
+        id_state = stats::render(id_state, ui_cell, client, state);
+    }
 
     id_state
 }
@@ -0,0 +1,88 @@
+use conrod::{self, widget, Widget, Colorable, Positionable};
+
+/// Which way the divider runs; a vertical divider splits panes left and right.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    Vertical,
+    Horizontal,
+}
+
+/// A thin bar between two panes that can be dragged to resize them.
+#[derive(WidgetCommon)]
+pub struct Divider {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    style: Style,
+    direction: Direction,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    #[conrod(default = "conrod::color::DARK_GREY")]
+    pub color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::WHITE")]
+    pub hover_color: Option<conrod::Color>,
+}
+
+widget_ids! {
+    struct Ids {
+        bar,
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+impl Divider {
+    pub fn new(direction: Direction) -> Self {
+        Divider {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            direction: direction,
+        }
+    }
+}
+
+impl Widget for Divider {
+    type State = State;
+    type Style = Style;
+    /// The absolute x (vertical) or y (horizontal) position the divider was dragged to.
+    type Event = Option<f64>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
+        let input = ui.widget_input(id);
+        let color = if input.mouse().is_some() { style.hover_color(&ui.theme) } else { style.color(&ui.theme) };
+
+        let position = input.drags().left()
+            .last()
+            .map(|drag| match self.direction {
+                Direction::Vertical => rect.x() + drag.to[0],
+                Direction::Horizontal => rect.y() + drag.to[1],
+            });
+
+        widget::Rectangle::fill(rect.dim())
+            .middle_of(id)
+            .color(color)
+            .graphics_for(id)
+            .set(state.ids.bar, ui)
+            ;
+
+        position
+    }
+}
+
+impl Colorable for Divider {
+    builder_method!(color { style.color = Some(conrod::Color) });
+}
@@ -0,0 +1,86 @@
+use ::state::{State, Client};
+use ::layout::Layout;
+use ::ui::id_state::IdState;
+use ::ui::divider::{Divider, Direction};
+use ::ui::panes::{Panes, MARGIN, GAP, TIMELINE_HEIGHT};
+use ::actions::{Action, Message};
+use conrod::{UiCell, Positionable, Widget, Sizeable};
+
+/// Renders the dividers between a client's panes, and stores where they are dragged to.
+pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client, state: &State) -> IdState {
+    let canvases = id_state.ids.client_canvases.to_vec();
+    let parent_id = id_state.last(&canvases, "client_canvases").unwrap();
+
+    let list_ids = id_state.ids.list_dividers.to_vec();
+    let list_id = id_state.next(&list_ids, "list_dividers").unwrap();
+    let payload_ids = id_state.ids.payload_dividers.to_vec();
+    let payload_id = id_state.next(&payload_ids, "payload_dividers").unwrap();
+    let chart_ids = id_state.ids.chart_dividers.to_vec();
+    let chart_id = id_state.next(&chart_ids, "chart_dividers").unwrap();
+
+    let canvas = match ui_cell.rect_of(parent_id) {
+        Some(canvas) => canvas,
+        None => return id_state,
+    };
+    let panes = Panes::of(ui_cell, state, client);
+    let right_x = canvas.right() - MARGIN - panes.right_width / 2.0;
+    let mut layout = state.layout.clone();
+    let mut changed = false;
+
+    let list_x = canvas.left() + MARGIN + panes.list[0] + GAP / 2.0;
+    let dragged = Divider::new(Direction::Vertical)
+        .w_h(GAP, panes.list[1])
+        .x_y(list_x, canvas.y())
+        .parent(parent_id)
+        .set(list_id, ui_cell)
+        ;
+    if let Some(x) = dragged {
+        layout.list_fraction = panes.list_fraction(x - canvas.left() - MARGIN - GAP / 2.0);
+        changed = true;
+    }
+
+    let inspectors_top = canvas.top() - MARGIN;
+    let payload_y = inspectors_top - panes.payload_height - GAP / 2.0;
+    let dragged = Divider::new(Direction::Horizontal)
+        .w_h(panes.right_width, GAP)
+        .x_y(right_x, payload_y)
+        .parent(parent_id)
+        .set(payload_id, ui_cell)
+        ;
+    if let Some(y) = dragged {
+        layout.payload_fraction = panes.payload_fraction(inspectors_top - GAP / 2.0 - y);
+        changed = true;
+    }
+
+    if client.chart.is_some() {
+        let chart_bottom = canvas.bottom() + MARGIN + TIMELINE_HEIGHT + GAP;
+        let chart_y = chart_bottom + panes.chart_height + GAP / 2.0;
+        let dragged = Divider::new(Direction::Horizontal)
+            .w_h(panes.right_width, GAP)
+            .x_y(right_x, chart_y)
+            .parent(parent_id)
+            .set(chart_id, ui_cell)
+            ;
+        if let Some(y) = dragged {
+            layout.chart_fraction = panes.chart_fraction(y - GAP / 2.0 - chart_bottom);
+            changed = true;
+        }
+    }
+
+    if changed {
+        dispatch_layout(layout, state);
+    }
+
+    id_state
+}
+
+pub fn dispatch_layout(layout: Layout, state: &State) {
+    let action = Action {
+        domain: String::from("layout"),
+        invocation: String::from("set"),
+        message: Message::Layout(layout),
+        sender: String::from("ui"),
+    };
+
+    state.dispatcher.send(action).unwrap();
+}
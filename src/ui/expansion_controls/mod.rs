@@ -0,0 +1,116 @@
+use conrod::{self, widget, Widget, Colorable, Labelable, Positionable, Sizeable};
+use ::state::Expansion;
+
+const BUTTON_HEIGHT: f64 = 20.0;
+
+/// A row of buttons to open or close every node of a `JsonInspector` at once.
+#[derive(WidgetCommon)]
+pub struct ExpansionControls {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    style: Style,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    #[conrod(default = "conrod::color::LIGHT_BLUE")]
+    pub color: Option<conrod::Color>,
+    #[conrod(default = "theme.font_size_small")]
+    pub label_font_size: Option<conrod::FontSize>,
+}
+
+widget_ids! {
+    struct Ids {
+        expand_all,
+        collapse_all,
+        depth,
+        expand_depth,
+    }
+}
+
+pub struct State {
+    ids: Ids,
+    depth: usize,
+}
+
+impl ExpansionControls {
+    pub fn new() -> Self {
+        ExpansionControls {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+        }
+    }
+}
+
+impl Widget for ExpansionControls {
+    type State = State;
+    type Style = Style;
+    type Event = Option<Expansion>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+            depth: 2,
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, style, ui, .. } = args;
+        let color = style.color(&ui.theme);
+        let font_size = style.label_font_size(&ui.theme);
+        let mut expansion = None;
+
+        let expand_all = widget::Button::new()
+            .w_h(90.0, BUTTON_HEIGHT)
+            .mid_left_of(id)
+            .color(color)
+            .label("Expand all")
+            .label_font_size(font_size)
+            .set(state.ids.expand_all, ui)
+            ;
+        if expand_all.was_clicked() {
+            expansion = Some(Expansion::All);
+        }
+
+        let collapse_all = widget::Button::new()
+            .w_h(90.0, BUTTON_HEIGHT)
+            .right_from(state.ids.expand_all, 5.0)
+            .color(color)
+            .label("Collapse all")
+            .label_font_size(font_size)
+            .set(state.ids.collapse_all, ui)
+            ;
+        if collapse_all.was_clicked() {
+            expansion = Some(Expansion::Nothing);
+        }
+
+        let expand_depth = widget::Button::new()
+            .w_h(110.0, BUTTON_HEIGHT)
+            .right_from(state.ids.collapse_all, 15.0)
+            .color(color)
+            .label("Expand to depth")
+            .label_font_size(font_size)
+            .set(state.ids.expand_depth, ui)
+            ;
+        if expand_depth.was_clicked() {
+            expansion = Some(Expansion::Depth(state.depth));
+        }
+
+        let depth = widget::NumberDialer::new(state.depth as f64, 1.0, 32.0, 0)
+            .w_h(40.0, BUTTON_HEIGHT)
+            .right_from(state.ids.expand_depth, 2.0)
+            .color(color)
+            .label_font_size(font_size)
+            .set(state.ids.depth, ui)
+            ;
+        if let Some(depth) = depth {
+            state.update(|state| state.depth = depth as usize);
+        }
+
+        expansion
+    }
+}
@@ -0,0 +1,39 @@
+use ::state::{State, Client};
+use ::ui::id_state::IdState;
+use ::ui::timeline;
+use ::ui::panes::{Panes, MARGIN, TIMELINE_HEIGHT};
+use ::actions::{Action, Message};
+use std::collections::HashSet;
+use conrod::{UiCell, Positionable, Widget, Sizeable};
+
+pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client, state: &State) -> IdState {
+    let canvases = id_state.ids.client_canvases.to_vec();
+    let parent_id = id_state.last(&canvases, "client_canvases").unwrap();
+
+    let ids = id_state.ids.timelines.to_vec();
+    let id = id_state.next(&ids, "timelines").unwrap();
+
+    let panes = Panes::of(ui_cell, state, client);
+    let timestamps = client.timestamps();
+    let selection = timeline::Timeline::new(&timestamps, client.selected_history_index())
+        .bottom_right_with_margins_on(parent_id, MARGIN, MARGIN)
+        .w_h(panes.right_width, TIMELINE_HEIGHT)
+        .set(id, ui_cell)
+        ;
+
+    if let Some(index) = selection {
+        let mut selections = HashSet::new();
+        selections.insert(index);
+
+        let action = Action {
+            domain: String::from("action"),
+            invocation: String::from("select"),
+            message: Message::SelectAction((client.id.clone(), selections)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    id_state
+}
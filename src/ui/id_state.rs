@@ -5,6 +5,10 @@ use ::ui::Ids;
 pub struct IdState {
     pub ids: Ids,
     indices: HashMap<String, usize>,
+    offset: usize,
+    /// The position of each client's ids in the per-client id lists. It stays the same while the
+    /// client is around, so its widgets keep their state when tabs are pinned or closed.
+    slots: HashMap<String, usize>,
 }
 
 impl IdState {
@@ -12,11 +16,24 @@ impl IdState {
         IdState {
             ids: ids,
             indices: HashMap::new(),
+            offset: 0,
+            slots: HashMap::new(),
         }
     }
 
+    /// Starts a frame, handing out ids from the start of every list again.
+    pub fn rewind(&mut self) {
+        self.indices.clear();
+        self.offset = 0;
+    }
+
+    /// The position of the ids of `client_id` in the per-client id lists.
+    pub fn slot(&self, client_id: &str) -> usize {
+        self.slots[client_id]
+    }
+
     pub fn next(&mut self, ref id_vec: &Vec<widget::Id>, key: &str) -> Option<widget::Id> {
-        let current_index = self.indices.entry(String::from(key)).or_insert(0);
+        let current_index = self.indices.entry(String::from(key)).or_insert(self.offset);
         match *current_index < id_vec.len() {
             true => {
                 let id = id_vec[*current_index];
@@ -40,11 +57,23 @@ impl IdState {
         }
     }
 
-    pub fn generate_client_widget_ids(&mut self, amount: usize, ui_cell: &mut UiCell) {
+    /// Gives each of `client_ids` a slot, reusing the slots of clients that are gone, and makes
+    /// sure every slot has its ids.
+    pub fn generate_client_widget_ids(&mut self, client_ids: &[String], ui_cell: &mut UiCell) {
+        assign_slots(&mut self.slots, client_ids);
+
+        let amount = self.slots.values().max().map_or(0, |slot| slot + 1);
         self.generate_client_canvases(amount, ui_cell);
         self.generate_action_lists(amount, ui_cell);
         self.generate_payload_texts(amount, ui_cell);
         self.generate_item_state_texts(amount, ui_cell);
+        self.generate_violation_texts(amount, ui_cell);
+        self.generate_timelines(amount, ui_cell);
+        self.generate_charts(amount, ui_cell);
+        self.generate_expansion_controls(amount, ui_cell);
+        self.generate_size_buttons(amount, ui_cell);
+        self.generate_tab_buttons(amount, ui_cell);
+        self.generate_dividers(amount, ui_cell);
     }
 
     fn generate_client_canvases(&mut self, amount: usize, ui_cell: &mut UiCell) {
@@ -63,11 +92,78 @@ impl IdState {
         self.ids.item_state_texts.resize(amount, &mut ui_cell.widget_id_generator());
     }
 
-    pub fn reset(&mut self) {
+    fn generate_violation_texts(&mut self, amount: usize, ui_cell: &mut UiCell) {
+        self.ids.violation_texts.resize(amount, &mut ui_cell.widget_id_generator());
+    }
+
+    fn generate_timelines(&mut self, amount: usize, ui_cell: &mut UiCell) {
+        self.ids.timelines.resize(amount, &mut ui_cell.widget_id_generator());
+    }
+
+    fn generate_charts(&mut self, amount: usize, ui_cell: &mut UiCell) {
+        self.ids.charts.resize(amount, &mut ui_cell.widget_id_generator());
+    }
+
+    fn generate_expansion_controls(&mut self, amount: usize, ui_cell: &mut UiCell) {
+        let generator = &mut ui_cell.widget_id_generator();
+        self.ids.expansion_controls.resize(amount, generator);
+        self.ids.payload_expansion_controls.resize(amount, generator);
+    }
+
+    fn generate_size_buttons(&mut self, amount: usize, ui_cell: &mut UiCell) {
+        let generator = &mut ui_cell.widget_id_generator();
+        self.ids.size_buttons.resize(amount, generator);
+        self.ids.node_buttons.resize(amount, generator);
+    }
+
+    fn generate_tab_buttons(&mut self, amount: usize, ui_cell: &mut UiCell) {
+        self.ids.tab_buttons.resize(amount, &mut ui_cell.widget_id_generator());
+    }
+
+    fn generate_dividers(&mut self, amount: usize, ui_cell: &mut UiCell) {
+        let generator = &mut ui_cell.widget_id_generator();
+        self.ids.list_dividers.resize(amount, generator);
+        self.ids.payload_dividers.resize(amount, generator);
+        self.ids.chart_dividers.resize(amount, generator);
+    }
+
+    /// Starts handing out the ids of `client_id`, so each client keeps its own widgets.
+    pub fn seek(&mut self, client_id: &str) {
         self.indices.clear();
+        self.offset = self.slot(client_id);
     }
+}
+
+/// Drops the slots of clients that are gone and gives each new client the lowest free slot.
+fn assign_slots(slots: &mut HashMap<String, usize>, client_ids: &[String]) {
+    slots.retain(|id, _| client_ids.contains(id));
+    for client_id in client_ids {
+        if !slots.contains_key(client_id) {
+            let free = (0..).find(|slot| !slots.values().any(|taken| taken == slot)).unwrap();
+            slots.insert(client_id.clone(), free);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(client_ids: &[&str]) -> Vec<String> {
+        client_ids.iter().map(|id| String::from(*id)).collect()
+    }
+
+    #[test]
+    fn clients_keep_their_slot_when_others_go_or_move() {
+        let mut slots = HashMap::new();
+        assign_slots(&mut slots, &ids(&["a", "b", "c"]));
+        assert_eq!((slots["a"], slots["b"], slots["c"]), (0, 1, 2));
+
+        assign_slots(&mut slots, &ids(&["a", "c"]));
+        assert_eq!(slots.len(), 2);
+        assert_eq!((slots["a"], slots["c"]), (0, 2));
 
-    pub fn unwrap(self) -> Ids {
-        self.ids
+        assign_slots(&mut slots, &ids(&["c", "a", "d"]));
+        assert_eq!((slots["a"], slots["c"], slots["d"]), (0, 2, 1));
     }
 }
@@ -1,24 +1,146 @@
-use ::state::{State, HistoryItem};
+use ::state::{State, Client, HistoryItem};
 use ::ui::id_state::IdState;
-use ::ui::json_inspector;
-use conrod::{UiCell, Positionable, Widget, Sizeable};
+use ::ui::{json_inspector, expansion_controls};
+use ::ui::panes::{Panes, GAP, CONTROLS_HEIGHT};
+use ::actions::{Action, Message};
+use ::schema;
+use ::growth;
+use std::collections::{HashMap, HashSet};
+use conrod::{widget, UiCell, Labelable, Positionable, Widget, Sizeable};
 use serde_json;
 
-pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref item: &HistoryItem, _state: &State) -> IdState {
+const SIZE_BUTTON_WIDTH: f64 = 90.0;
+const SIZE_BUTTON_HEIGHT: f64 = 20.0;
+
+/// Renders the state after the selected action, with its size and node count, which open a chart
+/// of them over the session when clicked.
+pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client, ref item: &HistoryItem, state: &State) -> IdState {
     let payload_texts = id_state.ids.payload_texts.to_vec();
     let sibling_id = id_state.last(&payload_texts, "payload_texts").unwrap();
 
+    let controls_ids = id_state.ids.expansion_controls.to_vec();
+    let controls_id = id_state.next(&controls_ids, "expansion_controls").unwrap();
+
     let ids = id_state.ids.item_state_texts.to_vec();
     let id = id_state.next(&ids, "item_state_texts").unwrap();
 
-    let json_value = serde_json::from_str(item.state.content.as_str()).unwrap();
+    let panes = Panes::of(ui_cell, state, client);
+    let expansion = expansion_controls::ExpansionControls::new()
+        .down_from(sibling_id, GAP)
+        .w_h(panes.right_width, CONTROLS_HEIGHT)
+        .set(controls_id, ui_cell)
+        ;
+
+    if let Some(expansion) = expansion {
+        let action = Action {
+            domain: String::from("inspector"),
+            invocation: String::from("expand"),
+            message: Message::Expansion((client.id.clone(), String::from("state"), expansion)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    let size_ids = id_state.ids.size_buttons.to_vec();
+    let size_id = id_state.next(&size_ids, "size_buttons").unwrap();
+    let node_ids = id_state.ids.node_buttons.to_vec();
+    let node_id = id_state.next(&node_ids, "node_buttons").unwrap();
+
+    let size = widget::Button::new()
+        .w_h(SIZE_BUTTON_WIDTH, SIZE_BUTTON_HEIGHT)
+        .mid_right_of(controls_id)
+        .label(&growth::format_bytes(item.state.size()))
+        .label_font_size(ui_cell.theme.font_size_small)
+        .set(size_id, ui_cell)
+        ;
+    let nodes = widget::Button::new()
+        .w_h(SIZE_BUTTON_WIDTH, SIZE_BUTTON_HEIGHT)
+        .left_from(size_id, GAP)
+        .label(&format!("{} nodes", item.node_count))
+        .label_font_size(ui_cell.theme.font_size_small)
+        .set(node_id, ui_cell)
+        ;
+
+    let chart = if size.was_clicked() {
+        Some("select size")
+    } else if nodes.was_clicked() {
+        Some("select nodes")
+    } else {
+        None
+    };
+    if let Some(invocation) = chart {
+        let action = Action {
+            domain: String::from("chart"),
+            invocation: String::from(invocation),
+            message: Message::ClientId(client.id.clone()),
+            sender: String::from("ui"),
+        };
 
-    json_inspector::JsonInspector::new(json_value, String::from("state"))
-        .down_from(sibling_id, 20.0)
-        .w_h(700.0, 450.0)
+        state.dispatcher.send(action).unwrap();
+    }
+
+    let json_value = serde_json::from_str(item.state.content.as_str())
+        .unwrap_or_else(|_| serde_json::Value::String(item.state.content.clone()));
+    let no_paths = HashSet::new();
+    let expanded = client.expanded_paths("state").unwrap_or(&no_paths);
+    let no_pages = HashMap::new();
+    let pages = client.page_starts("state").unwrap_or(&no_pages);
+    let highlighted = schema::highlighted_paths(&item.schema_errors, "state");
+
+    let event = json_inspector::JsonInspector::new(&json_value, String::from("state"), expanded)
+        .pages(pages)
+        .highlighted(&highlighted)
+        .editable()
+        .down_from(controls_id, GAP)
+        .w_h(panes.right_width, panes.state_height)
         .scroll_kids()
         .set(id, ui_cell)
         ;
 
+    if let Some(path) = event.toggled {
+        let action = Action {
+            domain: String::from("inspector"),
+            invocation: String::from("toggle"),
+            message: Message::InspectorPath((client.id.clone(), String::from("state"), path)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    if let Some(path) = event.selected {
+        let action = Action {
+            domain: String::from("chart"),
+            invocation: String::from("select"),
+            message: Message::JsonPath((client.id.clone(), path)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    if let (Some(edit), Some(index)) = (event.edited, client.selected_history_index()) {
+        let action = Action {
+            domain: String::from("state"),
+            invocation: String::from("edit"),
+            message: Message::EditState((client.id.clone(), index, edit)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    if let Some((path, start)) = event.paged {
+        let action = Action {
+            domain: String::from("inspector"),
+            invocation: String::from("page"),
+            message: Message::InspectorPage((client.id.clone(), String::from("state"), path, start)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
     id_state
 }
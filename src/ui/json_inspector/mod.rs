@@ -1,14 +1,32 @@
-use std::f64;
-use conrod::{self, widget, Widget, Colorable, Labelable, Positionable, Sizeable, UiCell};
+pub mod row;
+
+use std::collections::{HashMap, HashSet};
+use conrod::{self, widget, Widget, Positionable, Sizeable};
 use serde_json;
+use ::json_path;
+use ::state::Edit;
+use self::row::{Row, Kind};
+
+/// The amount of children of an array or object shown at once.
+pub const PAGE_SIZE: usize = 100;
+const ROW_HEIGHT: f64 = 25.0;
+const ROW_WIDTH: f64 = 1500.0;
 
+/// Shows a json document as a tree, one row per visible node.
+///
+/// The tree is flattened into rows for the opened nodes only, and only the rows scrolled into
+/// view are instantiated as widgets, so huge documents stay responsive.
 #[derive(WidgetCommon)]
-pub struct JsonInspector {
+pub struct JsonInspector<'a> {
     #[conrod(common_builder)]
     common: widget::CommonBuilder,
     style: Style,
     key: String,
-    content: serde_json::Value,
+    content: &'a serde_json::Value,
+    expanded: &'a HashSet<String>,
+    pages: Option<&'a HashMap<String, usize>>,
+    highlighted: Option<&'a HashSet<String>>,
+    editable: bool,
 }
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
@@ -25,24 +43,38 @@ pub struct Style {
 
 widget_ids! {
     struct Ids {
-        key,
-        item,
-        json,
-        button,
+        rows,
         scroll_horizontal,
-        scroll_vertical,
     }
 }
 
+/// The json pointers of the nodes the user picked, toggled open or closed, paged through or
+/// edited.
+pub struct Event {
+    pub selected: Option<String>,
+    pub toggled: Option<String>,
+    pub paged: Option<(String, usize)>,
+    pub edited: Option<Edit>,
+}
+
 pub struct State {
     ids: Ids,
-    opened: bool,
-    height: f64,
-    children_heights: Option<Vec<f64>>,
+    /// The json pointer of the value being edited, and the text typed so far.
+    editing: Option<(String, String)>,
+    /// The json pointer of the object a key is being added to, and the key typed so far.
+    naming: Option<(String, String)>,
+}
+
+struct Line<'a> {
+    depth: usize,
+    key: String,
+    path: String,
+    value: &'a serde_json::Value,
+    kind: Kind,
 }
 
-impl JsonInspector {
-    pub fn new(content: serde_json::Value, mut key: String) -> Self {
+impl<'a> JsonInspector<'a> {
+    pub fn new(content: &'a serde_json::Value, mut key: String, expanded: &'a HashSet<String>) -> Self {
         key.push_str(": ");
 
         JsonInspector {
@@ -50,169 +82,192 @@ impl JsonInspector {
             style: Style::default(),
             key: key,
             content: content,
+            expanded: expanded,
+            pages: None,
+            highlighted: None,
+            editable: false,
         }
     }
 
-    fn key_id(&self, state: &State) -> widget::Id {
-        match self.content {
-            serde_json::Value::Array(_) | serde_json::Value::Object(_) => state.ids.button,
-            _ => state.ids.key,
-        }
+    /// Allows leaf values to be edited, and keys and items to be added and removed.
+    pub fn editable(mut self) -> Self {
+        self.editable = true;
+        self
     }
 
-    fn key_button_width(ref key: &String) -> f64 {
-        f64::max(150.0, (key.len() * 10) as f64)
+    /// The first child index shown, per json pointer of the arrays and objects paged through.
+    pub fn pages(mut self, pages: &'a HashMap<String, usize>) -> Self {
+        self.pages = Some(pages);
+        self
     }
 
-    fn make_key(&self, parent_id: widget::Id, state: &State, key: String, ui: &mut UiCell) -> Option<()> {
-        match self.content {
-            serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
-                let color = if state.opened { conrod::color::LIGHT_BLUE } else { conrod::color::BLUE };
-                let button = widget::Button::new()
-                    .color(color)
-                    .w_h(JsonInspector::key_button_width(&key), 25.0)
-                    .top_left_of(parent_id)
-                    .label(key.as_str())
-                    .set(state.ids.button, ui)
-                    ;
-
-                if button.was_clicked() { Some(()) } else { None }
-            },
-            _ => {
-                widget::Text::new(key.as_str())
-                    .top_left_of(parent_id)
-                    .set(state.ids.key, ui)
-                    ;
-
-                None
-            },
-        }
+    /// The json pointers of the nodes to draw in the warning colour.
+    pub fn highlighted(mut self, highlighted: &'a HashSet<String>) -> Self {
+        self.highlighted = Some(highlighted);
+        self
     }
 
-    fn make_string_content(&self, id: widget::Id, key_id: widget::Id, content: String, ui: &mut UiCell) {
-        widget::Text::new(content.as_str())
-            .right_from(key_id, 1.0)
-            .set(id, ui)
-            ;
+    fn page_start(&self, path: &str) -> usize {
+        self.pages
+            .and_then(|pages| pages.get(path))
+            .cloned()
+            .unwrap_or(0)
     }
 
-    fn make_list_content(&self, state: &State, ui: &mut UiCell) -> Option<Vec<f64>> {
-        if state.opened {
-            let (mut items, _scrollbar) = widget::List::flow_down(self.item_amount())
-                .w_h(1500.0, state.height)
-                .right_from(self.key_id(state), 5.0)
-                .set(state.ids.json, ui)
-                ;
-
-            let mut children_heights = Vec::new();
-            while let Some(item) = items.next(ui) {
-                let (key, json_item) = self.get_item(item.i).expect("json item index out of range");
-                let json_insp = JsonInspector::new(json_item, key.clone())
-                    .w_h(1500.0, self.get_item_height(item.i, state));
-
-                let child_height = item.set(json_insp, ui);
+    fn lines(&self) -> Vec<Line<'a>> {
+        let mut lines = Vec::new();
+        self.push_lines(0, self.key.clone(), String::new(), self.content, &mut lines);
+        lines
+    }
 
-                children_heights.push(child_height);
-            };
+    fn push_lines(&self, depth: usize, key: String, path: String, value: &'a serde_json::Value, lines: &mut Vec<Line<'a>>) {
+        let opened = self.expanded.contains(&path);
+        lines.push(Line { depth: depth, key: key, path: path.clone(), value: value, kind: Kind::Node });
 
-            if children_heights.len() == 0 { None } else { Some(children_heights) }
-        } else {
-            None
+        if !opened {
+            return;
         }
-    }
 
-    fn item_amount(&self) -> usize {
-        match self.content {
-            serde_json::Value::Array(ref v) => v.len(),
-            serde_json::Value::Object(ref v) => v.len(),
-            _ => 1,
-        }
-    }
+        let total = match *value {
+            serde_json::Value::Array(ref items) => items.len(),
+            serde_json::Value::Object(ref items) => items.len(),
+            _ => return,
+        };
+        let start = usize::min(self.page_start(&path), total.saturating_sub(1));
+        let end = usize::min(start + PAGE_SIZE, total);
 
-    fn unwrap_item(&self) -> Option<String> {
-        match self.content {
-            serde_json::Value::Null => Some(String::from("null")),
-            serde_json::Value::Bool(ref v) => {
-                if *v {
-                    Some(String::from("true"))
-                } else {
-                    Some(String::from("false"))
+        match *value {
+            serde_json::Value::Array(ref items) => {
+                for (index, item) in items[start..end].iter().enumerate() {
+                    let key = (start + index).to_string();
+                    let child_path = json_path::child(&path, &key);
+                    self.push_lines(depth + 1, format!("{}: ", key), child_path, item, lines);
                 }
             },
-            serde_json::Value::Number(ref v) => Some(v.to_string()),
-            serde_json::Value::String(ref v) => Some(v.clone()),
-            _ => None,
-        }
-    }
-
-    fn get_item(&self, index: usize) -> Option<(String, serde_json::Value)> {
-        match self.content {
-            serde_json::Value::Array(ref v) => {
-                v.get(index).map(|ref item| (index.to_string(), (*item).clone()))
-            },
-            serde_json::Value::Object(ref v) => {
-                v.iter().nth(index).map(|(ref key, ref value)| ((*key).clone(), (*value).clone()))
+            serde_json::Value::Object(ref items) => {
+                for (key, item) in items.iter().skip(start).take(end - start) {
+                    let child_path = json_path::child(&path, key);
+                    self.push_lines(depth + 1, format!("{}: ", key), child_path, item, lines);
+                }
             },
-            _ => None,
+            _ => (),
         }
-    }
 
-    fn get_item_height(&self, index: usize, state: &State) -> f64 {
-        *state.children_heights
-            .as_ref()
-            .unwrap_or(&Vec::new())
-            .get(index)
-            .unwrap_or(&25.0)
+        if total > PAGE_SIZE {
+            let kind = Kind::Pager { start: start, page_size: PAGE_SIZE, total: total };
+            lines.push(Line { depth: depth + 1, key: String::new(), path: path, value: value, kind: kind });
+        }
     }
 }
 
-impl Widget for JsonInspector {
+impl<'a> Widget for JsonInspector<'a> {
     type State = State;
     type Style = Style;
-    type Event = f64;
+    type Event = Event;
 
     fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
-        let ids = Ids::new(id_gen);
         State {
-            ids: ids,
-            opened: false,
-            height: 25.0,
-            children_heights: None,
+            ids: Ids::new(id_gen),
+            editing: None,
+            naming: None,
         }
     }
 
     fn style(&self) -> Self::Style {
-        self.style.clone()
+        self.style
     }
 
     fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
-        let widget::UpdateArgs { id, state, ui, .. } = args;
-        let mut height = 0.0;
+        let widget::UpdateArgs { id, state, rect, ui, .. } = args;
+        let mut event = Event {
+            selected: None,
+            toggled: None,
+            paged: None,
+            edited: None,
+        };
 
-        widget::Scrollbar::x_axis(id).auto_hide(true).set(state.ids.scroll_horizontal, ui);
-        widget::Scrollbar::y_axis(id).auto_hide(true).set(state.ids.scroll_vertical, ui);
+        let lines = self.lines();
+        let (mut items, scrollbar) = widget::List::flow_down(lines.len())
+            .item_size(ROW_HEIGHT)
+            .scrollbar_on_top()
+            .w_h(ROW_WIDTH, rect.h())
+            .top_left_of(id)
+            .set(state.ids.rows, ui)
+            ;
 
-        match self.make_key(id, state, self.key.clone(), ui) {
-            Some(()) => state.update(|state| state.opened = !state.opened),
-            _ => (),
-        };
+        while let Some(item) = items.next(ui) {
+            let line = &lines[item.i];
+            let opened = self.expanded.contains(&line.path);
+            let edit_text = state.editing.iter().chain(state.naming.iter())
+                .find(|&&(ref path, _)| *path == line.path)
+                .map(|&(_, ref text)| text.as_str());
 
-        match self.content {
-            serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
-                let children_heights = self.make_list_content(&state, ui);
-                state.update(|state| state.children_heights = children_heights);
-                let sum: f64 = state.children_heights.as_ref().unwrap_or(&vec![25.0]).iter().sum();
-                height += sum;
-            },
-            _ => {
-                let item = self.unwrap_item().unwrap();
-                self.make_string_content(state.ids.item, state.ids.key, item, ui);
-                height += 25.0;
-            },
+            let highlighted = self.highlighted.map_or(false, |highlighted| highlighted.contains(&line.path));
+            let mut row = Row::new(line.depth, &line.key, line.value, line.kind, opened)
+                .edit_text(edit_text)
+                .highlighted(highlighted);
+            if self.editable {
+                row = row.editable(!line.path.is_empty());
+            }
+
+            match item.set(row, ui) {
+                Some(row::Event::Toggle) => event.toggled = Some(line.path.clone()),
+                Some(row::Event::Select) => event.selected = Some(line.path.clone()),
+                Some(row::Event::Page(start)) => event.paged = Some((line.path.clone(), start)),
+                Some(row::Event::StartEdit) => {
+                    let text = serde_json::to_string(line.value).unwrap_or_default();
+                    let editing = match state.editing {
+                        Some((ref path, _)) if *path == line.path => None,
+                        _ => Some((line.path.clone(), text)),
+                    };
+                    state.update(|state| state.editing = editing);
+                },
+                Some(row::Event::EditText(text)) => {
+                    let naming = state.naming.as_ref().map_or(false, |&(ref path, _)| *path == line.path);
+                    if naming {
+                        state.update(|state| state.naming = Some((line.path.clone(), text)));
+                    } else {
+                        state.update(|state| state.editing = Some((line.path.clone(), text)));
+                    }
+                },
+                Some(row::Event::CommitEdit) => {
+                    match (state.editing.clone(), state.naming.clone()) {
+                        (Some((path, text)), _) if path == line.path => {
+                            let value = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+                            event.edited = Some(Edit::Set(path, value));
+                        },
+                        (_, Some((path, key))) if path == line.path && !key.is_empty() => {
+                            event.edited = Some(Edit::AddChild(path, Some(key)));
+                        },
+                        _ => (),
+                    };
+                    state.update(|state| {
+                        state.editing = None;
+                        state.naming = None;
+                    });
+                },
+                // Arrays get their item right away, objects ask for the key first.
+                Some(row::Event::AddChild) => match *line.value {
+                    serde_json::Value::Object(_) => {
+                        let naming = match state.naming {
+                            Some((ref path, _)) if *path == line.path => None,
+                            _ => Some((line.path.clone(), String::new())),
+                        };
+                        state.update(|state| state.naming = naming);
+                    },
+                    _ => event.edited = Some(Edit::AddChild(line.path.clone(), None)),
+                },
+                Some(row::Event::Remove) => event.edited = Some(Edit::Remove(line.path.clone())),
+                None => (),
+            }
+        }
+
+        if let Some(scrollbar) = scrollbar {
+            scrollbar.set(ui);
         }
 
-        state.update(|state| state.height = height);
+        widget::Scrollbar::x_axis(id).auto_hide(true).set(state.ids.scroll_horizontal, ui);
 
-        height
+        event
     }
 }
@@ -0,0 +1,390 @@
+use std::f64;
+use conrod::{self, widget, Widget, Colorable, Labelable, Positionable, Sizeable};
+use serde_json;
+
+const INDENTATION: f64 = 20.0;
+/// Strings longer than this are cut short until clicked.
+const MAX_STRING_LENGTH: usize = 80;
+
+/// What a single line of the flattened json tree displays.
+#[derive(Copy, Clone)]
+pub enum Kind {
+    Node,
+    /// Paging controls below a long array or object, showing `start..start + page_size` of `total`.
+    Pager { start: usize, page_size: usize, total: usize },
+}
+
+#[derive(WidgetCommon)]
+pub struct Row<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    style: Style,
+    depth: usize,
+    key: &'a str,
+    value: &'a serde_json::Value,
+    kind: Kind,
+    opened: bool,
+    editable: bool,
+    removable: bool,
+    edit_text: Option<&'a str>,
+    highlighted: bool,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    #[conrod(default = "conrod::color::BLUE")]
+    pub color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::LIGHT_BLUE")]
+    pub opened_color: Option<conrod::Color>,
+    #[conrod(default = "theme.label_color")]
+    pub label_color: Option<conrod::Color>,
+    #[conrod(default = "theme.font_size_medium")]
+    pub label_font_size: Option<conrod::FontSize>,
+    #[conrod(default = "conrod::color::LIGHT_GREEN")]
+    pub string_color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::LIGHT_ORANGE")]
+    pub number_color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::LIGHT_PURPLE")]
+    pub bool_color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::GREY")]
+    pub null_color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::GREY")]
+    pub summary_color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::LIGHT_RED")]
+    pub warning_color: Option<conrod::Color>,
+}
+
+widget_ids! {
+    struct Ids {
+        key,
+        item,
+        button,
+        value_button,
+        length_button,
+        summary,
+        edit_button,
+        edit_box,
+        add_button,
+        remove_button,
+        previous_button,
+        next_button,
+        page_text,
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+pub enum Event {
+    Toggle,
+    Select,
+    Page(usize),
+    StartEdit,
+    EditText(String),
+    CommitEdit,
+    AddChild,
+    Remove,
+}
+
+impl<'a> Row<'a> {
+    pub fn new(depth: usize, key: &'a str, value: &'a serde_json::Value, kind: Kind, opened: bool) -> Self {
+        Row {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            depth: depth,
+            key: key,
+            value: value,
+            kind: kind,
+            opened: opened,
+            editable: false,
+            removable: false,
+            edit_text: None,
+            highlighted: false,
+        }
+    }
+
+    /// Shows buttons to edit the row, and to remove it from its parent if `removable`.
+    pub fn editable(mut self, removable: bool) -> Self {
+        self.editable = true;
+        self.removable = removable;
+        self
+    }
+
+    /// Replaces the value with a text box holding `text`. On an object, the text names the key to
+    /// add.
+    pub fn edit_text(mut self, text: Option<&'a str>) -> Self {
+        self.edit_text = text;
+        self
+    }
+
+    /// Draws the key in the warning colour, for nodes that fail a check.
+    pub fn highlighted(mut self, highlighted: bool) -> Self {
+        self.highlighted = highlighted;
+        self
+    }
+
+    fn key_button_width(key: &str) -> f64 {
+        f64::max(150.0, (key.len() * 10) as f64)
+    }
+
+    /// Strings are quoted and escaped, and cut short unless the row is opened.
+    fn unwrap_item(&self) -> String {
+        match *self.value {
+            serde_json::Value::Null => String::from("null"),
+            serde_json::Value::Bool(ref v) => v.to_string(),
+            serde_json::Value::Number(ref v) => v.to_string(),
+            serde_json::Value::String(ref v) => {
+                if self.opened || !Row::is_long_string(v) {
+                    serde_json::to_string(v).unwrap_or_else(|_| v.clone())
+                } else {
+                    let shortened: String = v.chars().take(MAX_STRING_LENGTH).collect();
+                    let quoted = serde_json::to_string(&shortened).unwrap();
+                    format!("{}…\" ({} chars)", &quoted[..quoted.len() - 1], v.chars().count())
+                }
+            },
+            _ => String::new(),
+        }
+    }
+
+    fn is_long_string(value: &str) -> bool {
+        value.chars().count() > MAX_STRING_LENGTH
+    }
+
+    fn value_color(&self, style: &Style, theme: &conrod::Theme) -> conrod::Color {
+        match *self.value {
+            serde_json::Value::Null => style.null_color(theme),
+            serde_json::Value::Bool(_) => style.bool_color(theme),
+            serde_json::Value::Number(_) => style.number_color(theme),
+            serde_json::Value::String(_) => style.string_color(theme),
+            _ => style.label_color(theme),
+        }
+    }
+
+    /// Describes a collapsed array or object, e.g. `[3 items]` or `{12 keys}`.
+    fn summary(&self) -> Option<String> {
+        let plural = |amount: usize, word: &str| {
+            if amount == 1 { format!("1 {}", word) } else { format!("{} {}s", group_thousands(amount), word) }
+        };
+
+        match *self.value {
+            serde_json::Value::Array(ref v) => Some(format!("[{}]", plural(v.len(), "item"))),
+            serde_json::Value::Object(ref v) => Some(format!("{{{}}}", plural(v.len(), "key"))),
+            _ => None,
+        }
+    }
+}
+
+/// Formats `1234567` as `1,234,567`.
+pub fn group_thousands(amount: usize) -> String {
+    let digits = amount.to_string();
+    let mut grouped = String::new();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
+
+impl<'a> Widget for Row<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = Option<Event>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, style, ui, .. } = args;
+        let indentation = self.depth as f64 * INDENTATION;
+        let color = style.color(&ui.theme);
+        let font_size = style.label_font_size(&ui.theme);
+        let mut event = None;
+
+        if let Kind::Pager { start, page_size, total } = self.kind {
+            let end = usize::min(start + page_size, total);
+
+            let previous = widget::Button::new()
+                .w_h(25.0, 23.0)
+                .mid_left_with_margin_on(id, indentation)
+                .color(color)
+                .label("<")
+                .set(state.ids.previous_button, ui)
+                ;
+            if previous.was_clicked() && start > 0 {
+                event = Some(Event::Page(start.saturating_sub(page_size)));
+            }
+
+            widget::Text::new(&format!("[{}..{}] of {}", start, end, group_thousands(total)))
+                .right_from(state.ids.previous_button, 5.0)
+                .font_size(font_size)
+                .color(style.label_color(&ui.theme))
+                .set(state.ids.page_text, ui)
+                ;
+
+            let next = widget::Button::new()
+                .w_h(25.0, 23.0)
+                .right_from(state.ids.page_text, 5.0)
+                .color(color)
+                .label(">")
+                .set(state.ids.next_button, ui)
+                ;
+            if next.was_clicked() && end < total {
+                event = Some(Event::Page(end));
+            }
+
+            return event;
+        }
+
+        let is_container = match *self.value {
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => true,
+            _ => false,
+        };
+
+        let key_color = if self.highlighted { style.warning_color(&ui.theme) } else { style.label_color(&ui.theme) };
+        let mut last_id = if is_container {
+            let color = match (self.highlighted, self.opened) {
+                (true, _) => style.warning_color(&ui.theme),
+                (false, true) => style.opened_color(&ui.theme),
+                (false, false) => color,
+            };
+            let button = widget::Button::new()
+                .color(color)
+                .w_h(Row::key_button_width(self.key), 23.0)
+                .mid_left_with_margin_on(id, indentation)
+                .label(self.key)
+                .set(state.ids.button, ui)
+                ;
+            if button.was_clicked() {
+                event = Some(Event::Toggle);
+            }
+            state.ids.button
+        } else {
+            widget::Text::new(self.key)
+                .mid_left_with_margin_on(id, indentation)
+                .font_size(font_size)
+                .color(key_color)
+                .set(state.ids.key, ui)
+                ;
+            state.ids.key
+        };
+
+        match (self.edit_text, self.value) {
+            (Some(text), _) => {
+                for edit in widget::TextBox::new(text)
+                    .w_h(400.0, 23.0)
+                    .right_from(last_id, 1.0)
+                    .font_size(font_size)
+                    .set(state.ids.edit_box, ui)
+                {
+                    event = match edit {
+                        widget::text_box::Event::Update(text) => Some(Event::EditText(text)),
+                        widget::text_box::Event::Enter => Some(Event::CommitEdit),
+                    };
+                }
+                last_id = state.ids.edit_box;
+            },
+            (None, &serde_json::Value::Array(_)) => {
+                let length = widget::Button::new()
+                    .color(style.opened_color(&ui.theme))
+                    .w_h(25.0, 23.0)
+                    .right_from(last_id, 1.0)
+                    .label("#")
+                    .set(state.ids.length_button, ui)
+                    ;
+                if length.was_clicked() {
+                    event = Some(Event::Select);
+                }
+                last_id = state.ids.length_button;
+            },
+            (None, &serde_json::Value::Number(_)) => {
+                let content = self.unwrap_item();
+                let button = widget::Button::new()
+                    .color(style.color(&ui.theme))
+                    .w_h(f64::max(50.0, (content.len() * 10) as f64), 23.0)
+                    .right_from(last_id, 1.0)
+                    .label(content.as_str())
+                    .label_color(style.number_color(&ui.theme))
+                    .set(state.ids.value_button, ui)
+                    ;
+                if button.was_clicked() {
+                    event = Some(Event::Select);
+                }
+                last_id = state.ids.value_button;
+            },
+            (None, &serde_json::Value::Object(_)) => (),
+            (None, _) => {
+                widget::Text::new(self.unwrap_item().as_str())
+                    .right_from(last_id, 1.0)
+                    .font_size(font_size)
+                    .color(self.value_color(&style, &ui.theme))
+                    .set(state.ids.item, ui)
+                    ;
+
+                let is_long_string = match *self.value {
+                    serde_json::Value::String(ref v) => Row::is_long_string(v),
+                    _ => false,
+                };
+                if is_long_string && ui.widget_input(state.ids.item).clicks().left().next().is_some() {
+                    event = Some(Event::Toggle);
+                }
+                last_id = state.ids.item;
+            },
+        }
+
+        if let (false, Some(summary)) = (self.opened, self.summary()) {
+            widget::Text::new(&summary)
+                .right_from(last_id, 5.0)
+                .font_size(font_size)
+                .color(style.summary_color(&ui.theme))
+                .set(state.ids.summary, ui)
+                ;
+            last_id = state.ids.summary;
+        }
+
+        if self.editable {
+            let (edit_id, edit_label, edit_event) = if is_container {
+                (state.ids.add_button, "+", Event::AddChild)
+            } else {
+                (state.ids.edit_button, "=", Event::StartEdit)
+            };
+
+            let edit = widget::Button::new()
+                .w_h(25.0, 23.0)
+                .right_from(last_id, 5.0)
+                .color(color)
+                .label(edit_label)
+                .set(edit_id, ui)
+                ;
+            if edit.was_clicked() {
+                event = Some(edit_event);
+            }
+
+            if self.removable {
+                let remove = widget::Button::new()
+                    .w_h(25.0, 23.0)
+                    .right_from(edit_id, 1.0)
+                    .color(color)
+                    .label("x")
+                    .set(state.ids.remove_button, ui)
+                    ;
+                if remove.was_clicked() {
+                    event = Some(Event::Remove);
+                }
+            }
+        }
+
+        event
+    }
+}
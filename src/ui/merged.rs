@@ -0,0 +1,173 @@
+use ::state::State;
+use ::ui::IdState;
+use ::ui::{action, theme, timeline, json_inspector};
+use ::ui::tabs::TAB_HEIGHT;
+use ::ui::status_bar::STATUS_HEIGHT;
+use ::ui::panes::{Panes, MARGIN, GAP, CONTROLS_HEIGHT, TIMELINE_HEIGHT};
+use ::actions::{Action, Message};
+use ::schema;
+use std::collections::{HashMap, HashSet};
+use conrod::{widget, UiCell, Colorable, Labelable, Positionable, Widget, Sizeable};
+use conrod::widget::list_select::Event;
+use serde_json;
+
+fn dispatch(state: &State, invocation: &str, message: Message) {
+    let action = Action {
+        domain: String::from("merged"),
+        invocation: String::from(invocation),
+        message: message,
+        sender: String::from("ui"),
+    };
+
+    state.dispatcher.send(action).unwrap();
+}
+
+fn select(state: &State, row: &(String, usize)) {
+    let mut selections = HashSet::new();
+    selections.insert(row.1);
+
+    dispatch(state, "select", Message::SelectAction((row.0.clone(), selections)));
+}
+
+/// Renders the history of several clients interleaved by timestamp. The left column lists the
+/// actions tagged with their client, the right column holds a toggle per client, the state of the
+/// picked action and a timeline of all listed actions.
+pub fn render(id_state: IdState, ui_cell: &mut UiCell, ref state: &State) -> IdState {
+    let parent_id = id_state.ids.merged_canvas;
+    let palette = theme::palette(&state.config.theme);
+    let panes = Panes::new(&state.layout, ui_cell.win_w, ui_cell.win_h - TAB_HEIGHT - STATUS_HEIGHT, false);
+    let rows = state.merged_rows();
+    let selected = state.merged_selection.as_ref()
+        .and_then(|selection| rows.iter().position(|row| row == selection));
+
+    let (mut events, _scrollbar) = widget::ListSelect::single(rows.len())
+        .flow_down()
+        .item_size(50.0)
+        .scrollbar_next_to()
+        .w_h(panes.list[0], panes.list[1])
+        .top_left_with_margins_on(parent_id, MARGIN, MARGIN)
+        .set(id_state.ids.merged_list, ui_cell)
+        ;
+
+    let relatives = match state.merged_selection {
+        Some(ref selection) => state.causal_relatives(selection),
+        None => HashSet::new(),
+    };
+    let is_selected = |position: usize| Some(position) == selected;
+
+    while let Some(event) = events.next(ui_cell, &is_selected) {
+        match event {
+            Event::Item(item) => {
+                let row = &rows[item.i];
+                let client = &state.clients[&row.0];
+                let history_item = &client.history[row.1];
+                let delay = match item.i {
+                    0 => None,
+                    position => state.merged_item(&rows[position - 1])
+                        .map(|previous| history_item.timestamp().saturating_sub(previous.timestamp())),
+                };
+                let color = theme::client_color(&client.id);
+                action::render_merged(item, ui_cell, client.display_name(), &history_item.action, delay, is_selected(item.i), relatives.contains(row), color, &palette);
+            },
+            Event::Selection(position) => select(state, &rows[position]),
+            _ => (),
+        }
+    }
+
+    let id_state = render_client_toggles(id_state, ui_cell, state, &panes);
+
+    let inspector_height = panes.payload_height + panes.state_height + GAP;
+    let picked = state.merged_selection.as_ref()
+        .and_then(|selection| state.merged_item(selection).map(|item| (selection, item)));
+
+    if let Some((&(ref client_id, _), item)) = picked {
+        let client = &state.clients[client_id];
+        let json_value = serde_json::from_str(item.state.content.as_str())
+            .unwrap_or_else(|_| serde_json::Value::String(item.state.content.clone()));
+        let no_paths = HashSet::new();
+        let expanded = client.expanded_paths("state").unwrap_or(&no_paths);
+        let no_pages = HashMap::new();
+        let pages = client.page_starts("state").unwrap_or(&no_pages);
+        let highlighted = schema::highlighted_paths(&item.schema_errors, "state");
+
+        let event = json_inspector::JsonInspector::new(&json_value, String::from("state"), expanded)
+            .pages(pages)
+            .highlighted(&highlighted)
+            .top_right_with_margins_on(parent_id, MARGIN + CONTROLS_HEIGHT + GAP, MARGIN)
+            .w_h(panes.right_width, inspector_height)
+            .scroll_kids()
+            .set(id_state.ids.merged_inspector, ui_cell)
+            ;
+
+        if let Some(path) = event.toggled {
+            let action = Action {
+                domain: String::from("inspector"),
+                invocation: String::from("toggle"),
+                message: Message::InspectorPath((client_id.clone(), String::from("state"), path)),
+                sender: String::from("ui"),
+            };
+
+            state.dispatcher.send(action).unwrap();
+        }
+
+        if let Some((path, start)) = event.paged {
+            let action = Action {
+                domain: String::from("inspector"),
+                invocation: String::from("page"),
+                message: Message::InspectorPage((client_id.clone(), String::from("state"), path, start)),
+                sender: String::from("ui"),
+            };
+
+            state.dispatcher.send(action).unwrap();
+        }
+    }
+
+    let timestamps: Vec<u64> = rows.iter()
+        .filter_map(|row| state.merged_item(row))
+        .map(|item| item.timestamp())
+        .collect();
+    let scrubbed = timeline::Timeline::new(&timestamps, selected)
+        .bottom_right_with_margins_on(parent_id, MARGIN, MARGIN)
+        .w_h(panes.right_width, TIMELINE_HEIGHT)
+        .set(id_state.ids.merged_timeline, ui_cell)
+        ;
+
+    if let Some(position) = scrubbed {
+        select(state, &rows[position]);
+    }
+
+    id_state
+}
+
+/// Renders a button per client above the state, which adds the client to or removes it from the
+/// merged timeline.
+fn render_client_toggles(mut id_state: IdState, ui_cell: &mut UiCell, state: &State, panes: &Panes) -> IdState {
+    let parent_id = id_state.ids.merged_canvas;
+    let palette = theme::palette(&state.config.theme);
+    let client_ids = state.client_ids();
+    id_state.ids.merged_toggles.resize(client_ids.len(), &mut ui_cell.widget_id_generator());
+
+    let width = panes.right_width / client_ids.len().max(1) as f64;
+    for (index, client_id) in client_ids.iter().enumerate() {
+        let id = id_state.ids.merged_toggles[index];
+        let included = !state.merged_excluded.contains(client_id);
+        let button = widget::Button::new()
+            .w_h(width, CONTROLS_HEIGHT)
+            .color(if included { theme::client_color(client_id) } else { palette.button })
+            .label(state.clients[client_id].display_name())
+            .label_font_size(ui_cell.theme.font_size_small)
+            .label_color(if included { palette.button_text } else { palette.muted })
+            ;
+
+        let button = match index {
+            0 => button.top_right_with_margins_on(parent_id, MARGIN, MARGIN + panes.right_width - width),
+            _ => button.right_from(id_state.ids.merged_toggles[index - 1], 0.0),
+        };
+
+        if button.set(id, ui_cell).was_clicked() {
+            dispatch(state, "toggle client", Message::ClientId(client_id.clone()));
+        }
+    }
+
+    id_state
+}
@@ -7,16 +7,33 @@ mod item_state;
 mod id_state;
 mod renderer;
 mod json_inspector;
+mod timeline;
+mod history_timeline;
+mod chart;
+mod state_chart;
+mod expansion_controls;
+mod shortcuts;
+mod quit_dialog;
+mod divider;
+mod dividers;
+mod panes;
+mod theme;
+mod status_bar;
+mod merged;
+mod stats;
 
-use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
 use std::io::Error;
-use ::state::{State, Status};
+use std::any::TypeId;
+use ::state::{State, Status, Navigation};
 use ::actions::{Message, Action};
 use conrod::backend::winit;
 use conrod::backend::glium;
 use conrod::backend::glium::glium::{glutin, texture, Display};
-use conrod::backend::glium::glium::glutin::{EventsLoop, Event, WindowBuilder, ContextBuilder};
-use conrod::{image, UiBuilder};
+use conrod::backend::glium::glium::glutin::{EventsLoop, EventsLoopProxy, Event, WindowBuilder, ContextBuilder};
+use conrod::{image, widget, Ui, UiBuilder};
 use self::id_state::IdState;
 use self::renderer::Renderer;
 
@@ -34,19 +51,60 @@ widget_ids! {
         action_buttons[],
         payload_texts[],
         item_state_texts[],
+        violation_texts[],
+        timelines[],
+        charts[],
+        expansion_controls[],
+        payload_expansion_controls[],
+        size_buttons[],
+        node_buttons[],
+        tab_buttons[],
+        status_bar,
+        status_summary,
+        status_error,
+        tab_pin_button,
+        tab_rename_button,
+        tab_rename_box,
+        tab_clear_button,
+        tab_close_button,
+        merged_tab,
+        merged_canvas,
+        merged_toggles[],
+        merged_list,
+        merged_inspector,
+        merged_timeline,
+        tab_stats_button,
+        tab_redundant_button,
+        stats_canvas,
+        stats_columns[],
+        search_button,
+        quit_dialog,
+        quit_text,
+        save_quit_button,
+        quit_button,
+        cancel_quit_button,
+        list_dividers[],
+        payload_dividers[],
+        chart_dividers[],
     }
 }
 
 pub fn run(rx: Receiver<State>) {
     let mut state = rx.recv().expect("Ui failed to receive application state");
-    let mut render_state = init().expect("Ui failed to init renderer");
+    let mut render_state = init(&state).expect("Ui failed to init renderer");
+
+    let (state_tx, state_rx) = channel();
+    let proxy = render_state.0.events_loop.create_proxy();
+    thread::spawn(move || forward_states(rx, state_tx, proxy));
 
     loop {
-        match rx.try_recv() {
-            Ok(new_state) => state = new_state,
-            Err(TryRecvError::Empty) => (),
-            Err(TryRecvError::Disconnected) => panic!("ui disconnected from main thread"),
-        };
+        loop {
+            match state_rx.try_recv() {
+                Ok(new_state) => state = new_state,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => panic!("ui disconnected from main thread"),
+            };
+        }
 
         match state.status {
             Status::ShuttingDown => break,
@@ -57,15 +115,36 @@ pub fn run(rx: Receiver<State>) {
     }
 }
 
-fn init() -> Result<(Renderer, Ids), Error> {
-    const WIDTH: u32 = 1024;
-    const HEIGHT: u32 = 768;
-    const FONT_PATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fonts/NotoSans/NotoSans-Regular.ttf");
+/// Passes new states on to the ui and wakes it up to draw them. It also wakes the ui once a
+/// second, so the status bar keeps up when nothing happens.
+fn forward_states(rx: Receiver<State>, tx: Sender<State>, proxy: EventsLoopProxy) {
+    loop {
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(state) => {
+                if tx.send(state).is_err() {
+                    break;
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        if proxy.wakeup().is_err() {
+            break;
+        }
+    }
+}
+
+fn init(state: &State) -> Result<(Renderer, IdState), Error> {
+    const MIN_WIDTH: u32 = 640;
+    const MIN_HEIGHT: u32 = 480;
+    let layout = &state.layout;
 
     let events_loop = EventsLoop::new();
     let window = WindowBuilder::new()
         .with_title("Vision Quest")
-        .with_dimensions(WIDTH, HEIGHT)
+        .with_dimensions(layout.window_width, layout.window_height)
+        .with_min_dimensions(MIN_WIDTH, MIN_HEIGHT)
         ;
 
     let context = ContextBuilder::new()
@@ -74,24 +153,29 @@ fn init() -> Result<(Renderer, Ids), Error> {
         ;
 
     let display = Display::new(window, context, &events_loop).expect("Failed to create ui display");
-    let mut ui = UiBuilder::new([WIDTH as f64, HEIGHT as f64]).build();
-    ui.fonts.insert_from_file(FONT_PATH).expect("Failed to insert font");
+    let mut ui = UiBuilder::new([layout.window_width as f64, layout.window_height as f64]).build();
+    ui.fonts.insert(theme::font(&state.config)?);
 
     let ids = Ids::new(ui.widget_id_generator());
     let renderer = glium::Renderer::new(&display).expect("Failed to create ui renderer");
     let image_map = image::Map::<texture::Texture2d>::new();
 
-    Ok((Renderer {
+    let mut renderer = Renderer {
         renderer: renderer,
         display: display,
         image_map: image_map,
         ui: ui,
         events_loop: events_loop,
         events: Vec::new(),
-    }, ids))
+        theme: String::new(),
+        background: conrod::color::BLACK,
+    };
+    renderer.apply_theme(&state.config);
+
+    Ok((renderer, IdState::new(ids)))
 }
 
-fn render(state: &State, (mut renderer, ids) : (Renderer, Ids)) -> (Renderer, Ids) {
+fn render(state: &State, (mut renderer, mut id_state) : (Renderer, IdState)) -> (Renderer, IdState) {
     let mut events = Vec::new();
     renderer.events_loop.poll_events(|event| events.push(event));
 
@@ -102,55 +186,178 @@ fn render(state: &State, (mut renderer, ids) : (Renderer, Ids)) -> (Renderer, Id
         });
     }
 
-    let mut id_state = IdState::new(ids);
+    if renderer.theme != state.config.theme {
+        renderer.apply_theme(&state.config);
+    }
 
-    for event in events.drain(..) {
-        handle_ui_event(event.clone(), &state);
+    id_state.rewind();
 
-        let input = match winit::convert_event(event, &renderer.display) {
-            None => continue,
-            Some(input) => input,
-        };
+    for event in events.drain(..) {
+        handle_ui_event(event.clone(), &state, &renderer.ui);
 
-        id_state.reset();
+        if let Some(input) = winit::convert_event(event, &renderer.display) {
+            renderer.ui.handle_event(input);
+        }
+    }
 
-        renderer.ui.handle_event(input);
+    {
         let ui_cell = &mut renderer.set_widgets();
 
-        id_state.generate_client_widget_ids(state.client_amount(), ui_cell);
+        id_state.generate_client_widget_ids(&state.client_ids(), ui_cell);
+        id_state = status_bar::render(id_state, ui_cell, state);
         id_state = tabs::render(id_state, ui_cell, state);
-        for (_, client_state) in &state.clients {
-            id_state = client::render(id_state, ui_cell, &client_state, state);
+        if state.merged_view {
+            id_state = merged::render(id_state, ui_cell, state);
+        } else if let Some(client_id) = state.active_client_id() {
+            id_state.seek(&client_id);
+            id_state = client::render(id_state, ui_cell, &state.clients[&client_id], state);
         }
+        id_state = quit_dialog::render(id_state, ui_cell, state);
     }
 
     renderer.draw();
-    (renderer, id_state.unwrap())
+    (renderer, id_state)
 }
 
-fn handle_ui_event(event: Event, state: &State) {
+fn handle_ui_event(event: Event, state: &State, ui: &Ui) {
     match event {
-        glutin::Event::WindowEvent { event, .. } => handle_window_event(event, state),
+        glutin::Event::WindowEvent { event, .. } => handle_window_event(event, state, ui),
         _ => (),
     }
 }
 
-fn handle_window_event(event: glutin::WindowEvent, state: &State) {
+fn dispatch(state: &State, domain: &str, invocation: &str, message: Message) {
+    let action = Action {
+        domain: String::from(domain),
+        invocation: String::from(invocation),
+        message: message,
+        sender: String::from("ui"),
+    };
+
+    state.dispatcher.send(action).expect("Failed to send ui action to the application state");
+}
+
+/// Whether a text box has the keyboard, in which case unmodified keys are typed rather than
+/// treated as shortcuts.
+fn is_typing(ui: &Ui) -> bool {
+    ui.global_input().current.widget_capturing_keyboard
+        .and_then(|id| ui.widget_graph().widget(id))
+        .map(|container| container.type_id == TypeId::of::<widget::text_edit::State>())
+        .unwrap_or(false)
+}
+
+fn handle_window_event(event: glutin::WindowEvent, state: &State, ui: &Ui) {
     match event {
-        glutin::WindowEvent::Closed |
+        glutin::WindowEvent::Closed => dispatch(state, "application", "request quit", Message::Empty),
+        glutin::WindowEvent::Resized(width, height) => {
+            let mut layout = state.layout.clone();
+            layout.window_width = width;
+            layout.window_height = height;
+            dispatch(state, "layout", "set", Message::Layout(layout));
+        },
+        glutin::WindowEvent::ReceivedCharacter(character) => {
+            if let (true, false, Some(client_id)) = (state.search_focused, character.is_control(), state.active_client_id()) {
+                dispatch(state, "search", "type", Message::Filter((client_id, character.to_string())));
+            }
+        },
         glutin::WindowEvent::KeyboardInput {
-            input: glutin::KeyboardInput { virtual_keycode: Some(glutin::VirtualKeyCode::Escape), .. },
+            input: glutin::KeyboardInput {
+                state: glutin::ElementState::Pressed,
+                virtual_keycode: Some(key),
+                modifiers,
+                ..
+            },
             ..
-        } => {
-            let action = Action {
-                domain: String::from("application"),
-                invocation: String::from("quit"),
-                message: Message::Empty,
-                sender: String::from("ui"),
-            };
+        } => handle_key(key, modifiers, state, ui),
+        _ => (),
+    }
+}
+
+fn handle_key(key: glutin::VirtualKeyCode, modifiers: glutin::ModifiersState, state: &State, ui: &Ui) {
+    let has_command_modifier = modifiers.ctrl || modifiers.alt || modifiers.logo;
+
+    if state.confirming_quit {
+        if key == glutin::VirtualKeyCode::Escape {
+            dispatch(state, "application", "cancel quit", Message::Empty);
+        }
+        return;
+    }
+
+    if state.search_focused {
+        match (key, state.active_client_id()) {
+            (glutin::VirtualKeyCode::Escape, _) | (glutin::VirtualKeyCode::Return, _) => {
+                dispatch(state, "search", "blur", Message::Empty);
+                return;
+            },
+            (glutin::VirtualKeyCode::Back, Some(client_id)) => {
+                dispatch(state, "search", "backspace", Message::ClientId(client_id));
+                return;
+            },
+            _ => (),
+        };
+        if !has_command_modifier {
+            return;
+        }
+    } else if !has_command_modifier && is_typing(ui) {
+        return;
+    }
 
-            state.dispatcher.send(action).expect("Failed to send quit action to the application state");
+    if let Some(command) = shortcuts::command(&state.config, key, modifiers) {
+        run_command(command, state);
+    }
+}
+
+fn run_command(command: &str, state: &State) {
+    let client_ids = state.client_ids();
+    let active = state.active_client_id();
+    let active_index = active.as_ref().and_then(|active| client_ids.iter().position(|id| id == active));
+
+    let navigation = match command {
+        "previous_action" => Some(Navigation::Previous),
+        "next_action" => Some(Navigation::Next),
+        "first_action" => Some(Navigation::First),
+        "last_action" => Some(Navigation::Last),
+        _ => None,
+    };
+    if let (Some(navigation), Some(client_id)) = (navigation, active.clone()) {
+        dispatch(state, "action", "navigate", Message::Navigate((client_id, navigation)));
+        return;
+    }
+
+    let tab = match (command, active_index) {
+        (_, None) => None,
+        ("next_tab", Some(index)) => Some((index + 1) % client_ids.len()),
+        ("previous_tab", Some(index)) => Some((index + client_ids.len() - 1) % client_ids.len()),
+        (command, _) if command.starts_with("tab_") => {
+            command["tab_".len()..].parse::<usize>().ok()
+                .and_then(|number| number.checked_sub(1))
+                .filter(|index| *index < client_ids.len())
         },
-        _ => (),
+        _ => None,
+    };
+    if let Some(index) = tab {
+        dispatch(state, "tab", "select", Message::ClientId(client_ids[index].clone()));
+        return;
     }
+
+    match command {
+        "focus_search" => dispatch(state, "search", "focus", Message::Empty),
+        "toggle_pause" => dispatch(state, "application", "toggle pause", Message::Empty),
+        "save" => dispatch(state, "session", "save", Message::Empty),
+        "cycle_theme" => dispatch(state, "theme", "cycle", Message::Empty),
+        "toggle_grouping" => dispatch(state, "action", "toggle grouping", Message::Empty),
+        "merged_view" => dispatch(state, "merged", "toggle view", Message::Empty),
+        "stats_view" => dispatch(state, "stats", "toggle view", Message::Empty),
+        "redundant_only" => if let Some(client_id) = active {
+            dispatch(state, "action", "toggle redundant", Message::ClientId(client_id));
+        },
+        "close_tab" => if let Some(client_id) = active {
+            dispatch(state, "tab", "close", Message::ClientId(client_id));
+        },
+        "clear_client" => if let Some(client_id) = active {
+            dispatch(state, "tab", "clear", Message::ClientId(client_id));
+        },
+        "quit" => dispatch(state, "application", "request quit", Message::Empty),
+        _ => (),
+    };
 }
@@ -0,0 +1,82 @@
+use ::layout::Layout;
+use ::state::{State, Client};
+use ::ui::tabs::TAB_HEIGHT;
+use ::ui::status_bar::STATUS_HEIGHT;
+use conrod::UiCell;
+
+pub const MARGIN: f64 = 10.0;
+/// The space between two panes, which holds the divider between them.
+pub const GAP: f64 = 5.0;
+pub const CONTROLS_HEIGHT: f64 = 20.0;
+pub const TIMELINE_HEIGHT: f64 = 60.0;
+const MIN_FRACTION: f64 = 0.1;
+const MAX_FRACTION: f64 = 0.9;
+
+/// The sizes of a client's panes for the current window size and layout.
+///
+/// The action list fills the left column. The right column stacks the amplitude inspector, the
+/// expansion controls, the state inspector, the chart if one is open and the timeline. The
+/// amplitude inspector has its own expansion controls at the top of its pane.
+pub struct Panes {
+    pub list: [f64; 2],
+    pub right_width: f64,
+    pub payload_height: f64,
+    pub state_height: f64,
+    pub chart_height: f64,
+    inner_width: f64,
+    /// The height shared by the inspectors and the chart.
+    flexible_height: f64,
+}
+
+impl Panes {
+    pub fn new(layout: &Layout, width: f64, height: f64, chart_open: bool) -> Panes {
+        let inner_width = f64::max(width - MARGIN * 2.0 - GAP, 0.0);
+        let inner_height = f64::max(height - MARGIN * 2.0, 0.0);
+        let list_width = inner_width * layout.list_fraction;
+
+        let chart_gap = if chart_open { GAP } else { 0.0 };
+        let fixed_height = CONTROLS_HEIGHT + TIMELINE_HEIGHT + GAP * 3.0 + chart_gap;
+        let flexible_height = f64::max(inner_height - fixed_height, 0.0);
+        let chart_height = if chart_open { flexible_height * layout.chart_fraction } else { 0.0 };
+        let inspectors_height = flexible_height - chart_height;
+        let payload_height = inspectors_height * layout.payload_fraction;
+
+        Panes {
+            list: [list_width, inner_height],
+            right_width: inner_width - list_width,
+            payload_height: payload_height,
+            state_height: inspectors_height - payload_height,
+            chart_height: chart_height,
+            inner_width: inner_width,
+            flexible_height: flexible_height,
+        }
+    }
+
+    /// The panes of `client`, which fill the window between the tab bar and the status bar.
+    pub fn of(ui_cell: &UiCell, state: &State, client: &Client) -> Panes {
+        Panes::new(&state.layout, ui_cell.win_w, ui_cell.win_h - TAB_HEIGHT - STATUS_HEIGHT, client.chart.is_some())
+    }
+
+    /// The list fraction putting the divider right of the action list at `list_width`.
+    pub fn list_fraction(&self, list_width: f64) -> f64 {
+        clamp_fraction(list_width, self.inner_width)
+    }
+
+    /// The payload fraction putting the divider below the amplitude at `payload_height`.
+    pub fn payload_fraction(&self, payload_height: f64) -> f64 {
+        clamp_fraction(payload_height, self.payload_height + self.state_height)
+    }
+
+    /// The chart fraction putting the divider above the chart at `chart_height`.
+    pub fn chart_fraction(&self, chart_height: f64) -> f64 {
+        clamp_fraction(chart_height, self.flexible_height)
+    }
+}
+
+fn clamp_fraction(part: f64, whole: f64) -> f64 {
+    if whole <= 0.0 {
+        return MIN_FRACTION;
+    }
+
+    (part / whole).clamp(MIN_FRACTION, MAX_FRACTION)
+}
@@ -1,22 +1,102 @@
-use ::state::{State, HistoryItem};
+use ::state::{State, Client, HistoryItem};
 use ::ui::id_state::IdState;
-use ::ui::json_inspector;
-use conrod::{UiCell, Positionable, Widget, Sizeable};
+use ::ui::{json_inspector, expansion_controls, theme};
+use ::ui::panes::{Panes, GAP, CONTROLS_HEIGHT};
+use ::actions::{Action, Message};
+use ::schema;
+use std::collections::{HashMap, HashSet};
+use conrod::{widget, UiCell, Colorable, Positionable, Widget, Sizeable};
 use serde_json;
 
-pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref item: &HistoryItem, _state: &State) -> IdState {
+pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client, ref item: &HistoryItem, state: &State) -> IdState {
     let action_lists = id_state.ids.action_lists.to_vec();
     let sibling_id = id_state.last(&action_lists, "action_lists").unwrap();
 
+    let controls_ids = id_state.ids.payload_expansion_controls.to_vec();
+    let controls_id = id_state.next(&controls_ids, "payload_expansion_controls").unwrap();
+
     let ids = id_state.ids.payload_texts.to_vec();
     let id = id_state.next(&ids, "payload_texts").unwrap();
 
-    json_inspector::JsonInspector::new(serde_json::from_str(item.action.amplitude.as_str()).unwrap(), String::from("amplitude"))
-        .right_from(sibling_id, 5.0)
-        .w_h(700.0, 200.0)
+    let panes = Panes::of(ui_cell, state, client);
+    let expansion = expansion_controls::ExpansionControls::new()
+        .right_from(sibling_id, GAP)
+        .align_top_of(sibling_id)
+        .w_h(panes.right_width, CONTROLS_HEIGHT)
+        .set(controls_id, ui_cell)
+        ;
+
+    if let Some(expansion) = expansion {
+        let action = Action {
+            domain: String::from("inspector"),
+            invocation: String::from("expand"),
+            message: Message::Expansion((client.id.clone(), String::from("amplitude"), expansion)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    let json_value = serde_json::from_str(item.action.amplitude.as_str())
+        .unwrap_or_else(|_| serde_json::Value::String(item.action.amplitude.clone()));
+    let no_paths = HashSet::new();
+    let expanded = client.expanded_paths("amplitude").unwrap_or(&no_paths);
+    let no_pages = HashMap::new();
+    let pages = client.page_starts("amplitude").unwrap_or(&no_pages);
+    let highlighted = schema::highlighted_paths(&item.schema_errors, "amplitude");
+
+    let event = json_inspector::JsonInspector::new(&json_value, String::from("amplitude"), expanded)
+        .pages(pages)
+        .highlighted(&highlighted)
+        .down_from(controls_id, GAP)
+        .w_h(panes.right_width, f64::max(panes.payload_height - CONTROLS_HEIGHT - GAP, 0.0))
         .scroll_kids()
         .set(id, ui_cell)
         ;
 
+    if let Some(path) = event.toggled {
+        let action = Action {
+            domain: String::from("inspector"),
+            invocation: String::from("toggle"),
+            message: Message::InspectorPath((client.id.clone(), String::from("amplitude"), path)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    if let Some((path, start)) = event.paged {
+        let action = Action {
+            domain: String::from("inspector"),
+            invocation: String::from("page"),
+            message: Message::InspectorPage((client.id.clone(), String::from("amplitude"), path, start)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    let violation_ids = id_state.ids.violation_texts.to_vec();
+    let violation_id = id_state.next(&violation_ids, "violation_texts").unwrap();
+
+    if !item.violations.is_empty() || !item.schema_errors.is_empty() || !item.size_alerts.is_empty() {
+        let palette = theme::palette(&state.config.theme);
+        let mut failures: Vec<String> = item.violations.iter()
+            .map(|violation| format!("! {}", violation))
+            .collect();
+        failures.extend(item.schema_errors.iter()
+            .map(|error| format!("! {}", error)));
+        failures.extend(item.size_alerts.iter()
+            .map(|alert| format!("! {}", alert)));
+
+        widget::Text::new(&failures.join("\n"))
+            .bottom_left_with_margins_on(id, GAP, GAP)
+            .w(panes.right_width - GAP * 2.0)
+            .font_size(ui_cell.theme.font_size_small)
+            .color(palette.warning)
+            .set(violation_id, ui_cell)
+            ;
+    }
+
     id_state
 }
@@ -0,0 +1,74 @@
+use ::state::State;
+use ::ui::IdState;
+use ::ui::theme;
+use ::actions::{Action, Message};
+use conrod::{widget, UiCell, Colorable, Labelable, Positionable, Widget, Sizeable};
+
+const BUTTON_WIDTH: f64 = 120.0;
+const BUTTON_HEIGHT: f64 = 30.0;
+
+fn dispatch(state: &State, domain: &str, invocation: &str) {
+    let action = Action {
+        domain: String::from(domain),
+        invocation: String::from(invocation),
+        message: Message::Empty,
+        sender: String::from("ui"),
+    };
+
+    state.dispatcher.send(action).unwrap();
+}
+
+/// Asks whether to save the recorded history before quitting.
+pub fn render(id_state: IdState, ui_cell: &mut UiCell, ref state: &State) -> IdState {
+    if !state.confirming_quit {
+        return id_state;
+    }
+
+    let palette = theme::palette(&state.config.theme);
+
+    widget::Canvas::new()
+        .floating(true)
+        .w_h(420.0, 120.0)
+        .middle_of(ui_cell.window)
+        .color(palette.panel)
+        .set(id_state.ids.quit_dialog, ui_cell)
+        ;
+
+    widget::Text::new("There is unsaved history. Quit anyway?")
+        .mid_top_with_margin_on(id_state.ids.quit_dialog, 20.0)
+        .color(palette.text)
+        .set(id_state.ids.quit_text, ui_cell)
+        ;
+
+    let save_and_quit = widget::Button::new()
+        .w_h(BUTTON_WIDTH, BUTTON_HEIGHT)
+        .bottom_left_with_margins_on(id_state.ids.quit_dialog, 20.0, 15.0)
+        .label("Save and quit")
+        .set(id_state.ids.save_quit_button, ui_cell)
+        ;
+    if save_and_quit.was_clicked() {
+        dispatch(state, "session", "save and quit");
+    }
+
+    let quit = widget::Button::new()
+        .w_h(BUTTON_WIDTH, BUTTON_HEIGHT)
+        .right_from(id_state.ids.save_quit_button, 15.0)
+        .label("Quit")
+        .set(id_state.ids.quit_button, ui_cell)
+        ;
+    if quit.was_clicked() {
+        dispatch(state, "application", "quit");
+    }
+
+    let cancel = widget::Button::new()
+        .w_h(BUTTON_WIDTH, BUTTON_HEIGHT)
+        .right_from(id_state.ids.quit_button, 15.0)
+        .label("Cancel")
+        .set(id_state.ids.cancel_quit_button, ui_cell)
+        ;
+    if cancel.was_clicked() {
+        dispatch(state, "application", "cancel quit");
+    }
+
+    id_state
+}
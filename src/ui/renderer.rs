@@ -1,6 +1,8 @@
 use conrod::backend::glium;
 use conrod::backend::glium::glium::{glutin, texture, Surface, Display};
-use conrod::{image, Ui, UiCell};
+use conrod::{image, Ui, UiCell, Color};
+use ::config::Config;
+use ::ui::theme;
 
 pub struct Renderer {
     pub display: Display,
@@ -9,14 +11,26 @@ pub struct Renderer {
     pub events: Vec<glutin::Event>,
     pub renderer: glium::Renderer,
     pub image_map: image::Map<texture::Texture2d>,
+    /// The name of the theme preset in use.
+    pub theme: String,
+    pub background: Color,
 }
 
 impl Renderer {
+    pub fn apply_theme(&mut self, config: &Config) {
+        let palette = theme::palette(&config.theme);
+        self.ui.theme = theme::theme(&palette, config.font_size);
+        self.ui.needs_redraw();
+        self.theme = config.theme.clone();
+        self.background = palette.background;
+    }
+
     pub fn draw(&mut self) {
         if let Some(primitives) = self.ui.draw_if_changed() {
             self.renderer.fill(&self.display, primitives, &self.image_map);
             let mut target = self.display.draw();
-            target.clear_color(0.0, 0.0, 0.0, 1.0);
+            let (red, green, blue, alpha) = self.background.to_fsa().into();
+            target.clear_color(red, green, blue, alpha);
             self.renderer.draw(&self.display, &mut target, &self.image_map).unwrap();
             target.finish().unwrap();
         }
@@ -0,0 +1,30 @@
+use ::config::Config;
+use conrod::backend::glium::glium::glutin::{VirtualKeyCode, ModifiersState};
+
+/// Whether a binding such as `Ctrl+Shift+S` names exactly this key and these modifiers.
+fn matches(binding: &str, key: VirtualKeyCode, modifiers: ModifiersState) -> bool {
+    let mut parts: Vec<&str> = binding.split('+').map(|part| part.trim()).collect();
+    let key_name = match parts.pop() {
+        Some(key_name) => key_name,
+        None => return false,
+    };
+
+    let has = |modifier: &str| parts.iter().any(|part| part.eq_ignore_ascii_case(modifier));
+    let same_modifiers = has("Ctrl") == modifiers.ctrl
+        && has("Shift") == modifiers.shift
+        && has("Alt") == modifiers.alt
+        && has("Logo") == modifiers.logo;
+
+    same_modifiers && key_name.eq_ignore_ascii_case(&format!("{:?}", key))
+}
+
+/// The command bound to the pressed key, if any.
+pub fn command(config: &Config, key: VirtualKeyCode, modifiers: ModifiersState) -> Option<&'static str> {
+    Config::commands()
+        .into_iter()
+        .find(|command| {
+            config.key_binding(command)
+                .map(|binding| matches(binding, key, modifiers))
+                .unwrap_or(false)
+        })
+}
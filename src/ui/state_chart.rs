@@ -0,0 +1,54 @@
+use ::state::{State, Client, ChartAxis, ChartSource};
+use ::ui::id_state::IdState;
+use ::ui::chart;
+use ::ui::panes::{Panes, GAP};
+use ::actions::{Action, Message};
+use conrod::{UiCell, Positionable, Widget, Sizeable};
+
+pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client, state: &State) -> IdState {
+    let timelines = id_state.ids.timelines.to_vec();
+    let sibling_id = id_state.last(&timelines, "timelines").unwrap();
+
+    let ids = id_state.ids.charts.to_vec();
+    let id = id_state.next(&ids, "charts").unwrap();
+
+    let chart = match client.chart {
+        Some(ref chart) => chart,
+        None => return id_state,
+    };
+
+    let panes = Panes::of(ui_cell, state, client);
+    let points = chart.points(&client.history);
+    let title = match chart.source {
+        ChartSource::Path(ref path) => format!("state{}", path),
+        ChartSource::Size => String::from("state size (bytes)"),
+        ChartSource::Nodes => String::from("state nodes"),
+    };
+    let x_label = match chart.axis {
+        ChartAxis::Action => "action",
+        ChartAxis::Time => "time",
+    };
+
+    let event = chart::LineChart::new(&title, x_label, &points)
+        .up_from(sibling_id, GAP)
+        .w_h(panes.right_width, panes.chart_height)
+        .set(id, ui_cell)
+        ;
+
+    let invocation = match event {
+        Some(chart::Event::ToggleAxis) => "toggle axis",
+        Some(chart::Event::Close) => "close",
+        None => return id_state,
+    };
+
+    let action = Action {
+        domain: String::from("chart"),
+        invocation: String::from(invocation),
+        message: Message::ClientId(client.id.clone()),
+        sender: String::from("ui"),
+    };
+
+    state.dispatcher.send(action).unwrap();
+
+    id_state
+}
@@ -0,0 +1,60 @@
+use ::state::{State, Client};
+use ::ui::IdState;
+use ::ui::theme;
+use ::ui::panes::{Panes, MARGIN, GAP, CONTROLS_HEIGHT};
+use ::timing::{self, Summary};
+use conrod::{widget, UiCell, Colorable, Positionable, Widget, Sizeable};
+
+const HEADERS: [&'static str; 6] = ["action", "count", "mean", "p95", "max", "mean gap"];
+/// The share of the panel width each column starts at.
+const COLUMN_STARTS: [f64; 6] = [0.0, 0.4, 0.5, 0.62, 0.74, 0.86];
+
+fn summary_text(summary: &Option<Summary>, pick: fn(&Summary) -> f64) -> String {
+    summary.as_ref().map_or(String::from("-"), |summary| timing::format_ms(pick(summary)))
+}
+
+/// Renders the timing of each action of `client` over its inspectors: how often it happened,
+/// how long the client took to handle it and how long after the item before it it came in.
+pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref client: &Client, state: &State) -> IdState {
+    let canvases = id_state.ids.client_canvases.to_vec();
+    let parent_id = id_state.last(&canvases, "client_canvases").unwrap();
+
+    let panes = Panes::of(ui_cell, state, client);
+    let palette = theme::palette(&state.config.theme);
+    let width = panes.right_width;
+    let height = panes.payload_height + panes.state_height + CONTROLS_HEIGHT + GAP * 2.0;
+
+    widget::Canvas::new()
+        .w_h(width, height)
+        .top_right_with_margins_on(parent_id, MARGIN, MARGIN)
+        .color(palette.panel)
+        .scroll_kids_vertically()
+        .set(id_state.ids.stats_canvas, ui_cell)
+        ;
+
+    let mut columns: Vec<Vec<String>> = HEADERS.iter().map(|header| vec![String::from(*header)]).collect();
+    for action in client.timings.actions() {
+        columns[0].push(action.action.clone());
+        columns[1].push(action.count.to_string());
+        columns[2].push(summary_text(&action.duration, |summary| summary.mean));
+        columns[3].push(summary_text(&action.duration, |summary| summary.p95));
+        columns[4].push(summary_text(&action.duration, |summary| summary.max));
+        columns[5].push(summary_text(&action.interval, |summary| summary.mean));
+    }
+
+    id_state.ids.stats_columns.resize(columns.len(), &mut ui_cell.widget_id_generator());
+    for (index, column) in columns.iter().enumerate() {
+        let start = COLUMN_STARTS[index] * (width - GAP * 2.0);
+        let end = COLUMN_STARTS.get(index + 1).map_or(width - GAP * 2.0, |next| next * (width - GAP * 2.0));
+
+        widget::Text::new(&column.join("\n"))
+            .top_left_with_margins_on(id_state.ids.stats_canvas, GAP, GAP + start)
+            .w(end - start)
+            .font_size(ui_cell.theme.font_size_small)
+            .color(palette.text)
+            .set(id_state.ids.stats_columns[index], ui_cell)
+            ;
+    }
+
+    id_state
+}
@@ -0,0 +1,93 @@
+use ::state::{State, Status, timestamp_now};
+use ::ui::IdState;
+use ::ui::theme;
+use ::ui::json_inspector::row::group_thousands;
+use ::growth;
+use conrod::{widget, UiCell, Colorable, Positionable, Widget, Sizeable};
+
+pub const STATUS_HEIGHT: f64 = 24.0;
+const PADDING: f64 = 8.0;
+
+fn summary(state: &State) -> String {
+    let addresses = if state.listen_addresses.is_empty() {
+        String::from("not listening")
+    } else {
+        format!("listening on {}", state.listen_addresses.join(", "))
+    };
+    let connected = state.connected_amount();
+    let status = match state.status {
+        Status::Initializing => "Starting",
+        Status::Running => "Running",
+        Status::Paused => "Paused",
+        Status::ShuttingDown => "Shutting down",
+    };
+
+    let violating = match state.violating_amount() {
+        0 => String::new(),
+        amount => format!("  |  {} breaking invariants", group_thousands(amount)),
+    };
+    let redundant = match state.redundant_amounts() {
+        (0, 0) => String::new(),
+        (unchanged, repeated) => format!("  |  {} no-ops, {} repeats", group_thousands(unchanged), group_thousands(repeated)),
+    };
+    let mismatching = match state.mismatching_amount() {
+        0 => String::new(),
+        amount => format!("  |  {} not matching schemas", group_thousands(amount)),
+    };
+    let growing = match state.growing_amount() {
+        0 => String::new(),
+        amount => format!("  |  {} size alerts", group_thousands(amount)),
+    };
+
+    format!("{}  |  {}  |  {} connected, {} disconnected  |  {} items  |  {:.1} msg/s  |  {}{}{}{}{}",
+        status,
+        addresses,
+        connected,
+        state.client_amount() - connected,
+        group_thousands(state.item_amount()),
+        state.messages_per_second(timestamp_now()),
+        growth::format_bytes(state.history_bytes()),
+        violating,
+        mismatching,
+        growing,
+        redundant,
+    )
+}
+
+/// Renders the recording status, connection and throughput figures and the last error along the
+/// bottom of the window.
+pub fn render(id_state: IdState, ui_cell: &mut UiCell, ref state: &State) -> IdState {
+    let palette = theme::palette(&state.config.theme);
+    let font_size = ui_cell.theme.font_size_small;
+    let width = ui_cell.win_w;
+
+    widget::Canvas::new()
+        .w_h(width, STATUS_HEIGHT)
+        .mid_bottom_of(ui_cell.window)
+        .color(palette.panel)
+        .set(id_state.ids.status_bar, ui_cell)
+        ;
+
+    let status_color = match state.status {
+        Status::Paused => palette.warning,
+        _ => palette.text,
+    };
+
+    widget::Text::new(&summary(state))
+        .mid_left_with_margin_on(id_state.ids.status_bar, PADDING)
+        .font_size(font_size)
+        .color(status_color)
+        .set(id_state.ids.status_summary, ui_cell)
+        ;
+
+    if let Some(ref error) = state.last_error {
+        widget::Text::new(error)
+            .mid_right_with_margin_on(id_state.ids.status_bar, PADDING)
+            .font_size(font_size)
+            .color(palette.warning)
+            .set(id_state.ids.status_error, ui_cell)
+            ;
+    }
+
+    id_state
+}
@@ -1,28 +1,235 @@
 use ::state::State;
+use ::ui::status_bar::STATUS_HEIGHT;
 use ::ui::IdState;
-use conrod::{widget, color, UiCell, Colorable, Positionable, Widget, Sizeable};
+use ::ui::theme;
+use ::actions::{Action, Message};
+use conrod::{widget, UiCell, Colorable, Labelable, Positionable, Widget, Sizeable};
 
-pub fn render(id_state: IdState, ui_cell: &mut UiCell, ref state: &State) -> IdState {
-    let mut tabs: Vec<(widget::Id, &str)> = Vec::new();
+pub const TAB_HEIGHT: f64 = 40.0;
+const TAB_WIDTH: f64 = 150.0;
+const SEARCH_WIDTH: f64 = 250.0;
+const CONTROL_WIDTH: f64 = 60.0;
+const RENAME_WIDTH: f64 = 150.0;
 
-    for (index, (id, _client)) in state.clients.iter().enumerate() {
-        tabs.push((id_state.ids.client_canvases[index], id));
+fn dispatch(state: &State, invocation: &str, message: Message) {
+    let action = Action {
+        domain: String::from("tab"),
+        invocation: String::from(invocation),
+        message: message,
+        sender: String::from("ui"),
     };
 
-    if tabs.len() == 0 {
-        id_state
+    state.dispatcher.send(action).unwrap();
+}
+
+/// Renders a tab button per client, the search field, and the canvas of the shown client.
+pub fn render(mut id_state: IdState, ui_cell: &mut UiCell, ref state: &State) -> IdState {
+    let client_ids = state.client_ids();
+    let active = state.active_client_id();
+    let palette = theme::palette(&state.config.theme);
+
+    for (index, client_id) in client_ids.iter().enumerate() {
+        let id = id_state.ids.tab_buttons[id_state.slot(client_id)];
+        let is_active = active.as_ref() == Some(client_id);
+        let client = &state.clients[client_id];
+        let pin = if client.pinned { "• " } else { "" };
+        let label = format!("{}{}: {}", pin, index + 1, client.display_name());
+        let button = widget::Button::new()
+            .w_h(TAB_WIDTH, TAB_HEIGHT)
+            .color(if is_active && !state.merged_view { palette.highlight } else { palette.button })
+            .label(&label)
+            ;
+
+        let button = match index {
+            0 => button.top_left_of(ui_cell.window),
+            _ => button.right_from(id_state.ids.tab_buttons[id_state.slot(&client_ids[index - 1])], 0.0),
+        };
+
+        if button.set(id, ui_cell).was_clicked() {
+            dispatch(state, "select", Message::ClientId(client_id.clone()));
+        }
+    }
+
+    if client_ids.len() > 1 || (state.merged_view && !client_ids.is_empty()) {
+        let merged = widget::Button::new()
+            .w_h(TAB_WIDTH, TAB_HEIGHT)
+            .right_from(id_state.ids.tab_buttons[id_state.slot(&client_ids[client_ids.len() - 1])], 0.0)
+            .color(if state.merged_view { palette.highlight } else { palette.button })
+            .label("Merged")
+            .set(id_state.ids.merged_tab, ui_cell)
+            ;
+        if merged.was_clicked() {
+            let action = Action {
+                domain: String::from("merged"),
+                invocation: String::from("show"),
+                message: Message::Empty,
+                sender: String::from("ui"),
+            };
+
+            state.dispatcher.send(action).unwrap();
+        }
+    }
+
+    id_state = render_search(id_state, ui_cell, state);
+    if let (false, Some(ref client_id)) = (state.merged_view, active.as_ref()) {
+        id_state = render_controls(id_state, ui_cell, client_id, state);
+    }
+
+    let (width, height) = (ui_cell.win_w, ui_cell.win_h - TAB_HEIGHT - STATUS_HEIGHT);
+    let canvas_id = if state.merged_view {
+        Some(id_state.ids.merged_canvas)
     } else {
-        widget::Tabs::new(&tabs)
-            .wh_of(ui_cell.window)
-            .mid_top_of(ui_cell.window)
-            .layout_horizontally()
-            .color(color::LIGHT_BLUE)
-            .bar_thickness(50.0)
-            .starting_tab_idx(0)
-            .starting_canvas(id_state.ids.client_canvases[0])
-            .set(id_state.ids.tab, ui_cell)
+        active.map(|active| id_state.ids.client_canvases[id_state.slot(&active)])
+    };
+
+    if let Some(canvas_id) = canvas_id {
+        widget::Canvas::new()
+            .w_h(width, height)
+            .mid_bottom_with_margin_on(ui_cell.window, STATUS_HEIGHT)
+            .set(canvas_id, ui_cell)
             ;
+    }
 
-        id_state
+    id_state
+}
+
+/// Renders buttons to list only redundant actions, to show timing statistics, and to pin, rename,
+/// clear and close the shown tab, left of the search field.
+fn render_controls(id_state: IdState, ui_cell: &mut UiCell, client_id: &str, ref state: &State) -> IdState {
+    let client = &state.clients[client_id];
+    let ids = &id_state.ids;
+
+    let close = widget::Button::new()
+        .w_h(CONTROL_WIDTH, TAB_HEIGHT)
+        .left_from(ids.search_button, 5.0)
+        .label("Close")
+        .set(ids.tab_close_button, ui_cell)
+        ;
+    if close.was_clicked() {
+        dispatch(state, "close", Message::ClientId(String::from(client_id)));
     }
+
+    let clear = widget::Button::new()
+        .w_h(CONTROL_WIDTH, TAB_HEIGHT)
+        .left_from(ids.tab_close_button, 0.0)
+        .label("Clear")
+        .set(ids.tab_clear_button, ui_cell)
+        ;
+    if clear.was_clicked() {
+        dispatch(state, "clear", Message::ClientId(String::from(client_id)));
+    }
+
+    let rename_id = if let Some(ref draft) = client.rename_draft {
+        for event in widget::TextBox::new(draft)
+            .w_h(RENAME_WIDTH, TAB_HEIGHT)
+            .left_from(ids.tab_clear_button, 0.0)
+            .font_size(ui_cell.theme.font_size_medium)
+            .set(ids.tab_rename_box, ui_cell)
+        {
+            match event {
+                widget::text_box::Event::Update(name) => {
+                    dispatch(state, "rename", Message::Rename((String::from(client_id), name)));
+                },
+                widget::text_box::Event::Enter => dispatch(state, "finish rename", Message::ClientId(String::from(client_id))),
+            }
+        }
+        ids.tab_rename_box
+    } else {
+        let rename = widget::Button::new()
+            .w_h(CONTROL_WIDTH, TAB_HEIGHT)
+            .left_from(ids.tab_clear_button, 0.0)
+            .label("Rename")
+            .set(ids.tab_rename_button, ui_cell)
+            ;
+        if rename.was_clicked() {
+            dispatch(state, "start rename", Message::ClientId(String::from(client_id)));
+        }
+        ids.tab_rename_button
+    };
+
+    let pin = widget::Button::new()
+        .w_h(CONTROL_WIDTH, TAB_HEIGHT)
+        .left_from(rename_id, 0.0)
+        .label(if client.pinned { "Unpin" } else { "Pin" })
+        .set(ids.tab_pin_button, ui_cell)
+        ;
+    if pin.was_clicked() {
+        dispatch(state, "toggle pin", Message::ClientId(String::from(client_id)));
+    }
+
+    let palette = theme::palette(&state.config.theme);
+    let stats = widget::Button::new()
+        .w_h(CONTROL_WIDTH, TAB_HEIGHT)
+        .left_from(ids.tab_pin_button, 0.0)
+        .color(if state.stats_view { palette.highlight } else { palette.button })
+        .label("Stats")
+        .set(ids.tab_stats_button, ui_cell)
+        ;
+    if stats.was_clicked() {
+        let action = Action {
+            domain: String::from("stats"),
+            invocation: String::from("toggle view"),
+            message: Message::Empty,
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    let redundant = widget::Button::new()
+        .w_h(CONTROL_WIDTH, TAB_HEIGHT)
+        .left_from(ids.tab_stats_button, 0.0)
+        .color(if client.redundant_only { palette.highlight } else { palette.button })
+        .label("No-ops")
+        .set(ids.tab_redundant_button, ui_cell)
+        ;
+    if redundant.was_clicked() {
+        let action = Action {
+            domain: String::from("action"),
+            invocation: String::from("toggle redundant"),
+            message: Message::ClientId(String::from(client_id)),
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    id_state
+}
+
+fn render_search(id_state: IdState, ui_cell: &mut UiCell, ref state: &State) -> IdState {
+    let filter = state.active_client_id()
+        .map(|id| state.clients[&id].filter.clone())
+        .unwrap_or_default();
+    let palette = theme::palette(&state.config.theme);
+
+    let label = if state.search_focused {
+        format!("{}|", filter)
+    } else if filter.is_empty() {
+        format!("Search ({})", state.config.key_binding("focus_search").unwrap_or(""))
+    } else {
+        filter
+    };
+
+    let search = widget::Button::new()
+        .w_h(SEARCH_WIDTH, TAB_HEIGHT)
+        .top_right_of(ui_cell.window)
+        .color(if state.search_focused { palette.highlight } else { palette.button })
+        .label(&label)
+        .set(id_state.ids.search_button, ui_cell)
+        ;
+
+    if search.was_clicked() {
+        let invocation = if state.search_focused { "blur" } else { "focus" };
+        let action = Action {
+            domain: String::from("search"),
+            invocation: String::from(invocation),
+            message: Message::Empty,
+            sender: String::from("ui"),
+        };
+
+        state.dispatcher.send(action).unwrap();
+    }
+
+    id_state
 }
@@ -0,0 +1,212 @@
+use std::env;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::any::TypeId;
+use conrod::{self, color, text, widget, Color, Theme};
+use conrod::theme::WidgetDefault;
+use ::config::Config;
+use ::ui::{timeline, chart, expansion_controls, divider};
+use ::ui::json_inspector::row;
+
+/// The font used unless the config names another, compiled into the binary so it can be moved.
+const EMBEDDED_FONT: &'static [u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fonts/NotoSans/NotoSans-Regular.ttf"));
+
+/// The colours of a theme preset.
+#[derive(Copy, Clone)]
+pub struct Palette {
+    /// The window and the client canvases.
+    pub background: Color,
+    /// The backgrounds of the timeline, charts and dialogs.
+    pub panel: Color,
+    pub button: Color,
+    pub button_text: Color,
+    /// Selected actions, the shown tab and opened nodes.
+    pub highlight: Color,
+    pub text: Color,
+    /// Less important text, such as summaries of collapsed nodes and null values.
+    pub muted: Color,
+    pub string: Color,
+    pub number: Color,
+    pub bool: Color,
+    pub warning: Color,
+    /// Things drawn over other content, such as the timeline scrubber.
+    pub accent: Color,
+}
+
+/// The colours of the preset called `name`, falling back to the dark preset.
+pub fn palette(name: &str) -> Palette {
+    match name {
+        "light" => Palette {
+            background: color::rgb(0.96, 0.96, 0.96),
+            panel: color::LIGHT_GREY,
+            button: color::LIGHT_BLUE,
+            button_text: color::BLACK,
+            highlight: color::BLUE,
+            text: color::BLACK,
+            muted: color::DARK_GREY,
+            string: color::DARK_GREEN,
+            number: color::DARK_ORANGE,
+            bool: color::DARK_PURPLE,
+            warning: color::DARK_RED,
+            accent: color::BLACK,
+        },
+        "high_contrast" => Palette {
+            background: color::BLACK,
+            panel: color::BLACK,
+            button: color::WHITE,
+            button_text: color::BLACK,
+            highlight: color::YELLOW,
+            text: color::WHITE,
+            muted: color::LIGHT_GREY,
+            string: color::GREEN,
+            number: color::YELLOW,
+            bool: color::LIGHT_BLUE,
+            warning: color::RED,
+            accent: color::YELLOW,
+        },
+        _ => Palette {
+            background: color::CHARCOAL,
+            panel: color::DARK_CHARCOAL,
+            button: color::BLUE,
+            button_text: color::BLACK,
+            highlight: color::LIGHT_BLUE,
+            text: color::WHITE,
+            muted: color::GREY,
+            string: color::LIGHT_GREEN,
+            number: color::LIGHT_ORANGE,
+            bool: color::LIGHT_PURPLE,
+            warning: color::ORANGE,
+            accent: color::WHITE,
+        },
+    }
+}
+
+/// A stable colour for an action domain, unless the config sets one.
+///
+/// The hue is derived from a hash of the domain name, so a domain keeps its colour between runs.
+pub fn domain_color(config: &Config, domain: &str) -> Color {
+    config.domain_colors.get(domain)
+        .and_then(|hex| parse_hex(hex))
+        .unwrap_or_else(|| {
+            let hue = (fnv1a(domain) % 360) as f32;
+            color::hsl(hue.to_radians(), 0.55, 0.7)
+        })
+}
+
+/// A stable colour tagging a client in views that mix several clients.
+pub fn client_color(client_id: &str) -> Color {
+    let hue = (fnv1a(client_id) % 360) as f32;
+    color::hsl(hue.to_radians(), 0.75, 0.45)
+}
+
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c9dc5, |hash: u32, byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+}
+
+/// Parses colours written as `#rrggbb`.
+fn parse_hex(hex: &str) -> Option<Color> {
+    let digits = hex.trim_start_matches('#');
+    if digits.len() != 6 || !digits.is_ascii() {
+        return None;
+    }
+
+    let channel = |range: ::std::ops::Range<usize>| u8::from_str_radix(&digits[range], 16).ok();
+    match (channel(0..2), channel(2..4), channel(4..6)) {
+        (Some(red), Some(green), Some(blue)) => Some(color::rgb_bytes(red, green, blue)),
+        _ => None,
+    }
+}
+
+fn insert_style<S: conrod::widget::Style + Send>(theme: &mut Theme, style: S) {
+    theme.widget_styling.insert(TypeId::of::<S>(), WidgetDefault::new(Box::new(style)));
+}
+
+/// Builds the conrod theme for a palette, including the defaults of this crate's widgets.
+pub fn theme(palette: &Palette, font_size: u32) -> Theme {
+    let mut theme = Theme::default();
+    theme.name = String::from("Vision Quest");
+    theme.background_color = palette.background;
+    theme.shape_color = palette.button;
+    theme.border_color = palette.background;
+    theme.label_color = palette.text;
+    theme.font_size_large = font_size + 8;
+    theme.font_size_medium = font_size;
+    theme.font_size_small = u32::max(font_size.saturating_sub(6), 8);
+
+    insert_style(&mut theme, widget::button::Style {
+        color: Some(palette.button),
+        label_color: Some(palette.button_text),
+        ..widget::button::Style::default()
+    });
+    insert_style(&mut theme, widget::canvas::Style {
+        color: Some(palette.background),
+        ..widget::canvas::Style::default()
+    });
+    insert_style(&mut theme, row::Style {
+        color: Some(palette.button),
+        opened_color: Some(palette.highlight),
+        label_color: Some(palette.text),
+        label_font_size: None,
+        string_color: Some(palette.string),
+        number_color: Some(palette.number),
+        bool_color: Some(palette.bool),
+        null_color: Some(palette.muted),
+        summary_color: Some(palette.muted),
+        warning_color: Some(palette.warning),
+    });
+    insert_style(&mut theme, timeline::Style {
+        color: Some(palette.panel),
+        tick_color: Some(palette.highlight),
+        burst_color: Some(palette.warning),
+        scrubber_color: Some(palette.accent),
+        label_color: Some(palette.text),
+        label_font_size: None,
+    });
+    insert_style(&mut theme, chart::Style {
+        color: Some(palette.panel),
+        line_color: Some(palette.highlight),
+        label_color: Some(palette.text),
+        label_font_size: None,
+    });
+    insert_style(&mut theme, expansion_controls::Style {
+        color: Some(palette.highlight),
+        label_font_size: None,
+    });
+    insert_style(&mut theme, divider::Style {
+        color: Some(palette.muted),
+        hover_color: Some(palette.accent),
+    });
+
+    theme
+}
+
+/// Resolves a relative font path against the working directory, then the executable's directory.
+fn resolve(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_absolute() || path.exists() {
+        return Some(path.to_path_buf());
+    }
+
+    env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(path)))
+        .filter(|path| path.exists())
+}
+
+/// Loads the font named in the config, or the embedded one.
+pub fn font(config: &Config) -> Result<text::Font, Error> {
+    let font = match config.font_path {
+        Some(ref path) => {
+            let resolved = resolve(path)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("font not found: {}", path)))?;
+            text::font::from_file(resolved)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{:?}", err)))?
+        },
+        None => {
+            text::FontCollection::from_bytes(EMBEDDED_FONT)
+                .and_then(|collection| collection.into_font())
+                .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{:?}", err)))?
+        },
+    };
+
+    Ok(font)
+}
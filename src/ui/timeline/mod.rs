@@ -0,0 +1,227 @@
+use std::f64;
+use conrod::{self, widget, Widget, Colorable, Positionable};
+
+const PADDING: f64 = 10.0;
+const TICK_AREA_HEIGHT: f64 = 30.0;
+
+#[derive(WidgetCommon)]
+pub struct Timeline<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    style: Style,
+    timestamps: &'a [u64],
+    selected: Option<usize>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    #[conrod(default = "conrod::color::DARK_CHARCOAL")]
+    pub color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::LIGHT_BLUE")]
+    pub tick_color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::ORANGE")]
+    pub burst_color: Option<conrod::Color>,
+    #[conrod(default = "conrod::color::WHITE")]
+    pub scrubber_color: Option<conrod::Color>,
+    #[conrod(default = "theme.label_color")]
+    pub label_color: Option<conrod::Color>,
+    #[conrod(default = "theme.font_size_small")]
+    pub label_font_size: Option<conrod::FontSize>,
+}
+
+widget_ids! {
+    struct Ids {
+        background,
+        scrubber,
+        summary,
+        gap,
+        ticks[],
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+impl<'a> Timeline<'a> {
+    pub fn new(timestamps: &'a [u64], selected: Option<usize>) -> Self {
+        Timeline {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            timestamps: timestamps,
+            selected: selected,
+        }
+    }
+
+    fn range(&self) -> (u64, u64) {
+        let start = self.timestamps.iter().min().cloned().unwrap_or(0);
+        let end = self.timestamps.iter().max().cloned().unwrap_or(0);
+        (start, end)
+    }
+
+    fn time_to_x(&self, time: u64, rect: &conrod::Rect) -> f64 {
+        let (start, end) = self.range();
+        let span = f64::max((end - start) as f64, 1.0);
+        let usable = f64::max(rect.w() - PADDING * 2.0, 1.0);
+
+        rect.left() + PADDING + ((time - start) as f64 / span) * usable
+    }
+
+    fn x_to_nearest_index(&self, x: f64, rect: &conrod::Rect) -> Option<usize> {
+        let (start, end) = self.range();
+        let usable = f64::max(rect.w() - PADDING * 2.0, 1.0);
+        let fraction = ((x - rect.left() - PADDING) / usable).clamp(0.0, 1.0);
+        let time = start as f64 + fraction * (end - start) as f64;
+
+        self.timestamps.iter()
+            .enumerate()
+            .min_by(|&(_, a), &(_, b)| {
+                let a = (*a as f64 - time).abs();
+                let b = (*b as f64 - time).abs();
+                a.partial_cmp(&b).unwrap()
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Counts the actions falling into each pixel column, so bursts stack into taller ticks.
+    fn columns(&self, rect: &conrod::Rect) -> Vec<(f64, usize)> {
+        let mut columns: Vec<(f64, usize)> = Vec::new();
+        let mut xs: Vec<f64> = self.timestamps.iter()
+            .map(|time| self.time_to_x(*time, rect).round())
+            .collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        for x in xs {
+            let is_same_column = columns.last().map(|&(last, _)| last == x).unwrap_or(false);
+            if is_same_column {
+                columns.last_mut().unwrap().1 += 1;
+            } else {
+                columns.push((x, 1));
+            }
+        }
+
+        columns
+    }
+
+    fn gap_to_previous(&self, index: usize) -> Option<u64> {
+        match index {
+            0 => None,
+            _ => {
+                let previous = self.timestamps[index - 1];
+                let current = self.timestamps[index];
+                Some(if current > previous { current - previous } else { 0 })
+            },
+        }
+    }
+}
+
+pub fn format_duration(milliseconds: u64) -> String {
+    if milliseconds < 1000 {
+        format!("{} ms", milliseconds)
+    } else if milliseconds < 60_000 {
+        format!("{:.2} s", milliseconds as f64 / 1000.0)
+    } else {
+        format!("{}m {}s", milliseconds / 60_000, (milliseconds % 60_000) / 1000)
+    }
+}
+
+impl<'a> Widget for Timeline<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = Option<usize>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
+
+        widget::Rectangle::fill(rect.dim())
+            .middle_of(id)
+            .color(style.color(&ui.theme))
+            .graphics_for(id)
+            .set(state.ids.background, ui)
+            ;
+
+        if self.timestamps.is_empty() {
+            return None;
+        }
+
+        let columns = self.columns(&rect);
+        if state.ids.ticks.len() != columns.len() {
+            state.update(|state| state.ids.ticks.resize(columns.len(), &mut ui.widget_id_generator()));
+        }
+
+        let tick_bottom = rect.top() - PADDING - TICK_AREA_HEIGHT;
+        for (&(x, count), &tick_id) in columns.iter().zip(state.ids.ticks.iter()) {
+            let height = f64::min(TICK_AREA_HEIGHT, 8.0 + (count as f64 - 1.0) * 4.0);
+            let color = if count > 1 { style.burst_color(&ui.theme) } else { style.tick_color(&ui.theme) };
+
+            widget::Rectangle::fill([1.5, height])
+                .x_y(x, tick_bottom + height / 2.0)
+                .color(color)
+                .parent(id)
+                .graphics_for(id)
+                .set(tick_id, ui)
+                ;
+        }
+
+        let mut selected = self.selected;
+        let input = ui.widget_input(id);
+        let pointer_xs = input.clicks().left().map(|click| click.xy[0])
+            .chain(input.drags().left().map(|drag| drag.to[0]));
+        for x in pointer_xs {
+            selected = self.x_to_nearest_index(rect.x() + x, &rect).or(selected);
+        }
+
+        let label_color = style.label_color(&ui.theme);
+        let font_size = style.label_font_size(&ui.theme);
+        let (start, end) = self.range();
+
+        widget::Text::new(&format!("{} actions over {}", self.timestamps.len(), format_duration(end - start)))
+            .bottom_left_with_margins_on(id, 4.0, PADDING)
+            .color(label_color)
+            .font_size(font_size)
+            .graphics_for(id)
+            .set(state.ids.summary, ui)
+            ;
+
+        if let Some(index) = selected {
+            let x = self.time_to_x(self.timestamps[index], &rect);
+
+            widget::Line::abs([x, rect.bottom()], [x, rect.top()])
+                .color(style.scrubber_color(&ui.theme))
+                .thickness(2.0)
+                .parent(id)
+                .graphics_for(id)
+                .set(state.ids.scrubber, ui)
+                ;
+
+            let gap = match self.gap_to_previous(index) {
+                Some(gap) => format!("#{} +{} since previous", index, format_duration(gap)),
+                None => format!("#{}", index),
+            };
+
+            widget::Text::new(&gap)
+                .bottom_right_with_margins_on(id, 4.0, PADDING)
+                .color(label_color)
+                .font_size(font_size)
+                .graphics_for(id)
+                .set(state.ids.gap, ui)
+                ;
+        }
+
+        if selected != self.selected { selected } else { None }
+    }
+}
+
+impl<'a> Colorable for Timeline<'a> {
+    builder_method!(color { style.color = Some(conrod::Color) });
+}
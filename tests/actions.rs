@@ -0,0 +1,310 @@
+extern crate vision_quest;
+#[macro_use]
+extern crate serde_json;
+
+use std::net::{TcpListener, TcpStream};
+use std::collections::HashSet;
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use vision_quest::actions::{self, Action, Message};
+use vision_quest::config::Config;
+use vision_quest::invariants::InvariantConfig;
+use vision_quest::layout::Layout;
+use vision_quest::message_parser::{parse, serialize_history_item};
+use vision_quest::state::{State, Status, HistoryAction, HistoryState, HistoryItem, Expansion, Edit};
+
+fn counter_item(amount: i64, count: i64) -> HistoryItem {
+    let action = HistoryAction::new(String::from("counter"), String::from("add"), format!("{{\"amount\":{}}}", amount));
+    let state = HistoryState::new(String::from("Counter"), format!("{{\"count\":{}}}", count));
+    let mut item = HistoryItem::new(action, state, Some(1_000), 2_000);
+    item.id = Some(format!("add-{}", amount));
+    item.caused_by = Some(String::from("click"));
+    item.duration = Some(1.5);
+    item
+}
+
+/// A state with one connected client called "app", checking that counts never go negative.
+fn state_with_client() -> (State, TcpListener) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let mut config = Config::default();
+    config.invariants = vec![InvariantConfig {
+        name: String::from("non-negative"),
+        state_type: None,
+        rule: String::from("count >= 0"),
+    }];
+
+    let (dispatcher, _) = channel();
+    let mut state = State::new(dispatcher, config, Layout::default());
+    state.add_client(String::from("app"), Arc::new(Mutex::new(stream)));
+    (state, listener)
+}
+
+fn empty_action(domain: &str, invocation: &str) -> Action {
+    Action {
+        domain: String::from(domain),
+        invocation: String::from(invocation),
+        message: Message::Empty,
+        sender: String::from("ui"),
+    }
+}
+
+fn status_name(status: &Status) -> &'static str {
+    match *status {
+        Status::Initializing => "initializing",
+        Status::Running => "running",
+        Status::Paused => "paused",
+        Status::ShuttingDown => "shutting down",
+    }
+}
+
+#[test]
+fn serialized_items_parse_back() {
+    let item = counter_item(2, 5);
+    let action = parse(serialize_history_item(&item).unwrap(), String::from("app")).unwrap();
+    assert_eq!((action.domain.as_str(), action.invocation.as_str(), action.sender.as_str()), ("item", "add", "app"));
+
+    let parsed = action.message.expect_history_item();
+    assert_eq!(parsed.action.domain, item.action.domain);
+    assert_eq!(parsed.action.invocation, item.action.invocation);
+    assert_eq!(parsed.action.amplitude, item.action.amplitude);
+    assert_eq!(parsed.state.id, item.state.id);
+    assert_eq!(parsed.state.content, item.state.content);
+    assert_eq!(parsed.sent_at, item.sent_at);
+    assert_eq!(parsed.id, item.id);
+    assert_eq!(parsed.caused_by, item.caused_by);
+    assert_eq!(parsed.duration, item.duration);
+}
+
+#[test]
+fn adding_items_checks_them_and_records_them() {
+    let (mut state, _listener) = state_with_client();
+
+    for item in vec![counter_item(2, 2), counter_item(-3, -1)] {
+        let action = parse(serialize_history_item(&item).unwrap(), String::from("app")).unwrap();
+        state = actions::run(action, state).unwrap();
+    }
+
+    let history = &state.clients["app"].history;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].action.amplitude, "{\"amount\":-3}");
+    assert!(history[0].violations.is_empty());
+    assert_eq!(history[1].violations.len(), 1);
+    assert_eq!(state.violating_amount(), 1);
+    assert!(state.unsaved);
+}
+
+#[test]
+fn states_are_compared_by_value() {
+    let (mut state, _listener) = state_with_client();
+
+    for content in vec!["{\"count\":1,\"step\":2}", "{ \"step\": 2, \"count\": 1 }", "{\"count\":1.5,\"step\":2}"] {
+        let action = HistoryAction::new(String::from("counter"), String::from("add"), String::from("null"));
+        let item = HistoryItem::new(action, HistoryState::new(String::from("Counter"), String::from(content)), None, 0);
+        let action = parse(serialize_history_item(&item).unwrap(), String::from("app")).unwrap();
+        state = actions::run(action, state).unwrap();
+    }
+
+    let unchanged: Vec<bool> = state.clients["app"].history.iter().map(|item| item.unchanged).collect();
+    assert_eq!(unchanged, vec![false, true, false]);
+    assert_eq!(state.redundant_amounts(), (1, 2));
+}
+
+#[test]
+fn links_causes_and_effects_across_clients() {
+    let (mut state, listener) = state_with_client();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    state.add_client(String::from("api"), Arc::new(Mutex::new(stream)));
+
+    let reports = vec![
+        ("app", "click", Some("c1"), None),
+        ("api", "fetch", Some("f1"), Some("c1")),
+        ("app", "render", None, Some("f1")),
+        ("api", "cache", None, Some("f1")),
+        ("app", "scroll", None, None),
+    ];
+    for (client, invocation, id, cause) in reports {
+        let action = HistoryAction::new(String::from("ui"), String::from(invocation), String::from("null"));
+        let mut item = HistoryItem::new(action, HistoryState::new(String::from("Ui"), String::from("{}")), None, 0);
+        item.id = id.map(String::from);
+        item.caused_by = cause.map(String::from);
+        state.add_history_item(String::from(client), item);
+    }
+
+    let mut relatives: Vec<(String, usize)> = state.causal_relatives(&(String::from("app"), 0)).into_iter().collect();
+    relatives.sort();
+    assert_eq!(relatives, vec![(String::from("api"), 0), (String::from("api"), 1), (String::from("app"), 1)]);
+    assert_eq!(state.causal_relatives(&(String::from("app"), 2)).len(), 0);
+    assert_eq!(state.clients["app"].causal_depths(), &[0, 1, 0]);
+    assert_eq!(state.clients["api"].causal_depths(), &[1, 2]);
+}
+
+#[test]
+fn renames_through_a_draft_that_can_be_emptied() {
+    let (mut state, _listener) = state_with_client();
+    let tab_action = |invocation: &str, message: Message| Action {
+        domain: String::from("tab"),
+        invocation: String::from(invocation),
+        message: message,
+        sender: String::from("ui"),
+    };
+
+    state = actions::run(tab_action("start rename", Message::ClientId(String::from("app"))), state).unwrap();
+    assert_eq!(state.clients["app"].rename_draft, Some(String::from("app")));
+    state = actions::run(tab_action("rename", Message::Rename((String::from("app"), String::from("shop")))), state).unwrap();
+    assert_eq!(state.clients["app"].display_name(), "app");
+    state = actions::run(tab_action("finish rename", Message::ClientId(String::from("app"))), state).unwrap();
+    assert_eq!(state.clients["app"].display_name(), "shop");
+
+    state = actions::run(tab_action("start rename", Message::ClientId(String::from("app"))), state).unwrap();
+    state = actions::run(tab_action("rename", Message::Rename((String::from("app"), String::new()))), state).unwrap();
+    assert_eq!(state.clients["app"].rename_draft, Some(String::new()));
+    state = actions::run(tab_action("finish rename", Message::ClientId(String::from("app"))), state).unwrap();
+    assert_eq!((state.clients["app"].name.clone(), state.clients["app"].rename_draft.clone()), (None, None));
+}
+
+#[test]
+fn expands_the_amplitude_of_the_selected_item() {
+    let (mut state, _listener) = state_with_client();
+    let action = HistoryAction::new(String::from("search"), String::from("filter"), String::from("{\"filter\":{\"tags\":[\"a\"]}}"));
+    state.add_history_item(String::from("app"), HistoryItem::new(action, HistoryState::new(String::from("Search"), String::from("{}")), None, 0));
+    state.clients.get_mut("app").unwrap().update_selections(vec![0].into_iter().collect());
+    let expand = |expansion: Expansion| Action {
+        domain: String::from("inspector"),
+        invocation: String::from("expand"),
+        message: Message::Expansion((String::from("app"), String::from("amplitude"), expansion)),
+        sender: String::from("ui"),
+    };
+
+    state = actions::run(expand(Expansion::Depth(2)), state).unwrap();
+    let expected: HashSet<String> = vec![String::new(), String::from("/filter")].into_iter().collect();
+    assert_eq!(state.clients["app"].expanded_paths("amplitude"), Some(&expected));
+
+    state = actions::run(expand(Expansion::All), state).unwrap();
+    assert_eq!(state.clients["app"].expanded_paths("amplitude").map(|paths| paths.len()), Some(3));
+    assert_eq!(state.clients["app"].expanded_paths("state"), None);
+
+    state = actions::run(expand(Expansion::Nothing), state).unwrap();
+    assert_eq!(state.clients["app"].expanded_paths("amplitude").map(|paths| paths.len()), Some(0));
+}
+
+#[test]
+fn saving_and_quitting_keeps_running_when_the_save_fails() {
+    let (mut state, _listener) = state_with_client();
+    state.add_history_item(String::from("app"), counter_item(1, 1));
+    state.config.session_path = String::from("/nonexistent/session.jsonl");
+    state = actions::run(empty_action("application", "request quit"), state).unwrap();
+    assert!(state.confirming_quit);
+
+    state = actions::run(empty_action("session", "save and quit"), state).unwrap();
+    assert!(state.unsaved && state.confirming_quit);
+    assert!(state.last_error.as_ref().map_or(false, |error| error.starts_with("failed to save the session")));
+    assert_eq!(status_name(&state.status), "initializing");
+
+    let path = std::env::temp_dir().join(format!("vision_quest_save_and_quit_{}.jsonl", std::process::id()));
+    state.config.session_path = path.to_string_lossy().into_owned();
+    state = actions::run(empty_action("session", "save and quit"), state).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(!state.unsaved);
+    assert_eq!(status_name(&state.status), "shutting down");
+}
+
+#[test]
+fn pausing_does_not_undo_quitting() {
+    let (mut state, _listener) = state_with_client();
+    state.status = Status::Running;
+    state = actions::run(empty_action("application", "toggle pause"), state).unwrap();
+    assert_eq!(status_name(&state.status), "paused");
+    state = actions::run(empty_action("application", "toggle pause"), state).unwrap();
+    assert_eq!(status_name(&state.status), "running");
+
+    state = actions::run(empty_action("application", "quit"), state).unwrap();
+    state = actions::run(empty_action("application", "toggle pause"), state).unwrap();
+    assert_eq!(status_name(&state.status), "shutting down");
+}
+
+#[test]
+fn merges_clients_by_timestamp_and_leaves_out_excluded_ones() {
+    let (mut state, listener) = state_with_client();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    state.add_client(String::from("api"), Arc::new(Mutex::new(stream)));
+
+    for &(client, timestamp) in [("app", 10), ("api", 20), ("app", 30), ("api", 30), ("app", 30), ("api", 5)].iter() {
+        let action = HistoryAction::new(String::from("ui"), timestamp.to_string(), String::from("null"));
+        let item = HistoryItem::new(action, HistoryState::new(String::from("Ui"), String::from("{}")), Some(timestamp), 0);
+        state.add_history_item(String::from(client), item);
+    }
+
+    let row = |client: &str, index: usize| (String::from(client), index);
+    assert_eq!(state.merged_rows(), vec![row("api", 2), row("app", 0), row("api", 0), row("app", 1), row("app", 2), row("api", 1)]);
+    assert_eq!(state.merged_item(&row("api", 2)).map(|item| item.timestamp()), Some(5));
+    assert!(state.merged_item(&row("api", 3)).is_none());
+    assert!(state.merged_item(&row("web", 0)).is_none());
+
+    let toggle_api = || Action {
+        domain: String::from("merged"),
+        invocation: String::from("toggle client"),
+        message: Message::ClientId(String::from("api")),
+        sender: String::from("ui"),
+    };
+    state = actions::run(toggle_api(), state).unwrap();
+    assert_eq!(state.merged_rows(), vec![row("app", 0), row("app", 1), row("app", 2)]);
+
+    state = actions::run(toggle_api(), state).unwrap();
+    assert_eq!(state.merged_rows().len(), 6);
+}
+
+#[test]
+fn closing_a_tab_keeps_the_order_of_the_others() {
+    let (mut state, listener) = state_with_client();
+    for client in vec!["api", "web"] {
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        state.add_client(String::from(client), Arc::new(Mutex::new(stream)));
+    }
+    let tab_action = |invocation: &str, client: &str| Action {
+        domain: String::from("tab"),
+        invocation: String::from(invocation),
+        message: Message::ClientId(String::from(client)),
+        sender: String::from("ui"),
+    };
+
+    state = actions::run(tab_action("toggle pin", "web"), state).unwrap();
+    assert_eq!(state.client_ids(), vec!["web", "app", "api"]);
+    state = actions::run(tab_action("select", "api"), state).unwrap();
+
+    state = actions::run(tab_action("close", "app"), state).unwrap();
+    assert_eq!(state.client_ids(), vec!["web", "api"]);
+    assert_eq!(state.active_client_id(), Some(String::from("api")));
+
+    state = actions::run(tab_action("close", "api"), state).unwrap();
+    assert_eq!(state.active_client_id(), Some(String::from("web")));
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    state.add_client(String::from("app"), Arc::new(Mutex::new(stream)));
+    assert_eq!(state.client_ids(), vec!["web", "app"]);
+}
+
+#[test]
+fn editing_a_state_pushes_the_edited_copy() {
+    let (mut state, _listener) = state_with_client();
+    state.add_history_item(String::from("app"), counter_item(1, 1));
+    let edit = |edit: Edit| Action {
+        domain: String::from("state"),
+        invocation: String::from("edit"),
+        message: Message::EditState((String::from("app"), 0, edit)),
+        sender: String::from("ui"),
+    };
+
+    state = actions::run(edit(Edit::Set(String::from("/count"), json!(-4))), state).unwrap();
+    {
+        let client = &state.clients["app"];
+        assert_eq!(client.history.len(), 2);
+        assert!(client.history[1].synthetic);
+        assert_eq!(client.history[1].state.content, "{\"count\":-4}");
+        assert_eq!(client.history[1].violations.len(), 1);
+        assert_eq!(client.selected_history_index(), Some(1));
+    }
+
+    state = actions::run(edit(Edit::AddChild(String::from(""), Some(String::from("count")))), state).unwrap();
+    assert_eq!(state.clients["app"].history.len(), 2);
+    assert!(state.last_error.as_ref().map_or(false, |error| error.contains("the new key is taken")));
+}
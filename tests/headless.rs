@@ -0,0 +1,73 @@
+extern crate vision_quest;
+#[macro_use]
+extern crate serde_json;
+
+use std::sync::mpsc::channel;
+use vision_quest::cli::Options;
+use vision_quest::collector::Update;
+use vision_quest::config::Config;
+use vision_quest::headless;
+use vision_quest::invariants::InvariantConfig;
+use vision_quest::layout::Layout;
+use vision_quest::state::{State, Status};
+
+fn broken_invariant(name: &str) -> InvariantConfig {
+    InvariantConfig {
+        name: String::from(name),
+        state_type: None,
+        rule: String::from("count >="),
+    }
+}
+
+/// Records the single update the state would send on shutdown, and returns the exit code.
+fn record(state: &State) -> i32 {
+    let (tx, rx) = channel();
+    tx.send(Update {
+        items: Vec::new(),
+        last_received: None,
+        status: Status::ShuttingDown,
+        last_error: state.last_error.clone(),
+        config_errors: state.config_errors.clone(),
+        listen_addresses: Vec::new(),
+        dispatcher: state.dispatcher.clone(),
+    }).unwrap();
+
+    headless::record(rx, Options::default(), 0)
+}
+
+#[test]
+fn reports_every_invariant_that_fails_to_parse() {
+    let mut config = Config::default();
+    config.invariants = vec![broken_invariant("first"), broken_invariant("second")];
+    let (dispatcher, _) = channel();
+    let state = State::new(dispatcher, config, Layout::default());
+
+    assert_eq!(state.config_errors.len(), 2);
+    assert!(state.config_errors[0].starts_with("invariant first: "));
+    assert!(state.config_errors[1].starts_with("invariant second: "));
+    assert!(state.last_error.as_ref().map_or(false, |error| error.contains("first") && error.contains("second")));
+    assert_eq!(record(&state), 1);
+}
+
+#[test]
+fn passes_with_a_valid_config() {
+    let (dispatcher, _) = channel();
+    let state = State::new(dispatcher, Config::default(), Layout::default());
+
+    assert!(state.config_errors.is_empty());
+    assert_eq!(record(&state), 0);
+}
+
+#[test]
+fn reports_every_schema_that_cannot_be_checked() {
+    let mut config = Config::default();
+    config.state_schemas.insert(String::from("Missing"), json!("/nonexistent/missing.schema.json"));
+    config.amplitude_schemas.insert(String::from("todo:add"), json!({"properties": {"name": {"pattern": "^a"}}}));
+    let (dispatcher, _) = channel();
+    let state = State::new(dispatcher, config, Layout::default());
+
+    assert_eq!(state.config_errors.len(), 2);
+    assert!(state.config_errors[0].starts_with("schema for Missing: "));
+    assert!(state.config_errors[1].starts_with("schema for todo:add: pattern"));
+    assert_eq!(record(&state), 1);
+}